@@ -0,0 +1,588 @@
+// Adapter Registry - the built-in CLI adapters merged with user definition files
+// Files in the app config dir add adapters or override built-in fields; a project's .aitm/adapters can only add
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::cli_adapter::{builtin_adapters, parse_version_req, CLIAdapter};
+use crate::config_format::ConfigFormat;
+use crate::config_location::{self, deserialize_paths, ConfigPaths};
+use crate::config_schema;
+use crate::health_checks::{CheckKind, HealthCheck};
+use crate::limits::ResourceLimits;
+use crate::prompt_runner::Invocation;
+use crate::spawn_env::{EnvMappings, EnvSource};
+use crate::tool_events::OutputFormat;
+
+/// Directory under the app config dir holding user adapter files
+pub const ADAPTER_DIR: &str = "adapters";
+
+/// Directory under a project root holding project adapter files
+pub const PROJECT_ADAPTER_DIR: &str = ".aitm/adapters";
+
+/// Platforms a config path can be given for
+const PLATFORMS: &[&str] = &["windows", "macos", "linux"];
+
+/// Install methods a package name can be given for
+const INSTALL_METHODS: &[&str] = &["npm", "brew", "pipx", "cargo"];
+
+/// One adapter as written in a TOML or JSON file
+///
+/// Every field but `id` is optional so a file can override a few fields of a
+/// built-in adapter. `config_paths`, `env` and `packages` are merged key by
+/// key; `broken_versions` and `health_checks` replace the built-in lists.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdapterDefinition {
+    id: String,
+    name: Option<String>,
+    executable: Option<String>,
+    detect_command: Option<String>,
+    version_command: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_paths")]
+    config_paths: ConfigPaths,
+    #[serde(default)]
+    env: EnvMappings,
+    limits: Option<ResourceLimits>,
+    supported_versions: Option<String>,
+    broken_versions: Option<Vec<String>>,
+    #[serde(default)]
+    packages: HashMap<String, String>,
+    output_format: Option<OutputFormat>,
+    invocation: Option<Invocation>,
+    health_checks: Option<Vec<HealthCheck>>,
+    /// A JSON Schema, or the path of one relative to the definition file
+    config_schema: Option<Value>,
+    config_format: Option<ConfigFormat>,
+    backup_retention: Option<usize>,
+}
+
+impl AdapterDefinition {
+    /// Apply the definition on top of `base`, or create a new adapter without one
+    fn apply(self, base: Option<CLIAdapter>, source: &Path) -> Result<CLIAdapter, Vec<String>> {
+        let config_schema = match self.config_schema {
+            Some(Value::String(file)) => Some(read_schema(source, &file, &self.id)?),
+            schema => schema,
+        };
+        let adapter = match base {
+            Some(mut adapter) => {
+                if let Some(name) = self.name {
+                    adapter.name = name;
+                }
+                if let Some(executable) = self.executable {
+                    adapter.executable = executable;
+                }
+                if let Some(detect_command) = self.detect_command {
+                    adapter.detect_command = detect_command;
+                }
+                if let Some(version_command) = self.version_command {
+                    adapter.version_command = version_command;
+                }
+                adapter.config_paths.extend(self.config_paths);
+                adapter.env.extend(self.env);
+                if self.limits.is_some() {
+                    adapter.limits = self.limits;
+                }
+                if self.supported_versions.is_some() {
+                    adapter.supported_versions = self.supported_versions;
+                }
+                if let Some(broken_versions) = self.broken_versions {
+                    adapter.broken_versions = broken_versions;
+                }
+                adapter.packages.extend(self.packages);
+                if self.output_format.is_some() {
+                    adapter.output_format = self.output_format;
+                }
+                if self.invocation.is_some() {
+                    adapter.invocation = self.invocation;
+                }
+                if let Some(health_checks) = self.health_checks {
+                    adapter.health_checks = health_checks;
+                }
+                if config_schema.is_some() {
+                    adapter.config_schema = config_schema;
+                }
+                if self.config_format.is_some() {
+                    adapter.config_format = self.config_format;
+                }
+                if self.backup_retention.is_some() {
+                    adapter.backup_retention = self.backup_retention;
+                }
+                adapter.source = Some(source.display().to_string());
+                adapter
+            }
+            None => {
+                let mut errors = Vec::new();
+                if self.name.is_none() {
+                    errors.push(format!("adapter '{}': missing field 'name'", self.id));
+                }
+                let Some(executable) = self.executable else {
+                    errors.push(format!("adapter '{}': missing field 'executable'", self.id));
+                    return Err(errors);
+                };
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                let default_command = format!("{} --version", executable);
+                CLIAdapter {
+                    id: self.id,
+                    name: self.name.unwrap_or_default(),
+                    detect_command: self.detect_command.unwrap_or_else(|| default_command.clone()),
+                    version_command: self.version_command.unwrap_or(default_command),
+                    executable,
+                    config_paths: self.config_paths,
+                    env: self.env,
+                    limits: self.limits,
+                    supported_versions: self.supported_versions,
+                    broken_versions: self.broken_versions.unwrap_or_default(),
+                    packages: self.packages,
+                    output_format: self.output_format,
+                    invocation: self.invocation,
+                    health_checks: self.health_checks.unwrap_or_default(),
+                    config_schema,
+                    config_format: self.config_format,
+                    backup_retention: self.backup_retention,
+                    source: Some(source.display().to_string()),
+                }
+            }
+        };
+
+        let errors = validate(&adapter);
+        if errors.is_empty() {
+            Ok(adapter)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Load a schema file named by a definition, relative to the definition's directory
+fn read_schema(source: &Path, file: &str, id: &str) -> Result<Value, Vec<String>> {
+    let path = source.parent().unwrap_or(Path::new("")).join(file);
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| vec![format!("adapter '{}': cannot read config_schema {}: {}", id, path.display(), e)])?;
+    serde_json::from_str(&contents)
+        .map_err(|e| vec![format!("adapter '{}': config_schema {} is not JSON: {}", id, path.display(), e)])
+}
+
+/// Problems with a complete adapter, empty if it is usable
+fn validate(adapter: &CLIAdapter) -> Vec<String> {
+    let mut errors = Vec::new();
+    let valid_id = adapter.id.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && adapter
+            .id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.'));
+    if !valid_id {
+        errors.push(format!(
+            "adapter '{}': id must be lowercase letters, digits, '-', '_' or '.'",
+            adapter.id
+        ));
+    }
+    for (field, value) in [
+        ("name", &adapter.name),
+        ("executable", &adapter.executable),
+        ("detect_command", &adapter.detect_command),
+        ("version_command", &adapter.version_command),
+    ] {
+        if value.trim().is_empty() {
+            errors.push(format!("adapter '{}': '{}' must not be empty", adapter.id, field));
+        }
+    }
+    for (platform, candidates) in &adapter.config_paths {
+        if !PLATFORMS.contains(&platform.as_str()) {
+            errors.push(format!(
+                "adapter '{}': unknown platform '{}' in config_paths (expected one of {})",
+                adapter.id,
+                platform,
+                PLATFORMS.join(", ")
+            ));
+        }
+        if candidates.is_empty() {
+            errors.push(format!("adapter '{}': config_paths.{} lists no paths", adapter.id, platform));
+        }
+        // Only syntax can be checked here; variables are looked up when the path is used
+        for candidate in candidates {
+            if let Err(e) = config_location::expand_with(candidate, &|_| Some(String::new())) {
+                errors.push(format!("adapter '{}': config path '{}': {}", adapter.id, candidate, e));
+            }
+        }
+    }
+    for method in adapter.packages.keys() {
+        if !INSTALL_METHODS.contains(&method.as_str()) {
+            errors.push(format!(
+                "adapter '{}': unknown install method '{}' in packages (expected one of {})",
+                adapter.id,
+                method,
+                INSTALL_METHODS.join(", ")
+            ));
+        }
+    }
+    let constraints = adapter.supported_versions.iter().chain(&adapter.broken_versions);
+    for constraint in constraints {
+        if let Err(e) = parse_version_req(constraint) {
+            errors.push(format!("adapter '{}': {}", adapter.id, e));
+        }
+    }
+    for check in &adapter.health_checks {
+        if let CheckKind::Command { command, .. } = &check.kind {
+            if command.trim().is_empty() {
+                errors.push(format!("adapter '{}': health check command must not be empty", adapter.id));
+            }
+        }
+    }
+    if let Some(problem) = adapter.config_schema.as_ref().and_then(config_schema::schema_problem) {
+        errors.push(format!("adapter '{}': invalid config_schema: {}", adapter.id, problem));
+    }
+    if let Some(invocation) = &adapter.invocation {
+        for problem in invocation.problems() {
+            errors.push(format!("adapter '{}': invocation {}", adapter.id, problem));
+        }
+    }
+    errors
+}
+
+/// A definition file that could not be used
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdapterFileError {
+    pub path: String,
+    pub errors: Vec<String>,
+}
+
+/// Result of re-reading the adapter files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdapterReload {
+    pub adapters: Vec<CLIAdapter>,
+    /// Files that were skipped; the rest still load
+    pub errors: Vec<AdapterFileError>,
+}
+
+/// Who wrote a definition file, which decides what it may do
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Origin {
+    /// The user's own adapter dir; files may add or override any adapter
+    User,
+    /// A project checkout, possibly someone else's; files may only add new
+    /// adapters and can't read secrets
+    Project,
+}
+
+/// Where adapter files are read from
+#[derive(Debug, Default)]
+struct AdapterDirs {
+    config_dir: Option<PathBuf>,
+    project_dir: Option<PathBuf>,
+}
+
+impl AdapterDirs {
+    /// Directories in the order they are applied; later ones win
+    fn search_path(&self) -> Vec<(PathBuf, Origin)> {
+        let user = self.config_dir.as_ref().map(|dir| (dir.join(ADAPTER_DIR), Origin::User));
+        let project = self
+            .project_dir
+            .as_ref()
+            .map(|dir| (dir.join(PROJECT_ADAPTER_DIR), Origin::Project));
+        user.into_iter().chain(project).collect()
+    }
+}
+
+fn adapter_dirs() -> &'static Mutex<AdapterDirs> {
+    static DIRS: OnceLock<Mutex<AdapterDirs>> = OnceLock::new();
+    DIRS.get_or_init(|| Mutex::new(AdapterDirs::default()))
+}
+
+/// Adapters from the last load; `None` until the registry is initialized
+fn loaded_adapters() -> &'static Mutex<Option<Vec<CLIAdapter>>> {
+    static ADAPTERS: OnceLock<Mutex<Option<Vec<CLIAdapter>>>> = OnceLock::new();
+    ADAPTERS.get_or_init(|| Mutex::new(None))
+}
+
+/// Load the user's adapter files from `config_dir` at startup
+pub fn init(config_dir: PathBuf) {
+    if let Ok(mut dirs) = adapter_dirs().lock() {
+        dirs.config_dir = Some(config_dir);
+    }
+    for error in reload().errors {
+        eprintln!("Warning: skipping adapter file {}: {}", error.path, error.errors.join("; "));
+    }
+}
+
+/// Every adapter currently available, built-in or from a file
+pub fn adapters() -> Vec<CLIAdapter> {
+    loaded_adapters()
+        .lock()
+        .ok()
+        .and_then(|adapters| adapters.clone())
+        .unwrap_or_else(builtin_adapters)
+}
+
+/// Re-read the adapter files and replace the loaded adapters
+fn reload() -> AdapterReload {
+    let search_path = adapter_dirs()
+        .lock()
+        .map(|dirs| dirs.search_path())
+        .unwrap_or_default();
+    let (adapters, errors) = load_from(&search_path);
+    if let Ok(mut loaded) = loaded_adapters().lock() {
+        *loaded = Some(adapters.clone());
+    }
+    AdapterReload { adapters, errors }
+}
+
+/// Merge the definition files in `dirs` over the built-in adapters
+///
+/// Files are applied directory by directory, alphabetically within each; a
+/// file that fails to parse or validate, or does more than its origin
+/// allows, is reported and skipped.
+fn load_from(dirs: &[(PathBuf, Origin)]) -> (Vec<CLIAdapter>, Vec<AdapterFileError>) {
+    let mut adapters = builtin_adapters();
+    let mut errors = Vec::new();
+
+    let files = dirs
+        .iter()
+        .flat_map(|(dir, origin)| definition_files(dir).into_iter().map(|path| (path, *origin)));
+    for (path, origin) in files {
+        let result = read_definition(&path).and_then(|definition| {
+            let index = adapters.iter().position(|a| a.id == definition.id);
+            if origin == Origin::Project {
+                check_project_definition(&definition, index.is_some())?;
+            }
+            let base = index.map(|i| adapters[i].clone());
+            let adapter = definition.apply(base, &path)?;
+            match index {
+                Some(i) => adapters[i] = adapter,
+                None => adapters.push(adapter),
+            }
+            Ok(())
+        });
+        if let Err(file_errors) = result {
+            errors.push(AdapterFileError {
+                path: path.display().to_string(),
+                errors: file_errors,
+            });
+        }
+    }
+
+    (adapters, errors)
+}
+
+/// Refuse what a project file must not do: change an adapter that already
+/// exists, whose executable and commands the user trusts, or put a stored
+/// secret into a command the project chose
+fn check_project_definition(definition: &AdapterDefinition, exists: bool) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    if exists {
+        errors.push(format!(
+            "adapter '{}': project files can only add new adapters, not change existing ones",
+            definition.id
+        ));
+    }
+    let mut secret_vars: Vec<&String> = definition
+        .env
+        .iter()
+        .filter(|(_, source)| matches!(source, EnvSource::Secret { .. }))
+        .map(|(name, _)| name)
+        .collect();
+    secret_vars.sort();
+    for name in secret_vars {
+        errors.push(format!(
+            "adapter '{}': project files cannot read secrets (env.{})",
+            definition.id, name
+        ));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// TOML and JSON files directly inside `dir`, sorted by name
+///
+/// `*.schema.json` files are config schemas that definitions refer to.
+fn definition_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("toml" | "json")))
+        .filter(|path| !path.to_string_lossy().ends_with(".schema.json"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn read_definition(path: &Path) -> Result<AdapterDefinition, Vec<String>> {
+    let contents = fs::read_to_string(path).map_err(|e| vec![e.to_string()])?;
+    let parsed = if path.extension().is_some_and(|e| e == "json") {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&contents).map_err(|e| e.to_string())
+    };
+    parsed.map_err(|e| vec![e])
+}
+
+/// Re-read adapter files without restarting the app
+///
+/// `project_dir` selects the project whose `.aitm/adapters` are included;
+/// without one only the built-ins and the user's files are loaded. Project
+/// files can only add adapters, and none that read secrets.
+#[tauri::command]
+pub fn reload_adapters(project_dir: Option<String>) -> Result<AdapterReload, String> {
+    adapter_dirs()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .project_dir = project_dir.map(PathBuf::from);
+    Ok(reload())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, contents: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join(name), contents).unwrap();
+    }
+
+    #[test]
+    fn test_builtins_without_files() {
+        let (adapters, errors) = load_from(&[(PathBuf::from("/nonexistent/adapters"), Origin::User)]);
+        assert!(errors.is_empty());
+        assert_eq!(adapters.len(), builtin_adapters().len());
+        assert!(adapters.iter().all(|a| a.source.is_none()));
+    }
+
+    #[test]
+    fn test_new_adapter_and_override() {
+        let temp = TempDir::new().unwrap();
+        let user = temp.path().join("user");
+        let project = temp.path().join("project");
+        write(
+            &user,
+            "aider.toml",
+            r#"
+id = "aider"
+name = "Aider"
+executable = "aider"
+
+[config_paths]
+linux = "~/.aider.conf.yml"
+macos = ["${AIDER_HOME}/conf.yml", "~/.aider.conf.yml"]
+
+[env.OPENAI_API_KEY]
+source = "secret"
+key = "openai_api_key"
+optional = true
+"#,
+        );
+        write(&user, "codex.json", r#"{"id":"codex","executable":"/opt/codex/bin/codex"}"#);
+        write(&user, "local-aider.toml", "id = \"aider\"\nexecutable = \"/opt/aider/bin/aider\"\n");
+        write(&project, "wrapper.toml", "id = \"team-wrapper\"\nname = \"Team Wrapper\"\nexecutable = \"./bin/tw\"\n");
+        write(&project, "notes.txt", "not an adapter");
+
+        let (adapters, errors) = load_from(&[(user.clone(), Origin::User), (project.clone(), Origin::Project)]);
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let aider = adapters.iter().find(|a| a.id == "aider").unwrap();
+        assert_eq!(aider.name, "Aider");
+        assert_eq!(aider.executable, "/opt/aider/bin/aider");
+        assert_eq!(aider.version_command, "aider --version");
+        assert_eq!(aider.config_paths["linux"], vec!["~/.aider.conf.yml"]);
+        assert_eq!(aider.config_paths["macos"], vec!["${AIDER_HOME}/conf.yml", "~/.aider.conf.yml"]);
+        assert!(matches!(aider.env["OPENAI_API_KEY"], EnvSource::Secret { optional: true, .. }));
+        assert_eq!(aider.source.as_deref(), Some(user.join("local-aider.toml").to_str().unwrap()));
+
+        let wrapper = adapters.iter().find(|a| a.id == "team-wrapper").unwrap();
+        assert_eq!(wrapper.source.as_deref(), Some(project.join("wrapper.toml").to_str().unwrap()));
+
+        let codex = adapters.iter().find(|a| a.id == "codex").unwrap();
+        assert_eq!(codex.executable, "/opt/codex/bin/codex");
+        assert_eq!(codex.name, "OpenAI Codex CLI");
+        assert!(codex.env.contains_key("OPENAI_API_KEY"));
+    }
+
+    #[test]
+    fn test_invalid_files_are_skipped() {
+        let temp = TempDir::new().unwrap();
+        write(temp.path(), "a.toml", "id = \"gemini\"\nname = \"Gemini CLI\"\n");
+        write(temp.path(), "b.toml", "id = \"Bad Id\"\nname = \"x\"\nexecutable = \"x\"\n");
+        write(temp.path(), "c.toml", "id = \"typo\"\nname = \"x\"\nexecutabel = \"x\"\n");
+        write(temp.path(), "d.json", "{ not json");
+        write(
+            temp.path(),
+            "e.toml",
+            "id = \"gemini-cli\"\nname = \"Gemini CLI\"\nexecutable = \"gemini\"\n[config_paths]\nlinx = \"~/.gemini\"\n",
+        );
+        write(temp.path(), "f.toml", "id = \"ok\"\nname = \"OK\"\nexecutable = \"ok\"\n");
+        write(temp.path(), "g.toml", "id = \"codex\"\nsupported_versions = \">=1.x.y\"\n");
+        write(temp.path(), "h.toml", "id = \"codex\"\n[config_paths]\nlinux = [\"${CODEX_HOME/config.toml\"]\n");
+
+        let (adapters, errors) = load_from(&[(temp.path().to_path_buf(), Origin::User)]);
+        assert_eq!(errors.len(), 7, "{:?}", errors);
+        assert!(errors[0].errors[0].contains("missing field 'executable'"));
+        assert!(errors[1].errors[0].contains("id must be"));
+        assert!(errors[2].errors[0].contains("executabel"));
+        assert!(errors[4].errors[0].contains("unknown platform 'linx'"));
+        assert!(errors[5].errors[0].contains("invalid version constraint"));
+        assert!(errors[6].errors[0].contains("Unclosed ${"), "{:?}", errors[6]);
+        assert!(adapters.iter().any(|a| a.id == "ok"));
+        assert!(!adapters.iter().any(|a| a.id == "gemini-cli"));
+    }
+
+    #[test]
+    fn test_project_files_only_add_adapters() {
+        let temp = TempDir::new().unwrap();
+        let user = temp.path().join("user");
+        let project = temp.path().join("project");
+        write(&user, "mine.toml", "id = \"mine\"\nname = \"Mine\"\nexecutable = \"mine\"\n");
+        write(&project, "a.toml", "id = \"codex\"\nexecutable = \"./evil\"\n");
+        write(&project, "b.toml", "id = \"mine\"\ndetect_command = \"./evil\"\n");
+        write(
+            &project,
+            "c.toml",
+            "id = \"leaky\"\nname = \"Leaky\"\nexecutable = \"leaky\"\n[env.TOKEN]\nsource = \"secret\"\nkey = \"openai_api_key\"\n",
+        );
+        write(
+            &project,
+            "d.toml",
+            "id = \"plain\"\nname = \"Plain\"\nexecutable = \"plain\"\n[env.MODE]\nsource = \"literal\"\nvalue = \"ci\"\n",
+        );
+
+        let (adapters, errors) = load_from(&[(user, Origin::User), (project, Origin::Project)]);
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].errors[0].contains("can only add new adapters"));
+        assert!(errors[1].errors[0].contains("can only add new adapters"));
+        assert!(errors[2].errors[0].contains("cannot read secrets (env.TOKEN)"));
+
+        let codex = adapters.iter().find(|a| a.id == "codex").unwrap();
+        assert_eq!(codex.executable, "codex");
+        assert_eq!(adapters.iter().find(|a| a.id == "mine").unwrap().detect_command, "mine --version");
+        assert!(!adapters.iter().any(|a| a.id == "leaky"));
+        assert!(adapters.iter().any(|a| a.id == "plain"));
+    }
+
+    #[test]
+    fn test_reload_with_project() {
+        let temp = TempDir::new().unwrap();
+        write(
+            &temp.path().join(PROJECT_ADAPTER_DIR),
+            "wrapper.toml",
+            "id = \"team-wrapper\"\nname = \"Team Wrapper\"\nexecutable = \"tw\"\n",
+        );
+
+        let reload = reload_adapters(Some(temp.path().display().to_string())).unwrap();
+        assert!(reload.adapters.iter().any(|a| a.id == "team-wrapper"));
+        assert!(adapters().iter().any(|a| a.id == "team-wrapper"));
+
+        let reload = reload_adapters(None).unwrap();
+        assert!(!reload.adapters.iter().any(|a| a.id == "team-wrapper"));
+    }
+}
@@ -1,5 +1,18 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use semver::{Op, Version, VersionReq};
+use std::path::{Path, PathBuf};
+
+use crate::adapter_registry;
+use crate::config_format::ConfigFormat;
+use crate::config_location::{deserialize_paths, ConfigPaths};
+use crate::config_schema;
+use crate::health_checks::{self, CheckKind, CheckResult, HealthCheck};
+use crate::limits::ResourceLimits;
+use crate::prompt_runner::Invocation;
+use crate::spawn_env::{EnvMappings, EnvSource};
+use crate::tool_detection::{self, Installation};
+use crate::tool_events::OutputFormat;
 
 /// CLI Adapter definition for AI tools
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,7 +22,45 @@ pub struct CLIAdapter {
     pub executable: String,
     pub detect_command: String,
     pub version_command: String,
-    pub config_paths: HashMap<String, String>,
+    /// Candidate config files by platform, in priority order; `~` and variables are expanded
+    #[serde(deserialize_with = "deserialize_paths")]
+    pub config_paths: ConfigPaths,
+    /// Environment injected when the tool is spawned
+    #[serde(default)]
+    pub env: EnvMappings,
+    /// Default resource limits for the tool's processes
+    #[serde(default)]
+    pub limits: Option<ResourceLimits>,
+    /// Semver range of tool versions the adapter works with, e.g. ">=1.0.30, <2"
+    #[serde(default)]
+    pub supported_versions: Option<String>,
+    /// Versions or ranges known not to work with the adapter
+    #[serde(default)]
+    pub broken_versions: Vec<String>,
+    /// Package name by install method ("npm", "brew", "pipx", "cargo"), for upgrade hints
+    #[serde(default)]
+    pub packages: HashMap<String, String>,
+    /// JSON output mode the tool can be switched into, parsed into tool events
+    #[serde(default)]
+    pub output_format: Option<OutputFormat>,
+    /// How to run a single prompt without a terminal
+    #[serde(default)]
+    pub invocation: Option<Invocation>,
+    /// Checks run after the installation and version checks
+    #[serde(default)]
+    pub health_checks: Vec<HealthCheck>,
+    /// JSON Schema the tool's config file is validated against
+    #[serde(default)]
+    pub config_schema: Option<serde_json::Value>,
+    /// Format of the config file; taken from its extension when not given
+    #[serde(default)]
+    pub config_format: Option<ConfigFormat>,
+    /// Config snapshots kept for rollback; 0 turns backups off
+    #[serde(default)]
+    pub backup_retention: Option<usize>,
+    /// File that defined or last overrode the adapter; `None` for a built-in
+    #[serde(default)]
+    pub source: Option<String>,
 }
 
 /// Result of tool detection
@@ -18,6 +69,9 @@ pub struct DetectionResult {
     pub installed: bool,
     pub version: Option<String>,
     pub path: Option<String>,
+    /// Every copy found, in lookup order; the first is the one `path` names
+    #[serde(default)]
+    pub installations: Vec<Installation>,
 }
 
 /// Result of health check
@@ -28,17 +82,93 @@ pub struct HealthCheckResult {
     pub version: Option<String>,
     pub errors: Vec<String>,
     pub suggestions: Vec<String>,
+    /// One row per adapter health check; empty when the tool couldn't be run
+    #[serde(default)]
+    pub checks: Vec<CheckResult>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// Ordered from best to worst
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum HealthStatus {
     Healthy,
+    /// Installed and runnable, but at a version the adapter doesn't support
+    /// or failing an optional health check
+    Degraded,
     Unhealthy,
 }
 
+/// Why a detected version isn't supported
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionProblem {
+    /// Older than the supported range allows
+    TooOld { supported: String },
+    /// Newer than the supported range allows
+    TooNew { supported: String },
+    /// Listed in `broken_versions`
+    KnownBroken { entry: String },
+}
+
+/// Parse a version constraint; a bare version such as "1.0.61" means exactly that version
+pub fn parse_version_req(text: &str) -> Result<VersionReq, String> {
+    match Version::parse(text.trim()) {
+        Ok(version) => VersionReq::parse(&format!("={}", version)),
+        Err(_) => VersionReq::parse(text),
+    }
+    .map_err(|e| format!("invalid version constraint '{}': {}", text, e))
+}
+
+impl CLIAdapter {
+    /// Check `version` against the supported range and the known-broken list
+    pub fn version_problem(&self, version: &Version) -> Result<Option<VersionProblem>, String> {
+        for entry in &self.broken_versions {
+            if parse_version_req(entry)?.matches(version) {
+                return Ok(Some(VersionProblem::KnownBroken { entry: entry.clone() }));
+            }
+        }
+
+        let Some(supported) = &self.supported_versions else {
+            return Ok(None);
+        };
+        let req = parse_version_req(supported)?;
+        if req.matches(version) {
+            return Ok(None);
+        }
+        let supported = supported.clone();
+        // Too old if it falls short of a lower bound, otherwise it is past an upper bound
+        let too_old = req.comparators.iter().any(|c| {
+            let bound = Version::new(c.major, c.minor.unwrap_or(0), c.patch.unwrap_or(0));
+            !matches!(c.op, Op::Less | Op::LessEq) && *version < bound
+        });
+        Ok(Some(if too_old {
+            VersionProblem::TooOld { supported }
+        } else {
+            VersionProblem::TooNew { supported }
+        }))
+    }
+
+    /// Command that upgrades the copy at `path`, if we can tell how it was installed
+    pub fn upgrade_hint(&self, path: &Path) -> Option<String> {
+        let (method, detected_package) = tool_detection::install_method(path)?;
+        let package = self
+            .packages
+            .get(method.as_str())
+            .cloned()
+            .or(detected_package)
+            .unwrap_or_else(|| self.executable.clone());
+        Some(method.upgrade_command(&package))
+    }
+}
+
 /// Get list of available CLI adapters
+///
+/// Built-in adapters merged with the user's and the current project's adapter files.
 #[tauri::command]
 pub fn get_available_adapters() -> Vec<CLIAdapter> {
+    adapter_registry::adapters()
+}
+
+/// Adapters that ship with the app
+pub fn builtin_adapters() -> Vec<CLIAdapter> {
     vec![
         CLIAdapter {
             id: "codex".to_string(),
@@ -46,7 +176,29 @@ pub fn get_available_adapters() -> Vec<CLIAdapter> {
             executable: "codex".to_string(),
             detect_command: "codex --version".to_string(),
             version_command: "codex --version".to_string(),
-            config_paths: create_config_paths("codex"),
+            config_paths: same_config_paths(&["${CODEX_HOME:-~/.codex}/config.toml"]),
+            env: api_key_env("OPENAI_API_KEY", "openai_api_key"),
+            limits: None,
+            supported_versions: None,
+            broken_versions: Vec::new(),
+            packages: package_names(&[("npm", "@openai/codex"), ("brew", "codex")]),
+            output_format: Some(OutputFormat::CodexJson),
+            invocation: Some(Invocation::new(&["exec", "--json", "--model={model}", "{prompt}"])),
+            // Codex keeps its own login in ~/.codex/auth.json, so ask it
+            health_checks: vec![
+                HealthCheck::new(CheckKind::ConfigFile, true),
+                HealthCheck::new(
+                    CheckKind::Command {
+                        command: "codex login status".to_string(),
+                        timeout_ms: None,
+                    },
+                    true,
+                ),
+            ],
+            config_schema: config_schema::builtin_schema("codex"),
+            config_format: Some(ConfigFormat::Toml),
+            backup_retention: None,
+            source: None,
         },
         CLIAdapter {
             id: "claude-code".to_string(),
@@ -54,7 +206,34 @@ pub fn get_available_adapters() -> Vec<CLIAdapter> {
             executable: "claude".to_string(),
             detect_command: "claude --version".to_string(),
             version_command: "claude --version".to_string(),
-            config_paths: create_config_paths("claude"),
+            config_paths: {
+                let mut paths = create_config_paths("claude");
+                // Claude Code's own settings file, used when there is no app-specific one
+                for candidates in paths.values_mut() {
+                    candidates.push("~/.claude/settings.json".to_string());
+                }
+                paths
+            },
+            env: api_key_env("ANTHROPIC_API_KEY", "anthropic_api_key"),
+            limits: None,
+            supported_versions: None,
+            broken_versions: Vec::new(),
+            packages: package_names(&[("npm", "@anthropic-ai/claude-code")]),
+            output_format: Some(OutputFormat::ClaudeStreamJson),
+            invocation: Some(Invocation::new(&[
+                "-p",
+                "{prompt}",
+                "--output-format",
+                "stream-json",
+                "--verbose",
+                "--model={model}",
+            ])),
+            // Claude Code signs in with OAuth as often as with an API key
+            health_checks: vec![HealthCheck::new(CheckKind::ConfigFile, true)],
+            config_schema: config_schema::builtin_schema("claude-code"),
+            config_format: None,
+            backup_retention: None,
+            source: None,
         },
         CLIAdapter {
             id: "google-cli".to_string(),
@@ -63,19 +242,67 @@ pub fn get_available_adapters() -> Vec<CLIAdapter> {
             detect_command: "google-cli --version".to_string(),
             version_command: "google-cli --version".to_string(),
             config_paths: create_config_paths("google-cli"),
+            env: api_key_env("GOOGLE_API_KEY", "google_api_key"),
+            limits: None,
+            supported_versions: None,
+            broken_versions: Vec::new(),
+            packages: HashMap::new(),
+            output_format: None,
+            invocation: None,
+            // The API key is its only way to sign in
+            health_checks: vec![
+                HealthCheck::new(CheckKind::ConfigFile, true),
+                HealthCheck::new(CheckKind::Credentials { keys: Vec::new() }, true),
+            ],
+            config_schema: config_schema::builtin_schema("google-cli"),
+            config_format: None,
+            backup_retention: None,
+            source: None,
         },
     ]
 }
 
+/// Map an API key variable to an optional credential in secure storage
+///
+/// Optional so a key exported in the user's shell keeps working when nothing
+/// is stored.
+fn api_key_env(variable: &str, credential_key: &str) -> EnvMappings {
+    HashMap::from([(
+        variable.to_string(),
+        EnvSource::Secret {
+            key: credential_key.to_string(),
+            optional: true,
+        },
+    )])
+}
+
+fn package_names(names: &[(&str, &str)]) -> HashMap<String, String> {
+    names
+        .iter()
+        .map(|(method, package)| (method.to_string(), package.to_string()))
+        .collect()
+}
+
+/// The same candidates on every platform
+fn same_config_paths(candidates: &[&str]) -> ConfigPaths {
+    ["windows", "macos", "linux"]
+        .iter()
+        .map(|platform| (platform.to_string(), candidates.iter().map(|c| c.to_string()).collect()))
+        .collect()
+}
+
 /// Create platform-specific config paths
-fn create_config_paths(tool_name: &str) -> HashMap<String, String> {
+///
+/// Paths are expanded when used, so `$XDG_CONFIG_HOME` honours the user's
+/// setting and falls back to `~/.config`.
+fn create_config_paths(tool_name: &str) -> ConfigPaths {
     let mut paths = HashMap::new();
     
     #[cfg(target_os = "windows")]
     {
         paths.insert(
             "windows".to_string(),
-            format!("%APPDATA%\\{}\\config.json", tool_name),
+            vec![format!("%APPDATA%\\{}\\config.json", tool_name)],
         );
     }
     
@@ -83,7 +310,7 @@ fn create_config_paths(tool_name: &str) -> HashMap<String, String> {
     {
         paths.insert(
             "macos".to_string(),
-            format!("~/Library/Application Support/{}/config.json", tool_name),
+            vec![format!("~/Library/Application Support/{}/config.json", tool_name)],
         );
     }
     
@@ -91,7 +318,7 @@ fn create_config_paths(tool_name: &str) -> HashMap<String, String> {
     {
         paths.insert(
             "linux".to_string(),
-            format!("~/.config/{}/config.json", tool_name),
+            vec![format!("$XDG_CONFIG_HOME/{}/config.json", tool_name)],
         );
     }
 
@@ -99,15 +326,15 @@ fn create_config_paths(tool_name: &str) -> HashMap<String, String> {
     if paths.is_empty() {
         paths.insert(
             "windows".to_string(),
-            format!("%APPDATA%\\{}\\config.json", tool_name),
+            vec![format!("%APPDATA%\\{}\\config.json", tool_name)],
         );
         paths.insert(
             "macos".to_string(),
-            format!("~/Library/Application Support/{}/config.json", tool_name),
+            vec![format!("~/Library/Application Support/{}/config.json", tool_name)],
         );
         paths.insert(
             "linux".to_string(),
-            format!("~/.config/{}/config.json", tool_name),
+            vec![format!("$XDG_CONFIG_HOME/{}/config.json", tool_name)],
         );
     }
 
@@ -115,30 +342,82 @@ fn create_config_paths(tool_name: &str) -> HashMap<String, String> {
 }
 
 /// Detect if a CLI tool is installed
+///
+/// Every copy on PATH and in the usual install dirs is probed, so a copy
+/// shadowing another shows up. The tool counts as installed if the first
+/// copy runs.
 #[tauri::command]
 pub async fn detect_cli_tool(tool_id: String) -> Result<DetectionResult, String> {
-    // Placeholder implementation
-    // Actual implementation will execute the detect_command
-    let adapters = get_available_adapters();
-    let adapter = adapters.iter().find(|a| a.id == tool_id);
+    let adapter = get_available_adapters()
+        .into_iter()
+        .find(|a| a.id == tool_id)
+        .ok_or_else(|| format!("Unknown tool: {}", tool_id))?;
 
-    match adapter {
-        Some(_) => Ok(DetectionResult {
-            installed: false,
-            version: None,
-            path: None,
-        }),
-        None => Err(format!("Unknown tool: {}", tool_id)),
+    Ok(detect_adapter(&adapter, &tool_detection::search_dirs()).await)
+}
+
+async fn detect_adapter(adapter: &CLIAdapter, dirs: &[(PathBuf, bool)]) -> DetectionResult {
+    let mut installations = Vec::new();
+    for (path, on_path) in tool_detection::find_all(&adapter.executable, dirs) {
+        installations.push(
+            tool_detection::inspect(
+                path,
+                on_path,
+                &adapter.detect_command,
+                &adapter.version_command,
+                tool_detection::COMMAND_TIMEOUT,
+            )
+            .await,
+        );
+    }
+
+    let primary = installations.first();
+    DetectionResult {
+        installed: primary.is_some_and(Installation::works),
+        version: primary.and_then(|i| i.version.clone().or_else(|| i.version_output.clone())),
+        path: primary.map(|i| i.path.clone()),
+        installations,
     }
 }
 
 /// Run health check for a tool
+///
+/// A tool that runs but whose version is outside the adapter's supported
+/// range, or known to be broken, is reported as degraded.
 #[tauri::command]
 pub async fn run_health_check(tool_id: String) -> Result<HealthCheckResult, String> {
-    let detection = detect_cli_tool(tool_id.clone()).await?;
+    let adapter = get_available_adapters()
+        .into_iter()
+        .find(|a| a.id == tool_id)
+        .ok_or_else(|| format!("Unknown tool: {}", tool_id))?;
+    let detection = detect_adapter(&adapter, &tool_detection::search_dirs()).await;
+    let mut result = evaluate_health(&adapter, &detection);
+    if let Some(primary) = detection.installations.first().filter(|i| i.works()) {
+        let checks = health_checks::run_checks(&adapter, Path::new(&primary.path)).await;
+        apply_checks(&mut result, checks);
+    }
+    Ok(result)
+}
 
-    if !detection.installed {
-        return Ok(HealthCheckResult {
+/// Add check rows to a result; failures become errors and worsen the status
+fn apply_checks(result: &mut HealthCheckResult, checks: Vec<CheckResult>) {
+    for check in checks.iter().filter(|check| !check.passed) {
+        let status = if check.optional {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Unhealthy
+        };
+        result.status = result.status.max(status);
+        result.errors.push(format!("{}: {}", check.name, check.message));
+        result.suggestions.extend(check.suggestion.clone());
+    }
+    result.checks = checks;
+}
+
+fn evaluate_health(adapter: &CLIAdapter, detection: &DetectionResult) -> HealthCheckResult {
+    let tool_id = adapter.id.clone();
+    let Some(primary) = detection.installations.first() else {
+        return HealthCheckResult {
             tool_id: tool_id.clone(),
             status: HealthStatus::Unhealthy,
             version: None,
@@ -147,21 +426,96 @@ pub async fn run_health_check(tool_id: String) -> Result<HealthCheckResult, Stri
                 format!("Install {} using the appropriate package manager", tool_id),
                 "Check the installation guide in the documentation".to_string(),
             ],
-        });
+            checks: Vec::new(),
+        };
+    };
+
+    let upgrade = adapter.upgrade_hint(Path::new(&primary.path));
+    let upgrade_suggestion = || match &upgrade {
+        Some(command) => format!("Upgrade with: {}", command),
+        None => format!("Upgrade {} using the package manager it was installed with", adapter.name),
+    };
+
+    if let Some(error) = &primary.error {
+        return HealthCheckResult {
+            tool_id,
+            status: HealthStatus::Unhealthy,
+            version: None,
+            errors: vec![format!("{} at {} does not run: {}", adapter.name, primary.path, error)],
+            suggestions: vec![upgrade_suggestion()],
+            checks: Vec::new(),
+        };
     }
 
-    Ok(HealthCheckResult {
+    let mut result = HealthCheckResult {
         tool_id,
         status: HealthStatus::Healthy,
-        version: detection.version,
+        version: detection.version.clone(),
         errors: vec![],
         suggestions: vec![],
-    })
+        checks: Vec::new(),
+    };
+    let constrained = adapter.supported_versions.is_some() || !adapter.broken_versions.is_empty();
+    if !constrained {
+        return result;
+    }
+
+    let Some(version) = primary.version.as_deref().and_then(|v| Version::parse(v).ok()) else {
+        result.status = HealthStatus::Degraded;
+        result.errors.push(format!(
+            "Could not read a version from '{}'",
+            primary.version_output.as_deref().unwrap_or_default()
+        ));
+        return result;
+    };
+
+    let problem = match adapter.version_problem(&version) {
+        Ok(problem) => problem,
+        Err(e) => {
+            result.status = HealthStatus::Degraded;
+            result.errors.push(format!("Adapter {} has {}", adapter.id, e));
+            return result;
+        }
+    };
+    match problem {
+        None => {}
+        Some(VersionProblem::TooOld { supported }) => {
+            result.status = HealthStatus::Degraded;
+            result.errors.push(format!(
+                "{} {} is older than the supported versions ({})",
+                adapter.name, version, supported
+            ));
+            result.suggestions.push(upgrade_suggestion());
+        }
+        Some(VersionProblem::TooNew { supported }) => {
+            result.status = HealthStatus::Degraded;
+            result.errors.push(format!(
+                "{} {} is newer than the supported versions ({})",
+                adapter.name, version, supported
+            ));
+            result.suggestions.push(format!(
+                "Install a version matching {} or update the adapter's supported_versions",
+                supported
+            ));
+        }
+        Some(VersionProblem::KnownBroken { entry }) => {
+            result.status = HealthStatus::Degraded;
+            result.errors.push(format!(
+                "{} {} is known not to work with this app ({})",
+                adapter.name, version, entry
+            ));
+            result.suggestions.push(upgrade_suggestion());
+        }
+    }
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::adapter_named;
+    #[cfg(unix)]
+    use crate::test_support::fake_executable;
 
     #[test]
     fn test_get_available_adapters() {
@@ -172,6 +526,122 @@ mod tests {
         assert!(adapters.iter().any(|a| a.id == "google-cli"));
     }
 
+    #[test]
+    fn test_builtin_checks_leave_tool_logins_alone() {
+        // Every config is checked; a login the tool keeps itself isn't second-guessed
+        for adapter in builtin_adapters() {
+            let kinds: Vec<&CheckKind> = adapter.health_checks.iter().map(|check| &check.kind).collect();
+            assert!(kinds.iter().any(|kind| matches!(kind, CheckKind::ConfigFile)), "{}", adapter.id);
+            if adapter.id != "google-cli" {
+                assert!(!kinds.iter().any(|kind| matches!(kind, CheckKind::Credentials { .. })), "{}", adapter.id);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_detect_adapter() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let executable = fake_executable(temp.path(), "detect-test-tool", "echo 'detect-test-tool 3.2.1'");
+
+        let mut adapter = adapter_named("codex");
+        adapter.executable = "detect-test-tool".to_string();
+        adapter.detect_command = "detect-test-tool --version".to_string();
+        adapter.version_command = "detect-test-tool --version".to_string();
+
+        let dirs = vec![(temp.path().to_path_buf(), true)];
+        let detection = detect_adapter(&adapter, &dirs).await;
+        assert!(detection.installed);
+        assert_eq!(detection.version.as_deref(), Some("3.2.1"));
+        assert_eq!(detection.path.as_deref(), executable.to_str());
+        assert_eq!(detection.installations.len(), 1);
+
+        let detection = detect_adapter(&adapter, &[]).await;
+        assert!(!detection.installed);
+        assert!(detection.installations.is_empty());
+    }
+
+    fn detected(version: &str) -> DetectionResult {
+        DetectionResult {
+            installed: true,
+            version: Some(version.to_string()),
+            path: Some("/usr/local/lib/node_modules/@openai/codex/bin/codex.js".to_string()),
+            installations: vec![Installation {
+                path: "/usr/local/lib/node_modules/@openai/codex/bin/codex.js".to_string(),
+                version: Some(version.to_string()),
+                version_output: Some(format!("codex-cli {}", version)),
+                on_path: true,
+                error: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_version_constraints() {
+        let mut adapter = adapter_named("codex");
+        adapter.supported_versions = Some(">=0.20.0, <1".to_string());
+        adapter.broken_versions = vec!["0.22.1".to_string(), ">=0.30.0, <0.30.3".to_string()];
+        let problem = |v: &str| adapter.version_problem(&Version::parse(v).unwrap()).unwrap();
+
+        assert_eq!(problem("0.21.0"), None);
+        assert_eq!(problem("0.22.2"), None);
+        assert!(matches!(problem("0.19.9"), Some(VersionProblem::TooOld { .. })));
+        assert!(matches!(problem("1.0.0"), Some(VersionProblem::TooNew { .. })));
+        assert!(matches!(problem("0.22.1"), Some(VersionProblem::KnownBroken { .. })));
+        assert!(matches!(problem("0.30.2"), Some(VersionProblem::KnownBroken { .. })));
+        assert!(parse_version_req("not a version").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_health() {
+        let mut adapter = adapter_named("codex");
+        let result = evaluate_health(&adapter, &detected("0.1.0"));
+        assert_eq!(result.status, HealthStatus::Healthy);
+
+        adapter.supported_versions = Some(">=0.20.0".to_string());
+        let result = evaluate_health(&adapter, &detected("0.19.0"));
+        assert_eq!(result.status, HealthStatus::Degraded);
+        assert!(result.errors[0].contains("older than the supported versions"));
+        assert_eq!(result.suggestions, vec!["Upgrade with: npm install -g @openai/codex@latest"]);
+
+        assert_eq!(evaluate_health(&adapter, &detected("0.21.0")).status, HealthStatus::Healthy);
+
+        let mut broken = detected("0.21.0");
+        broken.installed = false;
+        broken.installations[0].error = Some("exited with 1".to_string());
+        assert_eq!(evaluate_health(&adapter, &broken).status, HealthStatus::Unhealthy);
+
+        let missing = DetectionResult {
+            installed: false,
+            version: None,
+            path: None,
+            installations: vec![],
+        };
+        assert_eq!(evaluate_health(&adapter, &missing).status, HealthStatus::Unhealthy);
+    }
+
+    #[test]
+    fn test_apply_checks() {
+        let adapter = adapter_named("codex");
+        let check = |name: &str, passed: bool, optional: bool| CheckResult {
+            name: name.to_string(),
+            passed,
+            optional,
+            message: format!("{} message", name),
+            suggestion: (!passed).then(|| format!("fix {}", name)),
+        };
+
+        let mut result = evaluate_health(&adapter, &detected("0.1.0"));
+        apply_checks(&mut result, vec![check("Config file", true, false), check("Credentials", false, true)]);
+        assert_eq!(result.status, HealthStatus::Degraded);
+        assert_eq!(result.errors, vec!["Credentials: Credentials message"]);
+        assert_eq!(result.suggestions, vec!["fix Credentials"]);
+        assert_eq!(result.checks.len(), 2);
+
+        apply_checks(&mut result, vec![check("codex login status", false, false)]);
+        assert_eq!(result.status, HealthStatus::Unhealthy);
+    }
+
     #[test]
     fn test_health_status_serialization() {
         let status = HealthStatus::Healthy;
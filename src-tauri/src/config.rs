@@ -1,14 +1,26 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
 
-use crate::cli_adapter::get_available_adapters;
+use crate::cli_adapter::{get_available_adapters, CLIAdapter};
+use crate::config_backups;
+use crate::config_format::ConfigFormat;
+use crate::config_location::{self, ConfigLocation};
+use crate::config_schema::{self, ConfigIssue, Severity};
 use crate::error::AppError;
 
 /// Configuration validation result
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidationResult {
     pub valid: bool,
+    /// Error issues as "line L, column C: /pointer: message"
     pub errors: Vec<String>,
+    /// Warning issues, such as unknown keys, in the same form
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Every issue with its pointer and position
+    #[serde(default)]
+    pub issues: Vec<ConfigIssue>,
 }
 
 /// Read tool configuration file
@@ -21,23 +33,73 @@ pub async fn read_tool_config(tool_id: String) -> Result<String, String> {
 }
 
 /// Write tool configuration file
+///
+/// The previous content is kept as a backup that `restore_config_version` can bring back.
 #[tauri::command]
 pub async fn write_tool_config(tool_id: String, content: String) -> Result<(), String> {
-    // Validate before writing
-    let validation = validate_config_impl(&tool_id, &content)?;
+    write_config(&find_adapter(&tool_id)?, &content)
+}
+
+fn write_config(adapter: &CLIAdapter, content: &str) -> Result<(), String> {
+    // Validate before writing; warnings don't block the write
+    let validation = validate_for(adapter, content);
     if !validation.valid {
-        return Err(format!("Invalid configuration: {:?}", validation.errors));
+        let path = locate_config(adapter).map(|location| location.path).unwrap_or_default();
+        return Err(AppError::ConfigInvalid {
+            path,
+            errors: validation.errors,
+        }
+        .to_string());
     }
 
-    let config_path = get_config_path_impl(&tool_id)?;
-    
+    let config_path = config_file(adapter)?;
+    save_config_file(adapter, &config_path, content)
+}
+
+/// Set one value in a tool's config file, leaving the rest of the file as it is
+///
+/// `pointer` is a JSON pointer such as "/model_providers/local/base_url";
+/// missing parents are created. Comments, key order and formatting elsewhere
+/// in the file are kept, in JSON, TOML and YAML alike.
+#[tauri::command]
+pub async fn set_tool_config_value(tool_id: String, pointer: String, value: Value) -> Result<ValidationResult, String> {
+    set_config_value(&find_adapter(&tool_id)?, &pointer, &value)
+}
+
+fn set_config_value(adapter: &CLIAdapter, pointer: &str, value: &Value) -> Result<ValidationResult, String> {
+    let path = config_file(adapter)?;
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(AppError::IoError(e.to_string()).to_string()),
+    };
+
+    let format = config_format(adapter, &path);
+    let edited = format.set(&content, pointer, value)?;
+    let validation = validate_content(&edited, format, adapter.config_schema.as_ref());
+    if !validation.valid {
+        return Err(AppError::ConfigInvalid {
+            path: path.display().to_string(),
+            errors: validation.errors,
+        }
+        .to_string());
+    }
+
+    save_config_file(adapter, &path, &edited)?;
+    Ok(validation)
+}
+
+/// Back up the adapter's current config, then replace it with `content`
+fn save_config_file(adapter: &CLIAdapter, path: &Path, content: &str) -> Result<(), String> {
+    config_backups::backup(adapter, path)?;
+
     // Create parent directory if needed
-    if let Some(parent) = PathBuf::from(&config_path).parent() {
+    if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
             .map_err(|e| AppError::IoError(e.to_string()).to_string())?;
     }
 
-    std::fs::write(&config_path, content)
+    std::fs::write(path, content)
         .map_err(|e| AppError::IoError(e.to_string()).to_string())
 }
 
@@ -47,24 +109,40 @@ pub async fn validate_config(tool_id: String, content: String) -> Result<Validat
     validate_config_impl(&tool_id, &content)
 }
 
-fn validate_config_impl(_tool_id: &str, content: &str) -> Result<ValidationResult, String> {
-    let mut errors = Vec::new();
-
-    // Check if content is valid JSON
-    if let Err(e) = serde_json::from_str::<serde_json::Value>(content) {
-        errors.push(format!("Invalid JSON: {}", e));
-        return Ok(ValidationResult {
-            valid: false,
-            errors,
-        });
-    }
+pub(crate) fn validate_config_impl(tool_id: &str, content: &str) -> Result<ValidationResult, String> {
+    let Some(adapter) = get_available_adapters().into_iter().find(|a| a.id == tool_id) else {
+        return Ok(validate_content(content, ConfigFormat::Json, None));
+    };
+    Ok(validate_for(&adapter, content))
+}
 
-    // Additional validation can be added here based on tool-specific schemas
+/// Validate content in the format and against the schema of the adapter's config
+fn validate_for(adapter: &CLIAdapter, content: &str) -> ValidationResult {
+    let format = match config_file(adapter) {
+        Ok(path) => config_format(adapter, &path),
+        Err(_) => adapter.config_format.unwrap_or_default(),
+    };
+    validate_content(content, format, adapter.config_schema.as_ref())
+}
 
-    Ok(ValidationResult {
+/// Check config text for syntax errors and against the adapter's schema
+pub(crate) fn validate_content(content: &str, format: ConfigFormat, schema: Option<&Value>) -> ValidationResult {
+    let issues = config_schema::validate(content, format, schema);
+    let describe = |severity: Severity| -> Vec<String> {
+        issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .map(ConfigIssue::describe)
+            .collect()
+    };
+    let errors = describe(Severity::Error);
+    let warnings = describe(Severity::Warning);
+    ValidationResult {
         valid: errors.is_empty(),
         errors,
-    })
+        warnings,
+        issues,
+    }
 }
 
 /// Get configuration file path for a tool
@@ -74,19 +152,42 @@ pub fn get_config_path(tool_id: String) -> Result<String, String> {
 }
 
 fn get_config_path_impl(tool_id: &str) -> Result<String, String> {
-    let adapters = get_available_adapters();
-    let adapter = adapters.iter().find(|a| a.id == tool_id);
-
-    match adapter {
-        Some(a) => {
-            let platform = get_current_platform();
-            a.config_paths
-                .get(&platform)
-                .cloned()
-                .ok_or_else(|| format!("No config path for platform: {}", platform))
-        }
-        None => Err(format!("Unknown tool: {}", tool_id)),
-    }
+    Ok(locate_config(&find_adapter(tool_id)?)?.path)
+}
+
+/// Find a tool's config file among its candidate paths
+///
+/// Reports every candidate with its expansion, so the UI can show which one
+/// was found and why the others were passed over.
+#[tauri::command]
+pub fn locate_tool_config(tool_id: String) -> Result<ConfigLocation, String> {
+    locate_config(&find_adapter(&tool_id)?)
+}
+
+/// Format of the adapter's config file at `path`: declared by the adapter or implied by the extension
+pub(crate) fn config_format(adapter: &CLIAdapter, path: &Path) -> ConfigFormat {
+    adapter.config_format.unwrap_or_else(|| ConfigFormat::from_path(path))
+}
+
+pub(crate) fn find_adapter(tool_id: &str) -> Result<CLIAdapter, String> {
+    get_available_adapters()
+        .into_iter()
+        .find(|a| a.id == tool_id)
+        .ok_or_else(|| format!("Unknown tool: {}", tool_id))
+}
+
+/// The adapter's config file on this platform: the first candidate that exists, else the first usable one
+pub(crate) fn config_file(adapter: &CLIAdapter) -> Result<PathBuf, String> {
+    Ok(PathBuf::from(locate_config(adapter)?.path))
+}
+
+fn locate_config(adapter: &CLIAdapter) -> Result<ConfigLocation, String> {
+    let platform = get_current_platform();
+    let candidates = adapter
+        .config_paths
+        .get(&platform)
+        .ok_or_else(|| format!("No config path for platform: {}", platform))?;
+    config_location::locate(candidates)
 }
 
 fn get_current_platform() -> String {
@@ -106,18 +207,55 @@ fn get_current_platform() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     #[test]
     fn test_validate_valid_json() {
-        let result = validate_config_impl("codex", r#"{"key": "value"}"#).unwrap();
+        let result = validate_config_impl("claude-code", r#"{"key": "value"}"#).unwrap();
         assert!(result.valid);
         assert!(result.errors.is_empty());
     }
 
     #[test]
     fn test_validate_invalid_json() {
-        let result = validate_config_impl("codex", "not json").unwrap();
+        let result = validate_config_impl("claude-code", "not json").unwrap();
         assert!(!result.valid);
         assert!(!result.errors.is_empty());
     }
+
+    #[test]
+    fn test_validate_against_adapter_schema() {
+        let result = validate_config_impl("codex", "# Codex\nmodel = 4\nmodle = \"x\"\n").unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].starts_with("line 2, column 1: /model: "), "{}", result.errors[0]);
+        assert_eq!(result.warnings, vec!["line 3, column 1: /modle: Unknown key 'modle'"]);
+        assert_eq!(result.issues.len(), 2);
+
+        let result = validate_config_impl("codex", "model = \"o4-mini\"\nmodle = \"x\"\n").unwrap();
+        assert!(result.valid);
+        assert_eq!(result.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_write_rejects_type_mismatch() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        let mut adapter = find_adapter("codex").unwrap();
+        adapter.config_paths = HashMap::from([(get_current_platform(), vec![path.display().to_string()])]);
+
+        let err = write_config(&adapter, "model = 4\n").unwrap_err();
+        assert!(err.starts_with("Configuration invalid at"), "{}", err);
+        assert!(!path.exists());
+
+        write_config(&adapter, "# mine\nmodel = \"o3\"\n").unwrap();
+        let err = set_config_value(&adapter, "/approval_policy", &Value::from("sometimes")).unwrap_err();
+        assert!(err.contains("/approval_policy"), "{}", err);
+
+        set_config_value(&adapter, "/approval_policy", &Value::from("never")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# mine\nmodel = \"o3\"\napproval_policy = \"never\"\n"
+        );
+    }
 }
@@ -0,0 +1,303 @@
+// Config Backups - snapshots a tool's config before every write so edits can be rolled back
+// Each tool gets a directory under the app data dir; snapshots are named by time and old ones are pruned
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::cli_adapter::CLIAdapter;
+use crate::config;
+use crate::error::AppError;
+use crate::output;
+
+/// Directory under the app data dir holding all config backups
+pub const BACKUP_DIR: &str = "config-backups";
+
+/// Snapshots kept per tool unless its adapter sets `backup_retention`
+pub const DEFAULT_RETENTION: usize = 20;
+
+/// Version id that stands for the config file as it is now
+pub const CURRENT: &str = "current";
+
+/// Format of snapshot names; they sort in the order they were taken
+const ID_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// A stored version of a tool's config
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigVersion {
+    /// Name of the snapshot, e.g. "20261017-093015.250"
+    pub id: String,
+    /// Time the snapshot was taken, in milliseconds since the Unix epoch
+    pub created_at: u64,
+    pub size_bytes: u64,
+    pub path: String,
+}
+
+/// Line diff between two versions of a config
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigDiff {
+    pub from: String,
+    pub to: String,
+    /// Unified diff; empty when the versions are the same
+    pub unified: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Root of the backup directories, set once during app setup
+static BACKUP_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set the directory config backups are stored in
+///
+/// Called once during app setup; until then writes aren't backed up.
+pub fn init(root: PathBuf) {
+    let _ = BACKUP_ROOT.set(root);
+}
+
+fn backup_root() -> Result<&'static PathBuf, String> {
+    BACKUP_ROOT
+        .get()
+        .ok_or_else(|| AppError::IoError("Config backups are not set up".to_string()).to_string())
+}
+
+/// Directory holding the snapshots of one tool
+fn tool_dir(root: &Path, tool_id: &str) -> PathBuf {
+    let name: String = tool_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    root.join(name)
+}
+
+fn io_error(e: std::io::Error) -> String {
+    AppError::from(e).to_string()
+}
+
+/// Snapshot the config at `path` before it is overwritten
+///
+/// Does nothing when the file doesn't exist yet or backups aren't set up.
+pub(crate) fn backup(adapter: &CLIAdapter, path: &Path) -> Result<Option<ConfigVersion>, String> {
+    let Some(root) = BACKUP_ROOT.get() else {
+        return Ok(None);
+    };
+    let retention = adapter.backup_retention.unwrap_or(DEFAULT_RETENTION);
+    snapshot_in(&tool_dir(root, &adapter.id), path, retention, output::now_millis())
+}
+
+/// Store the content of `path` in `dir` unless it matches the newest snapshot, then prune
+fn snapshot_in(dir: &Path, path: &Path, retention: usize, now: u64) -> Result<Option<ConfigVersion>, String> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(io_error(e)),
+    };
+    if retention == 0 {
+        return Ok(None);
+    }
+
+    let versions = list_in(dir)?;
+    let unchanged = match versions.first() {
+        Some(newest) => fs::read(&newest.path).map_err(io_error)? == content,
+        None => false,
+    };
+    let snapshot = if unchanged {
+        None
+    } else {
+        fs::create_dir_all(dir).map_err(io_error)?;
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("bak");
+        // Two writes within a millisecond get consecutive names rather than one
+        let mut created_at = now.max(versions.first().map_or(0, |v| v.created_at + 1));
+        let mut file = dir.join(format!("{}.{}", version_id(created_at), extension));
+        while file.exists() {
+            created_at += 1;
+            file = dir.join(format!("{}.{}", version_id(created_at), extension));
+        }
+        fs::write(&file, &content).map_err(io_error)?;
+        Some(ConfigVersion {
+            id: version_id(created_at),
+            created_at,
+            size_bytes: content.len() as u64,
+            path: file.display().to_string(),
+        })
+    };
+
+    for old in list_in(dir)?.iter().skip(retention) {
+        fs::remove_file(&old.path).map_err(io_error)?;
+    }
+    Ok(snapshot)
+}
+
+fn version_id(created_at: u64) -> String {
+    let time = DateTime::<Utc>::from_timestamp_millis(created_at as i64).unwrap_or_default();
+    time.format(ID_FORMAT).to_string()
+}
+
+/// Snapshots in `dir`, newest first
+fn list_in(dir: &Path) -> Result<Vec<ConfigVersion>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io_error(e)),
+    };
+    let mut versions = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(io_error)?;
+        let path = entry.path();
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        // Skip anything that isn't a snapshot
+        let Ok(time) = NaiveDateTime::parse_from_str(id, ID_FORMAT) else {
+            continue;
+        };
+        versions.push(ConfigVersion {
+            id: id.to_string(),
+            created_at: time.and_utc().timestamp_millis() as u64,
+            size_bytes: entry.metadata().map_err(io_error)?.len(),
+            path: path.display().to_string(),
+        });
+    }
+    versions.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(versions)
+}
+
+/// Content of a snapshot, or of the live file for [`CURRENT`]
+fn read_in(dir: &Path, config_path: &Path, id: &str) -> Result<String, String> {
+    if id == CURRENT {
+        return match fs::read_to_string(config_path) {
+            Ok(content) => Ok(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(io_error(e)),
+        };
+    }
+    let version = list_in(dir)?
+        .into_iter()
+        .find(|v| v.id == id)
+        .ok_or_else(|| AppError::FileNotFound(format!("No config version '{}'", id)).to_string())?;
+    fs::read_to_string(&version.path).map_err(io_error)
+}
+
+fn diff(from: &str, to: &str, old: &str, new: &str) -> ConfigDiff {
+    let text_diff = TextDiff::from_lines(old, new);
+    let (mut added, mut removed) = (0, 0);
+    for change in text_diff.iter_all_changes() {
+        match change.tag() {
+            similar::ChangeTag::Insert => added += 1,
+            similar::ChangeTag::Delete => removed += 1,
+            similar::ChangeTag::Equal => {}
+        }
+    }
+    let unified = if added + removed == 0 {
+        String::new()
+    } else {
+        text_diff.unified_diff().context_radius(3).header(from, to).to_string()
+    };
+    ConfigDiff {
+        from: from.to_string(),
+        to: to.to_string(),
+        unified,
+        added,
+        removed,
+    }
+}
+
+/// List the stored versions of a tool's config, newest first
+#[tauri::command]
+pub fn list_config_history(tool_id: String) -> Result<Vec<ConfigVersion>, String> {
+    list_in(&tool_dir(backup_root()?, &tool_id))
+}
+
+/// Get the content of a stored version, or of the live file for "current"
+#[tauri::command]
+pub fn read_config_version(tool_id: String, version_id: String) -> Result<String, String> {
+    let path = config::config_file(&config::find_adapter(&tool_id)?)?;
+    read_in(&tool_dir(backup_root()?, &tool_id), &path, &version_id)
+}
+
+/// Diff two versions of a tool's config; either may be "current"
+#[tauri::command]
+pub fn diff_config_versions(tool_id: String, from: String, to: String) -> Result<ConfigDiff, String> {
+    let path = config::config_file(&config::find_adapter(&tool_id)?)?;
+    let dir = tool_dir(backup_root()?, &tool_id);
+    let old = read_in(&dir, &path, &from)?;
+    let new = read_in(&dir, &path, &to)?;
+    Ok(diff(&from, &to, &old, &new))
+}
+
+/// Put a stored version back as the tool's config
+///
+/// The config being replaced is snapshotted first, so a restore can itself be
+/// undone; that snapshot is returned. The restored content isn't validated,
+/// since it is what the file said before.
+#[tauri::command]
+pub fn restore_config_version(tool_id: String, version_id: String) -> Result<Option<ConfigVersion>, String> {
+    let adapter = config::find_adapter(&tool_id)?;
+    let path = config::config_file(&adapter)?;
+    let dir = tool_dir(backup_root()?, &tool_id);
+    if version_id == CURRENT {
+        return Err("Choose a stored version to restore".to_string());
+    }
+    let content = read_in(&dir, &path, &version_id)?;
+    let replaced = backup(&adapter, &path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    fs::write(&path, content).map_err(io_error)?;
+    Ok(replaced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const T0: u64 = 1_700_000_000_000;
+
+    #[test]
+    fn test_snapshots_are_listed_and_pruned() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("codex");
+        let config = temp.path().join("config.toml");
+
+        assert_eq!(snapshot_in(&dir, &config, 3, T0).unwrap(), None);
+
+        for (i, model) in ["a", "b", "c", "d"].iter().enumerate() {
+            fs::write(&config, format!("model = \"{}\"\n", model)).unwrap();
+            let version = snapshot_in(&dir, &config, 3, T0 + i as u64 * 1000).unwrap().unwrap();
+            assert_eq!(version.created_at, T0 + i as u64 * 1000);
+            assert!(version.path.ends_with(".toml"));
+        }
+        // Unchanged content isn't stored twice
+        assert_eq!(snapshot_in(&dir, &config, 3, T0 + 9000).unwrap(), None);
+
+        let versions = list_in(&dir).unwrap();
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[0].id, version_id(T0 + 3000));
+        assert_eq!(versions[2].id, version_id(T0 + 1000));
+        assert_eq!(read_in(&dir, &config, &versions[2].id).unwrap(), "model = \"b\"\n");
+
+        // Same millisecond, different content
+        fs::write(&config, "model = \"e\"\n").unwrap();
+        let version = snapshot_in(&dir, &config, 3, T0 + 3000).unwrap().unwrap();
+        assert_eq!(version.created_at, T0 + 3001);
+
+        assert_eq!(snapshot_in(&dir, &config, 0, T0 + 5000).unwrap(), None);
+        assert!(read_in(&dir, &config, "20000101-000000.000").is_err());
+    }
+
+    #[test]
+    fn test_diff_versions() {
+        let old = "# settings\nmodel = \"o3\"\napproval_policy = \"never\"\n";
+        let new = "# settings\nmodel = \"o4-mini\"\napproval_policy = \"never\"\nsandbox_mode = \"read-only\"\n";
+        let result = diff("20261017-093015.250", CURRENT, old, new);
+        assert_eq!((result.added, result.removed), (2, 1));
+        assert!(result.unified.starts_with("--- 20261017-093015.250\n+++ current\n"), "{}", result.unified);
+        assert!(result.unified.contains("-model = \"o3\"\n+model = \"o4-mini\"\n"), "{}", result.unified);
+
+        let same = diff("a", "b", old, old);
+        assert_eq!((same.added, same.removed, same.unified.as_str()), (0, 0, ""));
+    }
+}
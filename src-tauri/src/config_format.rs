@@ -0,0 +1,755 @@
+// Config Format - reads, locates and edits JSON, TOML and YAML tool configs
+// Edits splice the changed value into the text so comments, key order and layout survive
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// File format of a tool's config
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFormat {
+    #[default]
+    Json,
+    Toml,
+    Yaml,
+}
+
+/// Why config text could not be parsed, and where
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl ConfigFormat {
+    /// Format implied by a file's extension; JSON when it says nothing
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => ConfigFormat::Toml,
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "JSON",
+            ConfigFormat::Toml => "TOML",
+            ConfigFormat::Yaml => "YAML",
+        }
+    }
+
+    /// Parse config text into a JSON value so one schema covers every format
+    ///
+    /// An empty TOML or YAML file is an empty object.
+    pub fn parse(&self, content: &str) -> Result<Value, ParseError> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| ParseError {
+                message: e.to_string(),
+                line: Some(e.line()),
+                column: Some(e.column()),
+            }),
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| {
+                let position = e.span().map(|span| line_column(content, span.start));
+                ParseError {
+                    message: e.message().to_string(),
+                    line: position.map(|(line, _)| line),
+                    column: position.map(|(_, column)| column),
+                }
+            }),
+            ConfigFormat::Yaml => {
+                let value: Value = serde_yaml::from_str(content).map_err(|e| ParseError {
+                    message: e.to_string(),
+                    line: e.location().map(|l| l.line()),
+                    column: e.location().map(|l| l.column()),
+                })?;
+                Ok(if value.is_null() { Value::Object(Default::default()) } else { value })
+            }
+        }
+    }
+
+    /// 1-based line and column of the value at a JSON pointer
+    ///
+    /// For an object member the position of its key is returned, which is
+    /// where an editor should put the cursor.
+    pub fn locate(&self, content: &str, pointer: &str) -> Option<(usize, usize)> {
+        let segments = pointer_segments(pointer)?;
+        let offset = match self {
+            ConfigFormat::Json => json_locate(content, &segments)?.0,
+            ConfigFormat::Toml => toml_locate(content, &segments)?,
+            ConfigFormat::Yaml => yaml_locate(content, &segments)?,
+        };
+        Some(line_column(content, offset))
+    }
+
+    /// Set the value at a JSON pointer, changing nothing else in the text
+    ///
+    /// Missing parent objects are created. Setting `null` in TOML, which has
+    /// no null, removes the key.
+    pub fn set(&self, content: &str, pointer: &str, value: &Value) -> Result<String, String> {
+        let segments = pointer_segments(pointer).ok_or_else(|| format!("Invalid JSON pointer: {}", pointer))?;
+        if segments.is_empty() {
+            return Err("Cannot replace the whole config; set a key inside it".to_string());
+        }
+        match self {
+            ConfigFormat::Json => json_set(content, &segments, value),
+            ConfigFormat::Toml => toml_set(content, &segments, value),
+            ConfigFormat::Yaml => yaml_set(content, &segments, value),
+        }
+    }
+}
+
+/// Unescaped segments of a JSON pointer, or `None` if it isn't one
+fn pointer_segments(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    Some(
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+// JSON
+
+/// Offset to report for the value at `segments`, and the span of the value itself
+fn json_locate(content: &str, segments: &[String]) -> Option<(usize, std::ops::Range<usize>)> {
+    let mut scanner = Scanner { text: content.as_bytes(), pos: 0 };
+    scanner.skip_whitespace();
+    let mut found = scanner.pos;
+    for segment in segments {
+        found = scanner.enter(segment)?;
+    }
+    let start = scanner.pos;
+    scanner.skip_value()?;
+    Some((found, start..scanner.pos))
+}
+
+fn json_set(content: &str, segments: &[String], value: &Value) -> Result<String, String> {
+    let content = if content.trim().is_empty() { "{}\n" } else { content };
+    serde_json::from_str::<Value>(content).map_err(|e| format!("Cannot edit invalid JSON: {}", e))?;
+
+    if let Some((_, span)) = json_locate(content, segments) {
+        let indent = line_indent(content, span.start);
+        let rendered = indent_lines(&serde_json::to_string_pretty(value).unwrap_or_default(), &indent);
+        return Ok(format!("{}{}{}", &content[..span.start], rendered, &content[span.end..]));
+    }
+
+    // Find the deepest existing object and add the rest of the path to it
+    let mut depth = segments.len() - 1;
+    let parent = loop {
+        if let Some((_, span)) = json_locate(content, &segments[..depth]) {
+            break span;
+        }
+        depth -= 1;
+    };
+    if !content[parent.clone()].starts_with('{') {
+        return Err(format!("/{} is not an object", segments[..depth].join("/")));
+    }
+    let nested = segments[depth + 1..]
+        .iter()
+        .rev()
+        .fold(value.clone(), |inner, key| serde_json::json!({ key.as_str(): inner }));
+
+    let close = parent.end - 1;
+    let body = &content[parent.start + 1..close];
+    let parent_indent = line_indent(content, parent.start);
+    let member_indent = match body.trim().is_empty() {
+        true => format!("{}  ", parent_indent),
+        false => body
+            .lines()
+            .skip(1)
+            .find(|line| !line.trim().is_empty())
+            .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+            .unwrap_or_else(|| format!("{}  ", parent_indent)),
+    };
+    let member = format!(
+        "{}: {}",
+        serde_json::to_string(&segments[depth]).unwrap_or_default(),
+        indent_lines(&serde_json::to_string_pretty(&nested).unwrap_or_default(), &member_indent)
+    );
+
+    if body.trim().is_empty() {
+        Ok(format!(
+            "{}\n{}{}\n{}{}",
+            &content[..parent.start + 1],
+            member_indent,
+            member,
+            parent_indent,
+            &content[close..]
+        ))
+    } else {
+        let last = parent.start + 1 + body.trim_end().len();
+        Ok(format!("{},\n{}{}{}", &content[..last], member_indent, member, &content[last..]))
+    }
+}
+
+/// Leading whitespace of the line `offset` is on
+fn line_indent(content: &str, offset: usize) -> String {
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    content[line_start..].chars().take_while(|c| *c == ' ' || *c == '\t').collect()
+}
+
+/// Indent every line after the first, for text placed at an indented position
+fn indent_lines(text: &str, indent: &str) -> String {
+    text.replace('\n', &format!("\n{}", indent))
+}
+
+/// Walks JSON text just far enough to find where values start
+struct Scanner<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Expect `byte` after optional whitespace
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    /// Move to the child `segment` of the value at the cursor
+    ///
+    /// Leaves the cursor at the child's value and returns the offset to report.
+    fn enter(&mut self, segment: &str) -> Option<usize> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                loop {
+                    self.skip_whitespace();
+                    let key_start = self.pos;
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    self.skip_whitespace();
+                    if key == segment {
+                        return Some(key_start);
+                    }
+                    self.skip_value()?;
+                    self.expect(b',')?;
+                }
+            }
+            b'[' => {
+                let index: usize = segment.parse().ok()?;
+                self.pos += 1;
+                for _ in 0..index {
+                    self.skip_whitespace();
+                    self.skip_value()?;
+                    self.expect(b',')?;
+                }
+                self.skip_whitespace();
+                (self.peek()? != b']').then_some(self.pos)
+            }
+            _ => None,
+        }
+    }
+
+    /// Read a string at the cursor, decoding escapes
+    fn string(&mut self) -> Option<String> {
+        let start = self.pos;
+        self.skip_string()?;
+        serde_json::from_slice(&self.text[start..self.pos]).ok()
+    }
+
+    fn skip_string(&mut self) -> Option<()> {
+        (self.peek()? == b'"').then_some(())?;
+        self.pos += 1;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'"' => self.skip_string(),
+            open @ (b'{' | b'[') => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.peek()? == close {
+                    self.pos += 1;
+                    return Some(());
+                }
+                loop {
+                    self.skip_whitespace();
+                    if open == b'{' {
+                        self.skip_string()?;
+                        self.expect(b':')?;
+                        self.skip_whitespace();
+                    }
+                    self.skip_value()?;
+                    self.skip_whitespace();
+                    match self.peek()? {
+                        b',' => self.pos += 1,
+                        b if b == close => {
+                            self.pos += 1;
+                            return Some(());
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            _ => {
+                // A number or a literal runs until a delimiter
+                while self
+                    .peek()
+                    .is_some_and(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+                Some(())
+            }
+        }
+    }
+}
+
+// TOML
+
+fn toml_locate(content: &str, segments: &[String]) -> Option<usize> {
+    let document = toml_edit::ImDocument::parse(content).ok()?;
+    let mut item = document.as_item();
+    let mut found = 0;
+    for segment in segments {
+        if let Some(table) = item.as_table_like() {
+            let (key, child) = table.get_key_value(segment)?;
+            found = key.span().or_else(|| child.span())?.start;
+            item = child;
+        } else {
+            let array = item.as_array()?;
+            let element = array.get(segment.parse().ok()?)?;
+            found = element.span()?.start;
+            return (segment == segments.last()?).then_some(found);
+        }
+    }
+    Some(found)
+}
+
+fn toml_set(content: &str, segments: &[String], value: &Value) -> Result<String, String> {
+    let mut document: toml_edit::DocumentMut =
+        content.parse().map_err(|e| format!("Cannot edit invalid TOML: {}", e))?;
+    let (last, parents) = segments.split_last().unwrap_or_else(|| unreachable!("pointer has segments"));
+
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for (depth, segment) in parents.iter().enumerate() {
+        if table.get(segment).is_none() {
+            table.insert(segment, toml_edit::Item::Table(toml_edit::Table::new()));
+        }
+        table = table
+            .get_mut(segment)
+            .and_then(|item| item.as_table_like_mut())
+            .ok_or_else(|| format!("/{} is not a table", segments[..=depth].join("/")))?;
+    }
+
+    if value.is_null() {
+        table.remove(last);
+        return Ok(document.to_string());
+    }
+    let mut new_value = toml_value(value)?;
+    match table.get_mut(last) {
+        // Keep the spacing and trailing comment around the old value
+        Some(toml_edit::Item::Value(old)) => {
+            *new_value.decor_mut() = old.decor().clone();
+            *old = new_value;
+        }
+        Some(old @ toml_edit::Item::Table(_)) if value.is_object() => {
+            let inline = new_value.as_inline_table().cloned().unwrap_or_default();
+            let decor = old.as_table().map(|t| t.decor().clone()).unwrap_or_default();
+            let mut replacement = inline.into_table();
+            *replacement.decor_mut() = decor;
+            *old = toml_edit::Item::Table(replacement);
+        }
+        _ => {
+            table.insert(last, toml_edit::Item::Value(new_value));
+        }
+    }
+    Ok(document.to_string())
+}
+
+fn toml_value(value: &Value) -> Result<toml_edit::Value, String> {
+    Ok(match value {
+        Value::Null => return Err("TOML has no null values".to_string()),
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64().unwrap_or_default().into(),
+        },
+        Value::String(s) => s.as_str().into(),
+        Value::Array(items) => {
+            let mut array = toml_edit::Array::new();
+            for item in items {
+                array.push(toml_value(item)?);
+            }
+            array.into()
+        }
+        Value::Object(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, item) in map {
+                if !item.is_null() {
+                    table.insert(key, toml_value(item)?);
+                }
+            }
+            table.into()
+        }
+    })
+}
+
+// YAML
+
+/// A line of a block-style YAML document
+struct YamlLine<'a> {
+    indent: usize,
+    /// The line after its indentation
+    text: &'a str,
+    /// Offset of the first character after the indentation
+    offset: usize,
+}
+
+impl YamlLine<'_> {
+    /// Blank lines and comments don't affect structure
+    fn is_content(&self) -> bool {
+        !self.text.trim().is_empty() && !self.text.starts_with('#')
+    }
+
+    /// The mapping key on this line and where its value starts in `text`
+    fn key(&self) -> Option<(String, usize)> {
+        let text = self.text;
+        let (key, rest_start) = if let Some(quote @ ('"' | '\'')) = text.chars().next() {
+            let end = text[1..].find(quote)? + 1;
+            (text[1..end].to_string(), end + 1)
+        } else {
+            let end = text.find(": ").or_else(|| text.strip_suffix(':').map(|t| t.len()))?;
+            (text[..end].trim_end().to_string(), end)
+        };
+        let rest = &text[rest_start..];
+        rest.starts_with(':').then_some(())?;
+        (!text.starts_with("- ")).then_some((key, rest_start + 1))
+    }
+}
+
+fn yaml_lines(content: &str) -> Vec<YamlLine<'_>> {
+    let mut offset = 0;
+    content
+        .split_inclusive('\n')
+        .map(|raw| {
+            let line = raw.trim_end_matches(['\n', '\r']);
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            let parsed = YamlLine {
+                indent,
+                text: &line[indent..],
+                offset: offset + indent,
+            };
+            offset += raw.len();
+            parsed
+        })
+        .collect()
+}
+
+/// Index of the first content line in `range` and its indentation
+fn first_content(lines: &[YamlLine], range: std::ops::Range<usize>) -> Option<(usize, usize)> {
+    range.clone().find(|&i| lines[i].is_content()).map(|i| (i, lines[i].indent))
+}
+
+/// Lines after `index` that are nested under it
+fn yaml_block(lines: &[YamlLine], index: usize, end: usize) -> std::ops::Range<usize> {
+    let indent = lines[index].indent;
+    let mut last = index;
+    for (i, line) in lines.iter().enumerate().take(end).skip(index + 1) {
+        if line.is_content() {
+            if line.indent <= indent {
+                break;
+            }
+            last = i;
+        }
+    }
+    index + 1..last + 1
+}
+
+/// The line of `key` among the direct children in `range`
+fn yaml_find(lines: &[YamlLine], range: std::ops::Range<usize>, key: &str) -> Option<usize> {
+    let (_, indent) = first_content(lines, range.clone())?;
+    range
+        .filter(|&i| lines[i].is_content() && lines[i].indent == indent)
+        .find(|&i| lines[i].key().is_some_and(|(k, _)| k == key))
+}
+
+fn yaml_locate(content: &str, segments: &[String]) -> Option<usize> {
+    let lines = yaml_lines(content);
+    let mut range = 0..lines.len();
+    let mut found = first_content(&lines, range.clone()).map_or(0, |(i, _)| lines[i].offset);
+    for (depth, segment) in segments.iter().enumerate() {
+        let index = match yaml_find(&lines, range.clone(), segment) {
+            Some(index) => index,
+            None => {
+                // An item of a block sequence, written "- value"
+                let position: usize = segment.parse().ok()?;
+                let (_, indent) = first_content(&lines, range.clone())?;
+                let item = range
+                    .clone()
+                    .filter(|&i| lines[i].is_content() && lines[i].indent == indent && lines[i].text.starts_with('-'))
+                    .nth(position)?;
+                return (depth == segments.len() - 1).then(|| lines[item].offset + 2);
+            }
+        };
+        found = lines[index].offset;
+        range = yaml_block(&lines, index, range.end);
+    }
+    Some(found)
+}
+
+fn yaml_set(content: &str, segments: &[String], value: &Value) -> Result<String, String> {
+    serde_yaml::from_str::<serde_yaml::Value>(content).map_err(|e| format!("Cannot edit invalid YAML: {}", e))?;
+    let lines = yaml_lines(content);
+    let mut out: Vec<String> = content.lines().map(String::from).collect();
+
+    let mut range = 0..lines.len();
+    let mut parent_indent: Option<usize> = None;
+    for (depth, segment) in segments.iter().enumerate() {
+        let Some(index) = yaml_find(&lines, range.clone(), segment) else {
+            // Add the rest of the path after the parent's last line
+            let indent = first_content(&lines, range.clone())
+                .map(|(_, indent)| indent)
+                .unwrap_or_else(|| parent_indent.map_or(0, |i| i + 2));
+            let insert_at = (range.start..range.end)
+                .rev()
+                .find(|&i| lines[i].is_content())
+                .map_or(range.start, |i| i + 1);
+            let mut added = Vec::new();
+            for (level, key) in segments[depth..].iter().enumerate() {
+                let pad = " ".repeat(indent + level * 2);
+                if depth + level == segments.len() - 1 {
+                    added.push(format!("{}{}: {}", pad, yaml_key(key), yaml_scalar(value)));
+                } else {
+                    added.push(format!("{}{}:", pad, yaml_key(key)));
+                }
+            }
+            out.splice(insert_at..insert_at, added);
+            return Ok(join_lines(out, content));
+        };
+
+        if depth == segments.len() - 1 {
+            let line = &lines[index];
+            let (_, value_start) = line.key().unwrap_or_default();
+            let comment = yaml_comment(&line.text[value_start..]);
+            let block = yaml_block(&lines, index, range.end);
+            out[index] = format!(
+                "{}{}: {}{}",
+                " ".repeat(line.indent),
+                &line.text[..value_start - 1],
+                yaml_scalar(value),
+                comment
+            );
+            // A nested block that was the old value goes away
+            out.drain(block);
+            return Ok(join_lines(out, content));
+        }
+        parent_indent = Some(lines[index].indent);
+        range = yaml_block(&lines, index, range.end);
+    }
+    unreachable!("the loop returns at the last segment")
+}
+
+/// A trailing " # comment" in the value part of a line, kept when the value changes
+fn yaml_comment(value_part: &str) -> &str {
+    let mut quote = None;
+    let bytes = value_part.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        match (quote, b) {
+            (None, b'"' | b'\'') => quote = Some(b),
+            (Some(q), _) if b == q => quote = None,
+            (None, b'#') if i > 0 && bytes[i - 1] == b' ' => {
+                let start = value_part[..i].trim_end().len();
+                return &value_part[start..];
+            }
+            _ => {}
+        }
+    }
+    ""
+}
+
+fn yaml_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+        key.to_string()
+    } else {
+        serde_json::to_string(key).unwrap_or_default()
+    }
+}
+
+/// A value written on one line; lists and maps use flow style, which is JSON
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => {
+            let plain = !s.is_empty()
+                && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '@' | ' '))
+                && !s.starts_with([' ', '-', '@'])
+                && !s.ends_with(' ')
+                && serde_yaml::from_str::<serde_yaml::Value>(s).is_ok_and(|v| v.is_string());
+            if plain {
+                s.clone()
+            } else {
+                serde_json::to_string(s).unwrap_or_default()
+            }
+        }
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+/// Join edited lines, keeping the original's line ending and final newline
+fn join_lines(lines: Vec<String>, original: &str) -> String {
+    let newline = if original.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut text = lines.join(newline);
+    if original.is_empty() || original.ends_with('\n') {
+        text.push_str(newline);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const JSON_CONFIG: &str = r#"{
+  "model": "o4-mini",
+  "approval_policy": "sometimes",
+  "notify": ["say", 3],
+  "model_providers": {
+    "local": { "name": "Local", "base_url": 11434 }
+  }
+}"#;
+
+    const TOML_CONFIG: &str = r#"# Codex settings
+model = "o3"  # the default model
+approval_policy = "on-request"
+
+[model_providers.local]
+name = "Local"   # shown in the picker
+base_url = "http://localhost:11434/v1"
+"#;
+
+    const YAML_CONFIG: &str = "# Aider settings
+model: gpt-4o  # main model
+auto-commits: false
+lint-cmd:
+  - python: flake8
+editor:
+  theme: dark
+  font-size: 12
+";
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ConfigFormat::from_path(Path::new("~/.codex/config.toml")), ConfigFormat::Toml);
+        assert_eq!(ConfigFormat::from_path(Path::new(".aider.conf.yml")), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::from_path(Path::new("settings.json")), ConfigFormat::Json);
+    }
+
+    #[test]
+    fn test_parse_every_format() {
+        let toml = ConfigFormat::Toml.parse(TOML_CONFIG).unwrap();
+        assert_eq!(toml["model_providers"]["local"]["name"], "Local");
+        let yaml = ConfigFormat::Yaml.parse(YAML_CONFIG).unwrap();
+        assert_eq!(yaml["editor"]["font-size"], 12);
+        assert_eq!(ConfigFormat::Yaml.parse("").unwrap(), json!({}));
+
+        let err = ConfigFormat::Toml.parse("model = \n").unwrap_err();
+        assert_eq!(err.line, Some(1));
+        let err = ConfigFormat::Yaml.parse("a: b\n  c: [\n").unwrap_err();
+        assert!(err.line.is_some());
+    }
+
+    #[test]
+    fn test_locate() {
+        let json = ConfigFormat::Json;
+        assert_eq!(json.locate(JSON_CONFIG, ""), Some((1, 1)));
+        assert_eq!(json.locate(JSON_CONFIG, "/approval_policy"), Some((3, 3)));
+        assert_eq!(json.locate(JSON_CONFIG, "/notify/1"), Some((4, 21)));
+        assert_eq!(json.locate(JSON_CONFIG, "/model_providers/local/base_url"), Some((6, 33)));
+        assert_eq!(json.locate(JSON_CONFIG, "/missing"), None);
+        assert_eq!(json.locate(r#"{"a/b": {"~x": 1}}"#, "/a~1b/~0x"), Some((1, 10)));
+
+        assert_eq!(ConfigFormat::Toml.locate(TOML_CONFIG, "/approval_policy"), Some((3, 1)));
+        assert_eq!(ConfigFormat::Toml.locate(TOML_CONFIG, "/model_providers/local/base_url"), Some((7, 1)));
+
+        assert_eq!(ConfigFormat::Yaml.locate(YAML_CONFIG, "/editor/font-size"), Some((8, 3)));
+        assert_eq!(ConfigFormat::Yaml.locate(YAML_CONFIG, "/lint-cmd/0"), Some((5, 5)));
+        assert_eq!(ConfigFormat::Yaml.locate(YAML_CONFIG, "/editor/missing"), None);
+    }
+
+    #[test]
+    fn test_set_json_keeps_layout() {
+        let edited = ConfigFormat::Json.set(JSON_CONFIG, "/model", &json!("o3")).unwrap();
+        assert_eq!(edited, JSON_CONFIG.replace("\"o4-mini\"", "\"o3\""));
+
+        let edited = ConfigFormat::Json
+            .set(JSON_CONFIG, "/model_providers/remote/name", &json!("Remote"))
+            .unwrap();
+        assert!(edited.contains("\"base_url\": 11434 },\n    \"remote\": {\n      \"name\": \"Remote\"\n    }\n  }"), "{}", edited);
+        assert_eq!(serde_json::from_str::<Value>(&edited).unwrap()["model"], "o4-mini");
+
+        assert_eq!(ConfigFormat::Json.set("", "/model", &json!("o3")).unwrap(), "{\n  \"model\": \"o3\"\n}\n");
+    }
+
+    #[test]
+    fn test_set_toml_keeps_comments() {
+        let edited = ConfigFormat::Toml.set(TOML_CONFIG, "/model", &json!("o4-mini")).unwrap();
+        assert_eq!(edited, TOML_CONFIG.replace("\"o3\"", "\"o4-mini\""));
+
+        let edited = ConfigFormat::Toml
+            .set(TOML_CONFIG, "/model_providers/local/wire_api", &json!("chat"))
+            .unwrap();
+        assert!(edited.starts_with(TOML_CONFIG.trim_end()), "{}", edited);
+        assert!(edited.ends_with("wire_api = \"chat\"\n"), "{}", edited);
+
+        let edited = ConfigFormat::Toml.set(TOML_CONFIG, "/approval_policy", &Value::Null).unwrap();
+        assert!(!edited.contains("approval_policy"));
+        assert!(edited.contains("# the default model"));
+    }
+
+    #[test]
+    fn test_set_yaml_keeps_comments() {
+        let edited = ConfigFormat::Yaml.set(YAML_CONFIG, "/model", &json!("claude-sonnet-4")).unwrap();
+        assert_eq!(edited, YAML_CONFIG.replace("gpt-4o  #", "claude-sonnet-4  #"));
+
+        let edited = ConfigFormat::Yaml.set(YAML_CONFIG, "/editor/font-size", &json!(14)).unwrap();
+        assert_eq!(edited, YAML_CONFIG.replace("font-size: 12", "font-size: 14"));
+
+        let edited = ConfigFormat::Yaml.set(YAML_CONFIG, "/editor/keymap/save", &json!("ctrl s")).unwrap();
+        assert!(edited.ends_with("  font-size: 12\n  keymap:\n    save: ctrl s\n"), "{}", edited);
+
+        let edited = ConfigFormat::Yaml.set(YAML_CONFIG, "/lint-cmd", &json!(["ruff"])).unwrap();
+        assert!(edited.contains("lint-cmd: [\"ruff\"]\neditor:"), "{}", edited);
+        assert_eq!(ConfigFormat::Yaml.parse(&edited).unwrap()["lint-cmd"], json!(["ruff"]));
+
+        let edited = ConfigFormat::Yaml.set(YAML_CONFIG, "/auto-commits", &json!("true")).unwrap();
+        assert_eq!(ConfigFormat::Yaml.parse(&edited).unwrap()["auto-commits"], json!("true"));
+    }
+}
@@ -0,0 +1,267 @@
+// Config Location - expands adapter config paths and picks the candidate to use
+// Handles ~, $VAR, ${VAR}, ${VAR:-default} and %VAR%, with the XDG base directory defaults
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Candidate config paths by platform, in priority order
+pub type ConfigPaths = HashMap<String, Vec<String>>;
+
+/// Read config paths written either as one path or as a list of candidates
+pub fn deserialize_paths<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ConfigPaths, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Candidates {
+        One(String),
+        Many(Vec<String>),
+    }
+    let paths = HashMap::<String, Candidates>::deserialize(deserializer)?;
+    Ok(paths
+        .into_iter()
+        .map(|(platform, candidates)| match candidates {
+            Candidates::One(path) => (platform, vec![path]),
+            Candidates::Many(paths) => (platform, paths),
+        })
+        .collect())
+}
+
+/// One candidate path and what became of it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Candidate {
+    /// The path as the adapter writes it, e.g. "$XDG_CONFIG_HOME/claude/config.json"
+    pub template: String,
+    /// The expanded path, unless a variable it needs is unset
+    pub path: Option<String>,
+    pub exists: bool,
+    pub error: Option<String>,
+}
+
+/// Where a tool's config file is, and how it was chosen
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigLocation {
+    /// The file to read and write
+    pub path: String,
+    /// Index into `candidates` of the chosen one
+    pub index: usize,
+    /// Whether the file exists; if not, `path` is where it would be created
+    pub exists: bool,
+    pub candidates: Vec<Candidate>,
+}
+
+/// Pick the first candidate that exists, or else the first that expands
+pub fn locate(templates: &[String]) -> Result<ConfigLocation, String> {
+    let candidates: Vec<Candidate> = templates
+        .iter()
+        .map(|template| match expand(template) {
+            Ok(path) => Candidate {
+                template: template.clone(),
+                exists: Path::new(&path).is_file(),
+                path: Some(path),
+                error: None,
+            },
+            Err(e) => Candidate {
+                template: template.clone(),
+                path: None,
+                exists: false,
+                error: Some(e),
+            },
+        })
+        .collect();
+
+    let index = candidates
+        .iter()
+        .position(|c| c.exists)
+        .or_else(|| candidates.iter().position(|c| c.path.is_some()))
+        .ok_or_else(|| match candidates.first() {
+            Some(_) => {
+                let errors: Vec<&str> = candidates.iter().filter_map(|c| c.error.as_deref()).collect();
+                format!("No config path could be expanded: {}", errors.join("; "))
+            }
+            None => "No config paths listed".to_string(),
+        })?;
+    Ok(ConfigLocation {
+        path: candidates[index].path.clone().unwrap_or_default(),
+        index,
+        exists: candidates[index].exists,
+        candidates,
+    })
+}
+
+/// Expand a config path using the process environment
+pub fn expand(template: &str) -> Result<String, String> {
+    expand_with(template, &|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+}
+
+/// Expand `~` and environment variables, looking variables up with `lookup`
+///
+/// The XDG base directories fall back to their defaults under the home
+/// directory when unset, as the spec says. Any other unset variable is an
+/// error unless it has a `${VAR:-default}`.
+pub fn expand_with(template: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = template;
+
+    if let Some(after) = template.strip_prefix('~') {
+        if after.is_empty() || after.starts_with(['/', '\\']) {
+            out.push_str(&variable("HOME", lookup)?);
+            rest = after;
+        }
+    }
+
+    while let Some(i) = rest.find(['$', '%']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if let Some(braced) = tail.strip_prefix("${") {
+            let end = braced.find('}').ok_or_else(|| format!("Unclosed ${{ in '{}'", template))?;
+            let (name, default) = match braced[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&braced[..end], None),
+            };
+            if !is_name(name) {
+                return Err(format!("Invalid variable name '{}' in '{}'", name, template));
+            }
+            let value = match (variable(name, lookup), default) {
+                (Ok(value), _) => value,
+                (Err(_), Some(default)) => expand_with(default, lookup)?,
+                (Err(e), None) => return Err(e),
+            };
+            out.push_str(&value);
+            rest = &braced[end + 1..];
+        } else if let Some(after) = tail.strip_prefix('$') {
+            let len = name_len(after);
+            if len == 0 {
+                out.push('$');
+            } else {
+                out.push_str(&variable(&after[..len], lookup)?);
+            }
+            rest = &after[len..];
+        } else {
+            // %NAME% as on Windows; a lone % is literal
+            let after = &tail[1..];
+            match after.find('%').filter(|&end| is_name(&after[..end].replace(['(', ')'], ""))) {
+                Some(end) => {
+                    out.push_str(&variable(&after[..end], lookup)?);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    out.push('%');
+                    rest = after;
+                }
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Value of a variable, with the home directory and XDG defaults filled in
+fn variable(name: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    if let Some(value) = lookup(name) {
+        return Ok(value);
+    }
+    let home = || {
+        lookup("HOME")
+            .or_else(|| lookup("USERPROFILE"))
+            .ok_or_else(|| "Cannot find the home directory: HOME is not set".to_string())
+    };
+    let under_home = |relative: &str| home().map(|home| format!("{}/{}", home.trim_end_matches(['/', '\\']), relative));
+    match name {
+        "HOME" | "USERPROFILE" => home(),
+        "XDG_CONFIG_HOME" => under_home(".config"),
+        "XDG_DATA_HOME" => under_home(".local/share"),
+        "XDG_STATE_HOME" => under_home(".local/state"),
+        "XDG_CACHE_HOME" => under_home(".cache"),
+        _ => Err(format!("{} is not set", name)),
+    }
+}
+
+/// Length of the variable name at the start of `text`
+fn name_len(text: &str) -> usize {
+    let len = text
+        .char_indices()
+        .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '_'))
+        .map_or(text.len(), |(i, _)| i);
+    if text.starts_with(|c: char| c.is_ascii_digit()) {
+        0
+    } else {
+        len
+    }
+}
+
+fn is_name(text: &str) -> bool {
+    !text.is_empty() && name_len(text) == text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_expand() {
+        let lookup = env(&[("HOME", "/home/ada"), ("APPDATA", "C:\\Users\\ada\\AppData\\Roaming")]);
+        let expand = |template: &str| expand_with(template, &lookup);
+
+        assert_eq!(expand("~/.codex/config.toml").unwrap(), "/home/ada/.codex/config.toml");
+        assert_eq!(expand("~").unwrap(), "/home/ada");
+        assert_eq!(expand("~ada/x").unwrap(), "~ada/x");
+        assert_eq!(expand("$XDG_CONFIG_HOME/claude/config.json").unwrap(), "/home/ada/.config/claude/config.json");
+        assert_eq!(expand("${XDG_STATE_HOME}/tool.log").unwrap(), "/home/ada/.local/state/tool.log");
+        assert_eq!(expand("%APPDATA%\\claude\\config.json").unwrap(), "C:\\Users\\ada\\AppData\\Roaming\\claude\\config.json");
+        assert_eq!(expand("${CODEX_HOME:-~/.codex}/config.toml").unwrap(), "/home/ada/.codex/config.toml");
+        assert_eq!(expand("100%/$/x").unwrap(), "100%/$/x");
+
+        assert_eq!(expand("$CODEX_HOME/config.toml").unwrap_err(), "CODEX_HOME is not set");
+        assert!(expand("${HOME/x").is_err());
+        assert!(expand("~/x").is_ok());
+        assert!(expand_with("~/x", &env(&[])).is_err());
+
+        let lookup = env(&[("USERPROFILE", "C:\\Users\\ada"), ("CODEX_HOME", "D:\\codex")]);
+        assert_eq!(expand_with("~\\.claude.json", &lookup).unwrap(), "C:\\Users\\ada\\.claude.json");
+        assert_eq!(expand_with("${CODEX_HOME:-~/.codex}\\config.toml", &lookup).unwrap(), "D:\\codex\\config.toml");
+    }
+
+    #[test]
+    fn test_locate_prefers_existing_candidate() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let first = temp.path().join("first.json").display().to_string();
+        let second = temp.path().join("second.json").display().to_string();
+        let templates = vec![
+            "$CONFIG_LOCATION_TEST_UNSET/config.json".to_string(),
+            first.clone(),
+            second.clone(),
+        ];
+
+        let location = locate(&templates).unwrap();
+        assert_eq!((location.index, location.path.as_str(), location.exists), (1, first.as_str(), false));
+        assert_eq!(
+            location.candidates[0].error.as_deref(),
+            Some("CONFIG_LOCATION_TEST_UNSET is not set")
+        );
+
+        std::fs::write(&second, "{}").unwrap();
+        let location = locate(&templates).unwrap();
+        assert_eq!((location.index, location.path.as_str(), location.exists), (2, second.as_str(), true));
+
+        assert!(locate(&templates[..1]).is_err());
+        assert!(locate(&[]).is_err());
+    }
+
+    #[test]
+    fn test_paths_accept_one_or_many() {
+        #[derive(Deserialize)]
+        struct Adapter {
+            #[serde(deserialize_with = "deserialize_paths")]
+            config_paths: ConfigPaths,
+        }
+        let adapter: Adapter =
+            serde_json::from_str(r#"{"config_paths":{"linux":"~/.a.yml","macos":["~/b.json","~/.b.json"]}}"#).unwrap();
+        assert_eq!(adapter.config_paths["linux"], vec!["~/.a.yml"]);
+        assert_eq!(adapter.config_paths["macos"], vec!["~/b.json", "~/.b.json"]);
+    }
+}
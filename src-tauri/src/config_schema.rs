@@ -0,0 +1,188 @@
+// Config Schema - validates a tool's config file against the JSON Schema its adapter ships
+// Problems are reported with a JSON pointer and the line and column they start at
+
+use jsonschema::error::ValidationErrorKind;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config_format::ConfigFormat;
+
+/// How bad a config problem is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The tool would reject or misread the config; it isn't written
+    Error,
+    /// Probably a typo, such as a key the schema doesn't know
+    Warning,
+}
+
+/// One problem found in a config file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    /// JSON pointer to the offending value, "" for the whole document
+    pub pointer: String,
+    /// 1-based position of the value, or of the key for an unknown key
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    /// "line 3, column 5: /model: ..." for lists of plain messages
+    pub fn describe(&self) -> String {
+        let position = match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("line {}, column {}: ", line, column),
+            _ => String::new(),
+        };
+        let pointer = if self.pointer.is_empty() { "/" } else { self.pointer.as_str() };
+        format!("{}{}: {}", position, pointer, self.message)
+    }
+}
+
+/// The schema shipped for a built-in adapter's config file
+pub fn builtin_schema(tool_id: &str) -> Option<Value> {
+    let text = match tool_id {
+        "codex" => include_str!("../schemas/codex.schema.json"),
+        "claude-code" => include_str!("../schemas/claude-code.schema.json"),
+        "google-cli" => include_str!("../schemas/google-cli.schema.json"),
+        _ => return None,
+    };
+    serde_json::from_str(text).ok()
+}
+
+/// Why a schema can't be used, if it can't
+pub fn schema_problem(schema: &Value) -> Option<String> {
+    jsonschema::validator_for(schema).err().map(|e| e.to_string())
+}
+
+/// Check config text in `format`, against `schema` when there is one
+///
+/// Syntax errors and schema violations are errors; keys a schema's
+/// `additionalProperties: false` rules out are only warnings.
+pub fn validate(content: &str, format: ConfigFormat, schema: Option<&Value>) -> Vec<ConfigIssue> {
+    let document = match format.parse(content) {
+        Ok(document) => document,
+        Err(e) => {
+            return vec![ConfigIssue {
+                severity: Severity::Error,
+                pointer: String::new(),
+                line: e.line,
+                column: e.column,
+                message: format!("Invalid {}: {}", format.name(), e.message),
+            }]
+        }
+    };
+    let Some(schema) = schema else {
+        return Vec::new();
+    };
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(e) => {
+            return vec![ConfigIssue {
+                severity: Severity::Error,
+                pointer: String::new(),
+                line: None,
+                column: None,
+                message: format!("The adapter's config schema is invalid: {}", e),
+            }]
+        }
+    };
+
+    let mut issues = Vec::new();
+    for error in validator.iter_errors(&document) {
+        let pointer = error.instance_path.to_string();
+        match &error.kind {
+            ValidationErrorKind::AdditionalProperties { unexpected } => {
+                for key in unexpected {
+                    let key_pointer = format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+                    issues.push(issue(content, format, Severity::Warning, key_pointer, format!("Unknown key '{}'", key)));
+                }
+            }
+            _ => issues.push(issue(content, format, Severity::Error, pointer, error.to_string())),
+        }
+    }
+    issues.sort_by_key(|issue| (issue.line, issue.column));
+    issues
+}
+
+fn issue(content: &str, format: ConfigFormat, severity: Severity, pointer: String, message: String) -> ConfigIssue {
+    let (line, column) = match format.locate(content, &pointer) {
+        Some((line, column)) => (Some(line), Some(column)),
+        None => (None, None),
+    };
+    ConfigIssue {
+        severity,
+        pointer,
+        line,
+        column,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+  "model": "o4-mini",
+  "approval_policy": "sometimes",
+  "modle": "typo",
+  "notify": ["say", 3],
+  "model_providers": {
+    "local": { "name": "Local", "base_url": 11434 }
+  }
+}"#;
+
+    #[test]
+    fn test_schema_validation() {
+        let schema = builtin_schema("codex").unwrap();
+        assert_eq!(schema_problem(&schema), None);
+        let issues = validate(CONFIG, ConfigFormat::Json, Some(&schema));
+
+        let errors: Vec<&ConfigIssue> = issues.iter().filter(|i| i.severity == Severity::Error).collect();
+        let pointers: Vec<&str> = errors.iter().map(|i| i.pointer.as_str()).collect();
+        assert_eq!(
+            pointers,
+            vec!["/approval_policy", "/notify/1", "/model_providers/local/base_url"]
+        );
+        assert_eq!((errors[0].line, errors[0].column), (Some(3), Some(3)));
+
+        let warnings: Vec<&ConfigIssue> = issues.iter().filter(|i| i.severity == Severity::Warning).collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].pointer, "/modle");
+        assert_eq!(warnings[0].describe(), "line 4, column 3: /modle: Unknown key 'modle'");
+    }
+
+    #[test]
+    fn test_syntax_errors_and_no_schema() {
+        let issues = validate("{\n  \"model\": }", ConfigFormat::Json, None);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(2));
+        assert!(issues[0].message.starts_with("Invalid JSON"));
+
+        assert!(validate(r#"{"anything": 1}"#, ConfigFormat::Json, None).is_empty());
+        assert!(schema_problem(&serde_json::json!({"type": 5})).is_some());
+    }
+
+    #[test]
+    fn test_toml_validation() {
+        let schema = builtin_schema("codex").unwrap();
+        let config = "model = \"o3\"\n\n[model_providers.local]\nname = \"Local\"\nbase_url = 11434\nmodle = 1\n";
+        let issues = validate(config, ConfigFormat::Toml, Some(&schema));
+        let described: Vec<String> = issues.iter().map(|i| i.describe()).collect();
+        assert_eq!(issues.len(), 2, "{:?}", described);
+        assert_eq!(issues[0].pointer, "/model_providers/local/base_url");
+        assert_eq!((issues[0].line, issues[0].column), (Some(5), Some(1)));
+        assert_eq!(issues[1].severity, Severity::Warning);
+
+        let servers = "[mcp_servers.local]\ncommand = \"npx\"\n\n[mcp_servers.remote]\nurl = \"https://mcp.example.com/mcp\"\n\n[mcp_servers.broken]\nargs = []\n";
+        let issues = validate(servers, ConfigFormat::Toml, Some(&schema));
+        let pointers: Vec<&str> = issues.iter().map(|i| i.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["/mcp_servers/broken"]);
+
+        let issues = validate("model = \n", ConfigFormat::Toml, None);
+        assert!(issues[0].message.starts_with("Invalid TOML"), "{}", issues[0].message);
+    }
+}
@@ -1,8 +1,8 @@
 use crate::error::{AppError, AppResult};
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
 /// Session data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +37,21 @@ pub struct SearchResult {
     pub highlight: String,
 }
 
+/// Terminal recording metadata; the transcript itself is loaded on demand
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordingSummary {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub pid: u32,
+    pub tool_id: String,
+    /// Start time in milliseconds since the Unix epoch
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub width: u16,
+    pub height: u16,
+    pub event_count: u64,
+}
+
 /// Database connection wrapper
 pub struct DatabaseState {
     conn: Mutex<Connection>,
@@ -96,6 +111,28 @@ impl DatabaseState {
             [],
         )?;
 
+        // Create recordings table; session_id is NULL for sessions started outside a chat
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS recordings (
+                id TEXT PRIMARY KEY,
+                session_id TEXT,
+                pid INTEGER NOT NULL,
+                tool_id TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                width INTEGER NOT NULL,
+                height INTEGER NOT NULL,
+                event_count INTEGER NOT NULL,
+                transcript TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_recordings_session ON recordings(session_id)",
+            [],
+        )?;
+
         // Create FTS5 virtual table for full-text search
         conn.execute(
             "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
@@ -110,6 +147,17 @@ impl DatabaseState {
     }
 }
 
+/// Database the frontend initialized, where the backend saves what it records
+fn current_database_path() -> &'static Mutex<Option<PathBuf>> {
+    static DATABASE: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    DATABASE.get_or_init(|| Mutex::new(None))
+}
+
+/// The database set up by `init_database`, if it has run
+pub(crate) fn current_database() -> Option<PathBuf> {
+    current_database_path().lock().ok().and_then(|path| path.clone())
+}
+
 /// Initialize database
 #[tauri::command]
 pub async fn init_database(db_path: String) -> Result<(), String> {
@@ -121,12 +169,16 @@ pub async fn init_database(db_path: String) -> Result<(), String> {
             .map_err(|e| format!("Failed to create database directory: {}", e))?;
     }
 
-    let db_state = DatabaseState::new(path)
+    let db_state = DatabaseState::new(path.clone())
         .map_err(|e| format!("Failed to create database: {}", e))?;
     
     db_state.init_schema()
         .map_err(|e| format!("Failed to initialize schema: {}", e))?;
 
+    if let Ok(mut current) = current_database_path().lock() {
+        *current = Some(path);
+    }
+
     Ok(())
 }
 
@@ -206,8 +258,12 @@ pub async fn save_message(
     db_path: String,
     message: Message,
 ) -> Result<(), String> {
-    let path = PathBuf::from(db_path);
-    let db_state = DatabaseState::new(path)
+    insert_message(Path::new(&db_path), &message)
+}
+
+/// Insert a message and index it for search
+pub(crate) fn insert_message(db_path: &Path, message: &Message) -> Result<(), String> {
+    let db_state = DatabaseState::new(db_path.to_path_buf())
         .map_err(|e| format!("Failed to open database: {}", e))?;
 
     let conn = db_state.conn.lock()
@@ -336,6 +392,11 @@ pub async fn delete_session(
         params![session_id],
     ).map_err(|e| format!("Failed to delete messages: {}", e))?;
 
+    conn.execute(
+        "DELETE FROM recordings WHERE session_id = ?1",
+        params![session_id],
+    ).map_err(|e| format!("Failed to delete recordings: {}", e))?;
+
     // Delete session
     conn.execute(
         "DELETE FROM sessions WHERE id = ?1",
@@ -410,6 +471,102 @@ pub async fn export_session(
     }
 }
 
+/// Save a finished terminal recording
+pub(crate) fn save_recording(db_path: &Path, summary: &RecordingSummary, cast: &str) -> Result<(), String> {
+    let db_state = DatabaseState::new(db_path.to_path_buf())
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+
+    let conn = db_state.conn.lock()
+        .map_err(|e| format!("Failed to acquire lock: {}", e))?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO recordings
+         (id, session_id, pid, tool_id, started_at, duration_ms, width, height, event_count, transcript)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            summary.id,
+            summary.session_id,
+            summary.pid,
+            summary.tool_id,
+            summary.started_at,
+            summary.duration_ms,
+            summary.width,
+            summary.height,
+            summary.event_count,
+            cast,
+        ],
+    ).map_err(|e| format!("Failed to save recording: {}", e))?;
+
+    Ok(())
+}
+
+/// Load recording metadata, newest first, optionally for one session only
+pub(crate) fn query_recordings(db_path: &Path, session_id: Option<&str>) -> Result<Vec<RecordingSummary>, String> {
+    let db_state = DatabaseState::new(db_path.to_path_buf())
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+
+    let conn = db_state.conn.lock()
+        .map_err(|e| format!("Failed to acquire lock: {}", e))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, pid, tool_id, started_at, duration_ms, width, height, event_count
+         FROM recordings
+         WHERE ?1 IS NULL OR session_id = ?1
+         ORDER BY started_at DESC"
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let recordings = stmt.query_map(params![session_id], |row| {
+        Ok(RecordingSummary {
+            id: row.get(0)?,
+            session_id: row.get(1)?,
+            pid: row.get(2)?,
+            tool_id: row.get(3)?,
+            started_at: row.get(4)?,
+            duration_ms: row.get(5)?,
+            width: row.get(6)?,
+            height: row.get(7)?,
+            event_count: row.get(8)?,
+        })
+    }).map_err(|e| format!("Failed to query recordings: {}", e))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("Failed to collect recordings: {}", e))?;
+
+    Ok(recordings)
+}
+
+/// Load the asciicast transcript of a recording
+pub(crate) fn load_recording_cast(db_path: &Path, recording_id: &str) -> Result<String, String> {
+    let db_state = DatabaseState::new(db_path.to_path_buf())
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+
+    let conn = db_state.conn.lock()
+        .map_err(|e| format!("Failed to acquire lock: {}", e))?;
+
+    conn.query_row(
+        "SELECT transcript FROM recordings WHERE id = ?1",
+        params![recording_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to load recording {}: {}", recording_id, e))
+}
+
+/// List terminal recordings, optionally only those of one session
+#[tauri::command]
+pub async fn list_recordings(
+    db_path: String,
+    session_id: Option<String>,
+) -> Result<Vec<RecordingSummary>, String> {
+    query_recordings(Path::new(&db_path), session_id.as_deref())
+}
+
+/// Export a terminal recording as an asciicast v2 (.cast) document
+#[tauri::command]
+pub async fn export_recording(
+    db_path: String,
+    recording_id: String,
+) -> Result<String, String> {
+    load_recording_cast(Path::new(&db_path), &recording_id)
+}
+
 /// Export session to Markdown format
 fn export_to_markdown(session: &Session, messages: &[Message]) -> Result<String, String> {
     let mut output = String::new();
@@ -0,0 +1,26 @@
+// Events - pushes backend state changes to the frontend as Tauri events
+// The app handle is captured once during setup so background threads can emit
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter};
+
+/// App handle used for emitting events, set once during app setup
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+/// Register the app handle so events can be emitted from anywhere
+pub fn init(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+/// Emit an event to all webviews
+///
+/// Does nothing until `init` has been called, which keeps the process layer
+/// usable from unit tests without a running app.
+pub(crate) fn emit<S: Serialize + Clone>(event: &str, payload: S) {
+    if let Some(app) = APP_HANDLE.get() {
+        if let Err(e) = app.emit(event, payload) {
+            eprintln!("Warning: failed to emit {}: {}", event, e);
+        }
+    }
+}
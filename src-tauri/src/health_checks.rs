@@ -0,0 +1,347 @@
+// Health Checks - per-adapter checks beyond "is it installed"
+// Config file, stored credentials and an auth or status command, each reported as its own row
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::cli_adapter::CLIAdapter;
+use crate::config;
+use crate::secure_storage;
+use crate::spawn_env::{self, EnvSource};
+use crate::tool_detection;
+
+/// Time a check command gets unless it says otherwise
+const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A check an adapter runs as part of its health check
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthCheck {
+    #[serde(flatten)]
+    pub kind: CheckKind,
+    /// Label of the check's row; defaults to one derived from the kind
+    #[serde(default)]
+    pub name: Option<String>,
+    /// A failure degrades the tool instead of making it unhealthy
+    #[serde(default)]
+    pub optional: bool,
+    /// Shown when the check fails, in place of the default suggestion
+    #[serde(default)]
+    pub suggestion: Option<String>,
+}
+
+/// What a health check looks at
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CheckKind {
+    /// The adapter's config file, if there is one, can be read and passes validation
+    ConfigFile,
+    /// Credentials are in secure storage; by default every secret in the adapter's env
+    Credentials {
+        #[serde(default)]
+        keys: Vec<String>,
+    },
+    /// A cheap command such as "codex login status" exits successfully
+    Command {
+        command: String,
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+    },
+}
+
+impl HealthCheck {
+    pub fn new(kind: CheckKind, optional: bool) -> Self {
+        Self {
+            kind,
+            name: None,
+            optional,
+            suggestion: None,
+        }
+    }
+
+    fn name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        match &self.kind {
+            CheckKind::ConfigFile => "Config file".to_string(),
+            CheckKind::Credentials { .. } => "Credentials".to_string(),
+            CheckKind::Command { command, .. } => command.clone(),
+        }
+    }
+}
+
+/// Outcome of one check, shown as a row of the health report
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    /// Failures degrade rather than break the tool
+    pub optional: bool,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+/// Run every check of an adapter whose executable is at `executable`
+pub async fn run_checks(adapter: &CLIAdapter, executable: &Path) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+    for check in &adapter.health_checks {
+        let outcome = match &check.kind {
+            CheckKind::ConfigFile => check_config_file(adapter),
+            CheckKind::Credentials { keys } => check_credentials(adapter, keys).await,
+            CheckKind::Command { command, timeout_ms } => {
+                let timeout = timeout_ms.map(Duration::from_millis).unwrap_or(DEFAULT_COMMAND_TIMEOUT);
+                check_command(adapter, executable, command, timeout).await
+            }
+        };
+        let (passed, message, suggestion) = match outcome {
+            Ok(message) => (true, message, None),
+            Err((message, suggestion)) => (false, message, check.suggestion.clone().or(Some(suggestion))),
+        };
+        results.push(CheckResult {
+            name: check.name(),
+            passed,
+            optional: check.optional,
+            message,
+            suggestion,
+        });
+    }
+    results
+}
+
+/// A passing message, or a failure message with a suggestion
+type Outcome = Result<String, (String, String)>;
+
+fn check_config_file(adapter: &CLIAdapter) -> Outcome {
+    let path = config::config_file(adapter)
+        .map_err(|e| (e, format!("Add a config path for this platform to the {} adapter", adapter.id)))?;
+    let shown = path.display();
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        // Tools run on their defaults until a config is written
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(format!("{} does not exist; the tool uses its defaults", shown))
+        }
+        Err(e) => {
+            return Err((
+                format!("Cannot read {}: {}", shown, e),
+                format!("Check the permissions of {}", shown),
+            ))
+        }
+    };
+    let format = config::config_format(adapter, &path);
+    let validation = config::validate_content(&content, format, adapter.config_schema.as_ref());
+    if !validation.valid {
+        return Err((
+            format!("{} is invalid: {}", shown, validation.errors.join("; ")),
+            format!("Fix the errors in {}", shown),
+        ));
+    }
+    Ok(format!("{} is valid", shown))
+}
+
+async fn check_credentials(adapter: &CLIAdapter, keys: &[String]) -> Outcome {
+    let mut stored = HashSet::new();
+    for (key, _) in wanted_credentials(adapter, keys) {
+        if matches!(secure_storage::retrieve_credential(key.clone()).await, Ok(Some(_))) {
+            stored.insert(key);
+        }
+    }
+    credentials_with(adapter, keys, |key| stored.contains(key), |variable| std::env::var(variable).ok())
+}
+
+/// Credential keys to look for, each with the variable it is injected as, if the adapter maps one
+fn wanted_credentials(adapter: &CLIAdapter, keys: &[String]) -> Vec<(String, Option<String>)> {
+    let variable_for = |wanted: &str| {
+        adapter.env.iter().find_map(|(variable, source)| match source {
+            EnvSource::Secret { key, .. } if key == wanted => Some(variable.clone()),
+            _ => None,
+        })
+    };
+    if !keys.is_empty() {
+        return keys.iter().map(|key| (key.clone(), variable_for(key))).collect();
+    }
+    let mut secrets: Vec<(String, Option<String>)> = adapter
+        .env
+        .iter()
+        .filter_map(|(variable, source)| match source {
+            EnvSource::Secret { key, .. } => Some((key.clone(), Some(variable.clone()))),
+            EnvSource::Literal { .. } => None,
+        })
+        .collect();
+    secrets.sort();
+    secrets
+}
+
+/// Check credentials, asking `is_stored` about secure storage and `env` about exported variables
+fn credentials_with(
+    adapter: &CLIAdapter,
+    keys: &[String],
+    is_stored: impl Fn(&str) -> bool,
+    env: impl Fn(&str) -> Option<String>,
+) -> Outcome {
+    let wanted = wanted_credentials(adapter, keys);
+    if wanted.is_empty() {
+        return Ok("No credentials needed".to_string());
+    }
+
+    let mut missing = Vec::new();
+    for (key, variable) in &wanted {
+        // A key exported in the app's environment works as well as a stored one
+        let exported = variable
+            .as_deref()
+            .and_then(&env)
+            .is_some_and(|value| !value.is_empty());
+        if !is_stored(key) && !exported {
+            missing.push((key, variable));
+        }
+    }
+    if missing.is_empty() {
+        return Ok(format!("{} credential(s) available", wanted.len()));
+    }
+
+    let names: Vec<String> = missing.iter().map(|(key, _)| key.to_string()).collect();
+    let suggestion = match missing[0].1 {
+        Some(variable) => format!("Store {} in the credential manager or set {}", missing[0].0, variable),
+        None => format!("Store {} in the credential manager", missing[0].0),
+    };
+    Err((format!("Missing credentials: {}", names.join(", ")), suggestion))
+}
+
+async fn check_command(adapter: &CLIAdapter, executable: &Path, command: &str, timeout: Duration) -> Outcome {
+    let env = spawn_env::resolve(&adapter.env)
+        .await
+        .map_err(|e| (e, "Store the credentials the adapter's env refers to".to_string()))?;
+    match tool_detection::run_with_env(executable, command, timeout, &env).await {
+        Ok(output) => Ok(env.redact(output.trim())),
+        Err(e) => Err((
+            env.redact(&e),
+            format!("Run '{}' in a terminal; you may need to log in to {}", command, adapter.name),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::test_support::adapter_named;
+    #[cfg(unix)]
+    use crate::test_support::fake_executable;
+
+    fn platform() -> &'static str {
+        if cfg!(target_os = "windows") {
+            "windows"
+        } else if cfg!(target_os = "macos") {
+            "macos"
+        } else {
+            "linux"
+        }
+    }
+
+    fn adapter_with(checks: Vec<HealthCheck>) -> CLIAdapter {
+        let mut adapter = adapter_named("codex");
+        adapter.env = HashMap::new();
+        adapter.health_checks = checks;
+        adapter
+    }
+
+    #[test]
+    fn test_check_deserialization() {
+        let check: HealthCheck =
+            serde_json::from_str(r#"{"kind":"command","command":"tool auth status","optional":true}"#).unwrap();
+        assert_eq!(
+            check.kind,
+            CheckKind::Command {
+                command: "tool auth status".to_string(),
+                timeout_ms: None
+            }
+        );
+        assert!(check.optional);
+        assert_eq!(check.name(), "tool auth status");
+    }
+
+    #[tokio::test]
+    async fn test_config_file_check() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("config.toml");
+        let mut adapter = adapter_with(vec![HealthCheck::new(CheckKind::ConfigFile, false)]);
+        adapter.config_paths = HashMap::from([(platform().to_string(), vec![path.display().to_string()])]);
+
+        let results = run_checks(&adapter, Path::new("unused")).await;
+        assert!(results[0].passed);
+        assert!(results[0].message.contains("does not exist"), "{}", results[0].message);
+        assert_eq!(results[0].suggestion, None);
+
+        std::fs::write(&path, "model = ").unwrap();
+        let results = run_checks(&adapter, Path::new("unused")).await;
+        assert!(!results[0].passed);
+        assert!(results[0].message.contains("invalid"), "{}", results[0].message);
+        assert!(results[0].suggestion.is_some());
+
+        std::fs::write(&path, "model = \"o4-mini\" # default\n").unwrap();
+        let results = run_checks(&adapter, Path::new("unused")).await;
+        assert!(results[0].passed, "{}", results[0].message);
+        assert_eq!(results[0].suggestion, None);
+    }
+
+    #[test]
+    fn test_credentials_check() {
+        let mut adapter = adapter_with(Vec::new());
+        let check = |adapter: &CLIAdapter, keys: &[String]| {
+            credentials_with(
+                adapter,
+                keys,
+                |key| key == "stored",
+                |variable| (variable == "EXPORTED_KEY").then(|| "sk-test".to_string()),
+            )
+        };
+        assert_eq!(check(&adapter, &[]), Ok("No credentials needed".to_string()));
+
+        let secret = |key: &str| EnvSource::Secret {
+            key: key.to_string(),
+            optional: true,
+        };
+        adapter.env = HashMap::from([
+            ("EXPORTED_KEY".to_string(), secret("exported")),
+            ("STORED_KEY".to_string(), secret("stored")),
+            ("MISSING_KEY".to_string(), secret("missing")),
+        ]);
+        let (message, suggestion) = check(&adapter, &[]).unwrap_err();
+        assert_eq!(message, "Missing credentials: missing");
+        assert_eq!(suggestion, "Store missing in the credential manager or set MISSING_KEY");
+
+        let keys = ["stored".to_string(), "exported".to_string()];
+        assert_eq!(check(&adapter, &keys), Ok("2 credential(s) available".to_string()));
+        let (message, suggestion) = check(&adapter, &["unmapped".to_string()]).unwrap_err();
+        assert_eq!(message, "Missing credentials: unmapped");
+        assert_eq!(suggestion, "Store unmapped in the credential manager");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_check() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let executable = fake_executable(
+            temp.path(),
+            "auth-test-tool",
+            "if [ \"$1\" = status ]; then echo 'Logged in'; else echo 'Not logged in' >&2; exit 1; fi",
+        );
+
+        let command = |command: &str| CheckKind::Command {
+            command: command.to_string(),
+            timeout_ms: None,
+        };
+        let mut failing = HealthCheck::new(command("auth-test-tool whoami"), false);
+        failing.suggestion = Some("Run auth-test-tool login".to_string());
+        let adapter = adapter_with(vec![HealthCheck::new(command("auth-test-tool status"), false), failing]);
+
+        let results = run_checks(&adapter, &executable).await;
+        assert!(results[0].passed);
+        assert_eq!(results[0].message, "Logged in");
+        assert!(!results[1].passed);
+        assert!(results[1].message.contains("Not logged in"), "{}", results[1].message);
+        assert_eq!(results[1].suggestion.as_deref(), Some("Run auth-test-tool login"));
+    }
+}
@@ -0,0 +1,119 @@
+// Input - what the GUI can send to a running process
+// Encodes lines, raw bytes and named control keys for a pseudo-terminal or a plain pipe
+
+use serde::{Deserialize, Serialize};
+
+/// Input for `send_to_process`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ProcessInput {
+    /// Text followed by Enter
+    Line { text: String },
+    /// Bytes written exactly as given
+    Raw { bytes: Vec<u8> },
+    /// A named key such as Ctrl-C or an arrow key
+    Key { key: ControlKey },
+}
+
+/// Keys that interactive prompts respond to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlKey {
+    CtrlC,
+    CtrlD,
+    CtrlZ,
+    Esc,
+    Enter,
+    Tab,
+    ShiftTab,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    Right,
+    Left,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+impl ControlKey {
+    /// Bytes an xterm-compatible terminal sends for the key
+    pub fn sequence(&self) -> &'static [u8] {
+        match self {
+            ControlKey::CtrlC => b"\x03",
+            ControlKey::CtrlD => b"\x04",
+            ControlKey::CtrlZ => b"\x1a",
+            ControlKey::Esc => b"\x1b",
+            ControlKey::Enter => b"\r",
+            ControlKey::Tab => b"\t",
+            ControlKey::ShiftTab => b"\x1b[Z",
+            ControlKey::Backspace => b"\x7f",
+            ControlKey::Delete => b"\x1b[3~",
+            ControlKey::Up => b"\x1b[A",
+            ControlKey::Down => b"\x1b[B",
+            ControlKey::Right => b"\x1b[C",
+            ControlKey::Left => b"\x1b[D",
+            ControlKey::Home => b"\x1b[H",
+            ControlKey::End => b"\x1b[F",
+            ControlKey::PageUp => b"\x1b[5~",
+            ControlKey::PageDown => b"\x1b[6~",
+        }
+    }
+}
+
+impl ProcessInput {
+    /// Bytes to write to the process
+    ///
+    /// A terminal submits a line with a carriage return, a pipe with a newline.
+    pub fn encode(&self, terminal: bool) -> Vec<u8> {
+        match self {
+            ProcessInput::Line { text } => {
+                let mut bytes = text.as_bytes().to_vec();
+                bytes.push(if terminal { b'\r' } else { b'\n' });
+                bytes
+            }
+            ProcessInput::Raw { bytes } => bytes.clone(),
+            ProcessInput::Key { key: ControlKey::Enter } if !terminal => b"\n".to_vec(),
+            ProcessInput::Key { key } => key.sequence().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_line() {
+        let input = ProcessInput::Line {
+            text: "yes".to_string(),
+        };
+        assert_eq!(input.encode(true), b"yes\r");
+        assert_eq!(input.encode(false), b"yes\n");
+    }
+
+    #[test]
+    fn test_encode_keys() {
+        let key = |key| ProcessInput::Key { key };
+        assert_eq!(key(ControlKey::CtrlC).encode(true), b"\x03");
+        assert_eq!(key(ControlKey::Up).encode(true), b"\x1b[A");
+        assert_eq!(key(ControlKey::Enter).encode(true), b"\r");
+        assert_eq!(key(ControlKey::Enter).encode(false), b"\n");
+    }
+
+    #[test]
+    fn test_input_deserialization() {
+        let input: ProcessInput = serde_json::from_str(r#"{"mode":"key","key":"ctrl_c"}"#).unwrap();
+        assert_eq!(input, ProcessInput::Key { key: ControlKey::CtrlC });
+
+        let input: ProcessInput = serde_json::from_str(r#"{"mode":"raw","bytes":[27,91,65]}"#).unwrap();
+        assert_eq!(input.encode(true), ControlKey::Up.sequence());
+
+        let input: ProcessInput = serde_json::from_str(r#"{"mode":"line","text":"1"}"#).unwrap();
+        assert_eq!(input.encode(true), b"1\r");
+
+        assert!(serde_json::from_str::<ProcessInput>(r#"{"mode":"key","key":"ctrl_q"}"#).is_err());
+    }
+}
@@ -1,17 +1,40 @@
+mod adapter_registry;
 mod error;
+mod events;
 mod filesystem;
+mod health_checks;
+mod input;
+mod limits;
 mod process;
+mod process_store;
+mod prompt_runner;
+mod readiness;
+mod recording;
+mod runtime_id;
+mod runtime_instance;
+mod output;
 mod cli_adapter;
 mod config;
+mod config_backups;
+mod config_format;
+mod config_location;
+mod config_schema;
 mod mcp;
 mod token_estimator;
+mod tool_detection;
+mod tool_events;
+mod runtime_logs;
 mod runtime_monitor;
 mod database;
 mod secure_storage;
+mod spawn_env;
 mod store_service;
+mod supervisor;
 
 #[cfg(test)]
 mod store_service_test;
+#[cfg(test)]
+mod test_support;
 
 use tauri::Manager;
 
@@ -32,20 +55,37 @@ pub fn run() {
             token_estimator::estimate_tokens_batch,
             token_estimator::get_token_limit,
             process::spawn_cli_process,
+            process::resize_process,
             process::send_to_process,
             process::kill_process,
             process::get_process_output,
             process::start_runtime,
             process::stop_runtime,
             process::restart_runtime,
-            process::stream_process_output,
+            process::resume_process_output,
+            process_store::list_orphaned_processes,
+            process_store::adopt_orphaned_process,
+            process_store::cleanup_orphaned_process,
+            supervisor::get_supervisor_status,
+            supervisor::reset_supervisor,
+            output::set_output_limits,
+            output::get_output_limits,
             cli_adapter::get_available_adapters,
             cli_adapter::detect_cli_tool,
             cli_adapter::run_health_check,
+            adapter_registry::reload_adapters,
+            prompt_runner::run_prompt,
+            prompt_runner::cancel_prompt,
             config::read_tool_config,
             config::write_tool_config,
             config::validate_config,
+            config::set_tool_config_value,
             config::get_config_path,
+            config::locate_tool_config,
+            config_backups::list_config_history,
+            config_backups::read_config_version,
+            config_backups::diff_config_versions,
+            config_backups::restore_config_version,
             mcp::create_mcp_session,
             mcp::distribute_task,
             mcp::get_mcp_status,
@@ -53,6 +93,11 @@ pub fn run() {
             runtime_monitor::get_runtime_status,
             runtime_monitor::estimate_resource_usage,
             runtime_monitor::validate_runtime_path,
+            runtime_logs::list_runtime_logs,
+            runtime_logs::tail_runtime_log,
+            runtime_logs::search_runtime_logs,
+            runtime_logs::set_log_policy,
+            runtime_logs::get_log_policy,
             database::init_database,
             database::save_session,
             database::load_sessions,
@@ -61,6 +106,9 @@ pub fn run() {
             database::search_messages,
             database::delete_session,
             database::export_session,
+            database::list_recordings,
+            database::export_recording,
+            recording::replay_recording,
             secure_storage::store_credential,
             secure_storage::retrieve_credential,
             secure_storage::delete_credential,
@@ -75,6 +123,31 @@ pub fn run() {
             store_service::save_runtimes,
         ])
         .setup(|app| {
+            events::init(app.handle().clone());
+
+            match app.path().app_config_dir() {
+                Ok(dir) => adapter_registry::init(dir),
+                Err(e) => eprintln!("Warning: no app config dir for adapter files: {}", e),
+            }
+
+            match app.path().app_data_dir() {
+                Ok(dir) => {
+                    runtime_logs::init(dir.join(runtime_logs::LOG_DIR));
+                    config_backups::init(dir.join(config_backups::BACKUP_DIR));
+                    if let Err(e) = process_store::init(dir.join(process_store::PROCESS_FILE)) {
+                        eprintln!("Warning: failed to restore process registry: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Warning: no app data dir for the process registry: {}", e),
+            }
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = store_service::sync_runtime_settings(handle).await {
+                    eprintln!("Warning: failed to load runtime settings: {}", e);
+                }
+            });
+
             #[cfg(debug_assertions)]
             {
                 let window = app.get_webview_window("main").unwrap();
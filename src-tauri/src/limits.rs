@@ -0,0 +1,413 @@
+// Limits - resource limits for spawned runtimes and CLI tools
+// Uses rlimits on Unix and, where the cgroup v2 hierarchy is delegated to us, memory/CPU quotas
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+use crate::process::ExitStatusInfo;
+
+/// Extra CPU seconds between the soft limit (SIGXCPU) and the hard limit (SIGKILL)
+const CPU_HARD_LIMIT_SLACK_SECS: u64 = 5;
+
+/// Period used for cgroup CPU quotas, in microseconds
+const CGROUP_CPU_PERIOD_US: u64 = 100_000;
+
+/// Mount point of the unified cgroup v2 hierarchy
+#[cfg(target_os = "linux")]
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Optional resource limits for a spawned process
+///
+/// The rlimits apply on Unix. The cgroup quotas only apply on Linux with
+/// cgroup v2 and a cgroup the app may create children in; otherwise they are
+/// skipped with a warning.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResourceLimits {
+    /// Virtual address space in bytes (RLIMIT_AS)
+    #[serde(default)]
+    pub max_address_space_bytes: Option<u64>,
+    /// Resident set size in bytes (RLIMIT_RSS; only enforced by some kernels)
+    #[serde(default)]
+    pub max_rss_bytes: Option<u64>,
+    /// CPU time in seconds (RLIMIT_CPU)
+    #[serde(default)]
+    pub max_cpu_seconds: Option<u64>,
+    /// Number of open file descriptors (RLIMIT_NOFILE)
+    #[serde(default)]
+    pub max_open_files: Option<u64>,
+    /// cgroup v2 `memory.max` in bytes
+    #[serde(default)]
+    pub cgroup_memory_bytes: Option<u64>,
+    /// cgroup v2 CPU quota as a percentage of one core
+    #[serde(default)]
+    pub cgroup_cpu_percent: Option<u32>,
+}
+
+impl ResourceLimits {
+    /// Whether no limit is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn has_rlimits(&self) -> bool {
+        self.max_address_space_bytes.is_some()
+            || self.max_rss_bytes.is_some()
+            || self.max_cpu_seconds.is_some()
+            || self.max_open_files.is_some()
+    }
+
+    fn has_cgroup_limits(&self) -> bool {
+        self.cgroup_memory_bytes.is_some() || self.cgroup_cpu_percent.is_some()
+    }
+}
+
+/// Which limit a process ran into
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum LimitViolation {
+    /// CPU time limit reached (SIGXCPU)
+    CpuTime,
+    /// Killed by the cgroup OOM killer
+    Memory,
+}
+
+/// Limits and cgroup applied to a running process
+#[derive(Debug, Clone)]
+struct AppliedLimits {
+    limits: ResourceLimits,
+    cgroup: Option<PathBuf>,
+}
+
+/// Limits of every process spawned with limits, by PID
+fn applied_limits() -> &'static Mutex<HashMap<u32, AppliedLimits>> {
+    static APPLIED: OnceLock<Mutex<HashMap<u32, AppliedLimits>>> = OnceLock::new();
+    APPLIED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Type of the resource argument of `setrlimit`, which differs between libcs
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+/// rlimits as (resource, soft, hard) triples
+#[cfg(unix)]
+fn rlimit_values(limits: &ResourceLimits) -> Vec<(RlimitResource, u64, u64)> {
+    let mut values = Vec::new();
+    if let Some(bytes) = limits.max_address_space_bytes {
+        values.push((libc::RLIMIT_AS, bytes, bytes));
+    }
+    if let Some(bytes) = limits.max_rss_bytes {
+        values.push((libc::RLIMIT_RSS, bytes, bytes));
+    }
+    if let Some(secs) = limits.max_cpu_seconds {
+        // The soft limit sends SIGXCPU so the violation can be told apart
+        values.push((libc::RLIMIT_CPU, secs, secs + CPU_HARD_LIMIT_SLACK_SECS));
+    }
+    if let Some(files) = limits.max_open_files {
+        values.push((libc::RLIMIT_NOFILE, files, files));
+    }
+    values
+}
+
+/// Set rlimits on a command so they apply from the first instruction
+pub fn apply_to_command(command: &mut Command, limits: &ResourceLimits) {
+    #[cfg(unix)]
+    if limits.has_rlimits() {
+        use std::os::unix::process::CommandExt;
+
+        let values = rlimit_values(limits);
+        // Only async-signal-safe calls are allowed between fork and exec
+        unsafe {
+            command.pre_exec(move || {
+                for &(resource, soft, hard) in &values {
+                    let rlimit = libc::rlimit {
+                        rlim_cur: soft as libc::rlim_t,
+                        rlim_max: hard as libc::rlim_t,
+                    };
+                    if libc::setrlimit(resource, &rlimit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = command;
+    #[cfg(not(unix))]
+    if limits.has_rlimits() {
+        eprintln!("Warning: rlimits are not supported on this platform");
+    }
+}
+
+/// Wrap a pseudo-terminal command so its rlimits apply before it starts
+///
+/// Pseudo-terminal spawns offer no hook between fork and exec, so the
+/// executable is started through `/bin/sh`, which sets the limits with
+/// `ulimit` and then execs it under the same PID. `ulimit` counts memory in
+/// KiB, so byte limits are rounded down. Returns the executable and arguments
+/// to spawn.
+pub fn wrap_for_pty(executable: &str, args: &[String], limits: &ResourceLimits) -> (String, Vec<String>) {
+    #[cfg(unix)]
+    if limits.has_rlimits() {
+        let mut script = String::new();
+        for (resource, soft, hard) in rlimit_values(limits) {
+            let (flag, scale) = match resource {
+                libc::RLIMIT_AS => ('v', 1024),
+                libc::RLIMIT_RSS => ('m', 1024),
+                libc::RLIMIT_CPU => ('t', 1),
+                _ => ('n', 1),
+            };
+            // Soft first, so it never exceeds the hard limit being set
+            script.push_str(&format!(
+                "ulimit -S -{flag} {} && ulimit -H -{flag} {} && ",
+                soft / scale,
+                hard / scale
+            ));
+        }
+        script.push_str("exec \"$0\" \"$@\"");
+
+        let wrapped = ["-c".to_string(), script, executable.to_string()]
+            .into_iter()
+            .chain(args.iter().cloned())
+            .collect();
+        return ("/bin/sh".to_string(), wrapped);
+    }
+
+    #[cfg(not(unix))]
+    if limits.has_rlimits() {
+        eprintln!("Warning: rlimits are not supported on this platform");
+    }
+    (executable.to_string(), args.to_vec())
+}
+
+/// Put a spawned process under its cgroup quotas and remember its limits
+pub fn attach(pid: u32, limits: &ResourceLimits) {
+    if limits.is_empty() {
+        return;
+    }
+
+    let cgroup = if limits.has_cgroup_limits() {
+        match create_cgroup(pid, limits) {
+            Ok(path) => Some(path),
+            Err(e) => {
+                eprintln!("Warning: cgroup limits not applied to process {}: {}", pid, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    if let Ok(mut applied) = applied_limits().lock() {
+        applied.insert(
+            pid,
+            AppliedLimits {
+                limits: *limits,
+                cgroup,
+            },
+        );
+    }
+}
+
+/// Work out whether an exited process was stopped by one of its limits
+///
+/// Also removes the process's cgroup, so call this once after the exit.
+pub fn take_violation(pid: u32, exit: &ExitStatusInfo) -> Option<LimitViolation> {
+    let applied = applied_limits().lock().ok()?.remove(&pid)?;
+
+    let oom_killed = applied.cgroup.as_ref().is_some_and(|path| {
+        let killed = cgroup_oom_kills(path) > 0;
+        remove_cgroup(path);
+        killed
+    });
+
+    violation_from_exit(&applied.limits, exit, oom_killed)
+}
+
+fn violation_from_exit(
+    limits: &ResourceLimits,
+    exit: &ExitStatusInfo,
+    oom_killed: bool,
+) -> Option<LimitViolation> {
+    if oom_killed {
+        return Some(LimitViolation::Memory);
+    }
+
+    #[cfg(unix)]
+    if limits.max_cpu_seconds.is_some() && exit.signal == Some(libc::SIGXCPU) {
+        return Some(LimitViolation::CpuTime);
+    }
+
+    #[cfg(not(unix))]
+    let _ = (limits, exit);
+
+    None
+}
+
+/// `cpu.max` contents for a percentage of one core
+fn cpu_max(percent: u32) -> String {
+    let quota = CGROUP_CPU_PERIOD_US * u64::from(percent.max(1)) / 100;
+    format!("{} {}", quota, CGROUP_CPU_PERIOD_US)
+}
+
+/// Create a child cgroup for a process, set its quotas and move the process in
+#[cfg(target_os = "linux")]
+fn create_cgroup(pid: u32, limits: &ResourceLimits) -> Result<PathBuf, String> {
+    use std::fs;
+
+    let root = PathBuf::from(CGROUP_ROOT);
+    if !root.join("cgroup.controllers").exists() {
+        return Err("cgroup v2 is not available".to_string());
+    }
+
+    // Our own cgroup, from the "0::/path" line of /proc/self/cgroup
+    let own = fs::read_to_string("/proc/self/cgroup")
+        .map_err(|e| e.to_string())?
+        .lines()
+        .find_map(|line| line.strip_prefix("0::").map(str::to_string))
+        .ok_or_else(|| "process is not in a cgroup v2 hierarchy".to_string())?;
+
+    let path = root
+        .join(own.trim_start_matches('/'))
+        .join(format!("aitm-{}", pid));
+    fs::create_dir(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let configure = || -> std::io::Result<()> {
+        if let Some(bytes) = limits.cgroup_memory_bytes {
+            fs::write(path.join("memory.max"), bytes.to_string())?;
+        }
+        if let Some(percent) = limits.cgroup_cpu_percent {
+            fs::write(path.join("cpu.max"), cpu_max(percent))?;
+        }
+        fs::write(path.join("cgroup.procs"), pid.to_string())
+    };
+
+    if let Err(e) = configure() {
+        let _ = fs::remove_dir(&path);
+        return Err(format!("{}: {}", path.display(), e));
+    }
+    Ok(path)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn create_cgroup(_pid: u32, _limits: &ResourceLimits) -> Result<PathBuf, String> {
+    Err("cgroups are only supported on Linux".to_string())
+}
+
+/// Number of processes the OOM killer has killed in a cgroup
+fn cgroup_oom_kills(path: &std::path::Path) -> u64 {
+    std::fs::read_to_string(path.join("memory.events"))
+        .ok()
+        .and_then(|events| {
+            events.lines().find_map(|line| {
+                line.strip_prefix("oom_kill ")
+                    .and_then(|count| count.trim().parse().ok())
+            })
+        })
+        .unwrap_or(0)
+}
+
+/// Remove a process's cgroup once everything in it has exited
+fn remove_cgroup(path: &std::path::Path) {
+    if let Err(e) = std::fs::remove_dir(path) {
+        eprintln!("Warning: failed to remove cgroup {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_limits() {
+        assert!(ResourceLimits::default().is_empty());
+
+        let limits = ResourceLimits {
+            max_open_files: Some(64),
+            ..Default::default()
+        };
+        assert!(!limits.is_empty());
+        assert!(limits.has_rlimits());
+        assert!(!limits.has_cgroup_limits());
+    }
+
+    #[test]
+    fn test_cpu_max() {
+        assert_eq!(cpu_max(50), "50000 100000");
+        assert_eq!(cpu_max(200), "200000 100000");
+        assert_eq!(cpu_max(0), "1000 100000");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wrap_for_pty() {
+        let args = vec!["--model".to_string(), "o3".to_string()];
+        let (program, wrapped) = wrap_for_pty("codex", &args, &ResourceLimits::default());
+        assert_eq!((program.as_str(), wrapped), ("codex", args.clone()));
+
+        let limits = ResourceLimits {
+            max_address_space_bytes: Some(4 << 30),
+            max_cpu_seconds: Some(60),
+            cgroup_cpu_percent: Some(50),
+            ..Default::default()
+        };
+        let (program, wrapped) = wrap_for_pty("codex", &args, &limits);
+        assert_eq!(program, "/bin/sh");
+        assert_eq!(
+            wrapped,
+            [
+                "-c",
+                "ulimit -S -v 4194304 && ulimit -H -v 4194304 && ulimit -S -t 60 && ulimit -H -t 65 && exec \"$0\" \"$@\"",
+                "codex",
+                "--model",
+                "o3",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_violation_from_exit() {
+        let limits = ResourceLimits {
+            max_cpu_seconds: Some(1),
+            ..Default::default()
+        };
+        let xcpu = ExitStatusInfo {
+            code: None,
+            signal: Some(libc::SIGXCPU),
+        };
+        let clean = ExitStatusInfo {
+            code: Some(0),
+            signal: None,
+        };
+
+        assert_eq!(violation_from_exit(&limits, &xcpu, false), Some(LimitViolation::CpuTime));
+        assert_eq!(violation_from_exit(&limits, &clean, false), None);
+        assert_eq!(violation_from_exit(&limits, &clean, true), Some(LimitViolation::Memory));
+        assert_eq!(violation_from_exit(&ResourceLimits::default(), &xcpu, false), None);
+    }
+
+    #[test]
+    fn test_cgroup_oom_kills() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("memory.events"),
+            "low 0\nhigh 0\nmax 3\noom 1\noom_kill 1\n",
+        )
+        .unwrap();
+        assert_eq!(cgroup_oom_kills(dir.path()), 1);
+        assert_eq!(cgroup_oom_kills(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn test_limits_deserialize_partial() {
+        let limits: ResourceLimits = serde_json::from_str(r#"{"max_cpu_seconds": 60}"#).unwrap();
+        assert_eq!(limits.max_cpu_seconds, Some(60));
+        assert_eq!(limits.max_open_files, None);
+    }
+}
@@ -0,0 +1,585 @@
+// Process Output - captures child process output and streams it to the UI
+// Every chunk gets a per-process sequence number so a reloaded webview can
+// resume from the last sequence it saw without losing or duplicating lines.
+// Output is kept in a bounded ring buffer per process; the sequence number
+// doubles as the offset for paging through it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+use crate::events;
+use crate::runtime_logs;
+
+/// Event emitted for every captured chunk of process output
+pub const OUTPUT_EVENT: &str = "process://output";
+
+/// How many exited processes keep their output buffers
+///
+/// Beyond this the buffer of the process that exited first is dropped.
+const MAX_EXITED_BUFFERS: usize = 16;
+
+/// Which stream of the child a chunk came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// A single captured chunk of process output
+///
+/// Piped processes produce one chunk per line; PTY processes produce raw
+/// terminal chunks. `data` keeps the trailing newline so concatenating all
+/// chunks reproduces the original stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputLine {
+    pub pid: u32,
+    pub seq: u64,
+    pub stream: OutputStream,
+    pub data: String,
+    pub timestamp: u64,
+}
+
+/// Size caps for a process output buffer
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct OutputLimits {
+    pub max_bytes: usize,
+    pub max_lines: usize,
+}
+
+impl Default for OutputLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 8 * 1024 * 1024,
+            max_lines: 50_000,
+        }
+    }
+}
+
+/// A page of process output returned by offset-based retrieval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputPage {
+    pub lines: Vec<OutputLine>,
+    /// Offset to request the next page from
+    pub next_offset: u64,
+    /// Offset of the oldest line still held in the buffer
+    pub first_offset: u64,
+    /// Number of lines evicted from the buffer so far
+    pub dropped: u64,
+}
+
+/// Bounded ring buffer holding the output of a single process
+///
+/// The oldest lines are evicted once either cap is exceeded. The newest line
+/// is always kept, even if it alone is larger than `max_bytes`.
+#[derive(Debug)]
+pub(crate) struct OutputBuffer {
+    limits: OutputLimits,
+    lines: VecDeque<OutputLine>,
+    bytes: usize,
+    next_seq: u64,
+    dropped: u64,
+    /// Held from numbering a chunk until it is logged and emitted, so both
+    /// happen in sequence order without holding the store lock
+    in_order: Arc<Mutex<()>>,
+}
+
+impl OutputBuffer {
+    pub(crate) fn new(limits: OutputLimits) -> Self {
+        Self {
+            limits,
+            lines: VecDeque::new(),
+            bytes: 0,
+            next_seq: 0,
+            dropped: 0,
+            in_order: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Append a chunk, assigning it the next sequence number
+    fn push(&mut self, pid: u32, stream: OutputStream, data: String) -> OutputLine {
+        let line = OutputLine {
+            pid,
+            seq: self.next_seq,
+            stream,
+            data,
+            timestamp: now_millis(),
+        };
+        self.next_seq += 1;
+        self.bytes += line.data.len();
+        self.lines.push_back(line.clone());
+        self.evict();
+        line
+    }
+
+    /// Change the caps, evicting immediately if the buffer is now too large
+    fn set_limits(&mut self, limits: OutputLimits) {
+        self.limits = limits;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.lines.len() > 1
+            && (self.lines.len() > self.limits.max_lines || self.bytes > self.limits.max_bytes)
+        {
+            if let Some(old) = self.lines.pop_front() {
+                self.bytes -= old.data.len();
+                self.dropped += 1;
+            }
+        }
+    }
+
+    /// Sequence number of the oldest retained line
+    fn first_seq(&self) -> u64 {
+        self.lines.front().map(|l| l.seq).unwrap_or(self.next_seq)
+    }
+
+    /// Get up to `limit` lines starting at `offset`, optionally from one stream
+    ///
+    /// An offset that has already been evicted starts at the oldest retained line.
+    pub(crate) fn page(&self, offset: u64, limit: usize, stream: Option<OutputStream>) -> OutputPage {
+        let first_seq = self.first_seq();
+        let start = offset.saturating_sub(first_seq) as usize;
+
+        let mut lines = Vec::new();
+        let mut next_offset = self.next_seq.max(offset);
+        for line in self.lines.iter().skip(start) {
+            if stream.is_some_and(|s| s != line.stream) {
+                continue;
+            }
+            if lines.len() == limit {
+                next_offset = line.seq;
+                break;
+            }
+            lines.push(line.clone());
+        }
+
+        OutputPage {
+            lines,
+            next_offset,
+            first_offset: first_seq,
+            dropped: self.dropped,
+        }
+    }
+}
+
+/// Output buffers for processes, keyed by PID
+fn output_store() -> &'static Mutex<HashMap<u32, OutputBuffer>> {
+    static OUTPUT: OnceLock<Mutex<HashMap<u32, OutputBuffer>>> = OnceLock::new();
+    OUTPUT.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Caps applied to output buffers
+fn output_limits() -> &'static Mutex<OutputLimits> {
+    static LIMITS: OnceLock<Mutex<OutputLimits>> = OnceLock::new();
+    LIMITS.get_or_init(|| Mutex::new(OutputLimits::default()))
+}
+
+/// PIDs of exited processes whose buffers are still kept, oldest first
+fn exited_buffers() -> &'static Mutex<VecDeque<u32>> {
+    static EXITED: OnceLock<Mutex<VecDeque<u32>>> = OnceLock::new();
+    EXITED.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+/// Create an empty output buffer for a newly spawned process
+pub(crate) fn register(pid: u32) -> Result<(), String> {
+    let limits = *output_limits().lock().map_err(|e| e.to_string())?;
+    // A reused PID belongs to a live process again
+    exited_buffers().lock().map_err(|e| e.to_string())?.retain(|&p| p != pid);
+    output_store()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(pid, OutputBuffer::new(limits));
+    Ok(())
+}
+
+/// Drop the output buffer of a process
+pub(crate) fn unregister(pid: u32) {
+    if let Ok(mut exited) = exited_buffers().lock() {
+        exited.retain(|&p| p != pid);
+    }
+    if let Ok(mut store) = output_store().lock() {
+        store.remove(&pid);
+    }
+}
+
+/// Keep the output of a process that has exited, dropping the oldest
+/// exited buffers once there are more than `MAX_EXITED_BUFFERS`
+pub(crate) fn mark_exited(pid: u32) {
+    let dropped: Vec<u32> = {
+        let Ok(mut exited) = exited_buffers().lock() else {
+            return;
+        };
+        if !exited.contains(&pid) {
+            exited.push_back(pid);
+        }
+        let excess = exited.len().saturating_sub(MAX_EXITED_BUFFERS);
+        exited.drain(..excess).collect()
+    };
+    for pid in dropped {
+        unregister(pid);
+    }
+}
+
+/// Set the output caps for new and existing buffers
+#[tauri::command]
+pub fn set_output_limits(limits: OutputLimits) -> Result<(), String> {
+    if limits.max_bytes == 0 || limits.max_lines == 0 {
+        return Err("Output limits must be greater than zero".to_string());
+    }
+
+    *output_limits().lock().map_err(|e| e.to_string())? = limits;
+    for buffer in output_store().lock().map_err(|e| e.to_string())?.values_mut() {
+        buffer.set_limits(limits);
+    }
+    Ok(())
+}
+
+/// Get the current output caps
+#[tauri::command]
+pub fn get_output_limits() -> Result<OutputLimits, String> {
+    output_limits()
+        .lock()
+        .map(|limits| *limits)
+        .map_err(|e| e.to_string())
+}
+
+/// Append a chunk of output, emit it to the UI and write it to the runtime log
+///
+/// Only the buffer update holds the store lock, so slow disk I/O doesn't
+/// stall other processes. The process's own lock is held throughout, so its
+/// chunks reach the log and the UI in sequence order even with stdout and
+/// stderr readers racing.
+pub(crate) fn push(pid: u32, stream: OutputStream, data: String) {
+    let Some(in_order) = output_store()
+        .lock()
+        .ok()
+        .and_then(|store| store.get(&pid).map(|buffer| buffer.in_order.clone()))
+    else {
+        return;
+    };
+    let _in_order = in_order.lock().unwrap_or_else(|e| e.into_inner());
+
+    let line = {
+        let Ok(mut store) = output_store().lock() else {
+            return;
+        };
+        let Some(buffer) = store.get_mut(&pid) else {
+            return;
+        };
+        buffer.push(pid, stream, data)
+    };
+
+    runtime_logs::append(pid, stream, &line.data, line.timestamp);
+    events::emit(OUTPUT_EVENT, line);
+}
+
+/// Get a page of a process's output
+pub(crate) fn page(
+    pid: u32,
+    offset: u64,
+    limit: usize,
+    stream: Option<OutputStream>,
+) -> Option<OutputPage> {
+    let store = output_store().lock().ok()?;
+    store.get(&pid).map(|buffer| buffer.page(offset, limit, stream))
+}
+
+/// Get the output chunks of a process starting at a sequence number
+pub(crate) fn lines_from(pid: u32, from_seq: u64) -> Option<Vec<OutputLine>> {
+    page(pid, from_seq, usize::MAX, None).map(|page| page.lines)
+}
+
+/// The last `count` chunks of one stream of a process, without trailing newlines
+pub(crate) fn tail(pid: u32, stream: OutputStream, count: usize) -> Vec<String> {
+    let Ok(store) = output_store().lock() else {
+        return Vec::new();
+    };
+    let Some(buffer) = store.get(&pid) else {
+        return Vec::new();
+    };
+
+    let mut lines: Vec<String> = buffer
+        .lines
+        .iter()
+        .rev()
+        .filter(|line| line.stream == stream)
+        .take(count)
+        .map(|line| line.data.trim_end().to_string())
+        .collect();
+    lines.reverse();
+    lines
+}
+
+/// Number of reader threads still draining each process's pipes
+fn open_readers() -> &'static Mutex<HashMap<u32, usize>> {
+    static READERS: OnceLock<Mutex<HashMap<u32, usize>>> = OnceLock::new();
+    READERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Note that a reader of `pid` reached EOF
+///
+/// The process usually exits before its pipes are drained, so its runtime log
+/// is only detached once the last reader is done and every line is written.
+fn reader_closed(pid: u32) {
+    let Ok(mut readers) = open_readers().lock() else {
+        return;
+    };
+    let Some(count) = readers.get_mut(&pid) else {
+        return;
+    };
+    *count -= 1;
+    if *count == 0 {
+        readers.remove(&pid);
+        runtime_logs::detach(pid);
+    }
+}
+
+/// Read a process's piped streams line by line, each on a background thread
+///
+/// All readers are counted before any starts, so a stream that ends at once
+/// can't finish the process's output while another is still starting.
+pub(crate) fn spawn_line_readers(pid: u32, streams: Vec<(OutputStream, Box<dyn Read + Send>)>) {
+    if streams.is_empty() {
+        return;
+    }
+    if let Ok(mut readers) = open_readers().lock() {
+        *readers.entry(pid).or_default() += streams.len();
+    }
+    for (stream, reader) in streams {
+        spawn_line_reader(pid, stream, reader);
+    }
+}
+
+fn spawn_line_reader(pid: u32, stream: OutputStream, reader: Box<dyn Read + Send>) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut buf = Vec::new();
+
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => push(pid, stream, String::from_utf8_lossy(&buf).into_owned()),
+            }
+        }
+        reader_closed(pid);
+    });
+}
+
+/// Current time in milliseconds since the Unix epoch
+pub(crate) fn now_millis() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fake_pid;
+
+    #[test]
+    fn test_sequence_numbers_are_ordered() {
+        let pid = fake_pid();
+        register(pid).unwrap();
+
+        push(pid, OutputStream::Stdout, "one\n".to_string());
+        push(pid, OutputStream::Stderr, "two\n".to_string());
+        push(pid, OutputStream::Stdout, "three\n".to_string());
+
+        let lines = lines_from(pid, 0).unwrap();
+        let seqs: Vec<u64> = lines.iter().map(|l| l.seq).collect();
+        assert_eq!(seqs, vec![0, 1, 2]);
+        assert_eq!(lines[1].stream, OutputStream::Stderr);
+    }
+
+    #[test]
+    fn test_resume_from_sequence() {
+        let pid = fake_pid();
+        register(pid).unwrap();
+
+        for i in 0..5 {
+            push(pid, OutputStream::Stdout, format!("line {}\n", i));
+        }
+
+        let resumed = lines_from(pid, 3).unwrap();
+        assert_eq!(resumed.len(), 2);
+        assert_eq!(resumed[0].data, "line 3\n");
+        assert!(lines_from(pid, 5).unwrap().is_empty());
+    }
+
+    fn line_data(page: &OutputPage) -> Vec<&str> {
+        page.lines.iter().map(|l| l.data.as_str()).collect()
+    }
+
+    #[test]
+    fn test_ring_buffer_line_cap() {
+        let mut buffer = OutputBuffer::new(OutputLimits {
+            max_bytes: 1024,
+            max_lines: 3,
+        });
+        for i in 0..5 {
+            buffer.push(1, OutputStream::Stdout, format!("{}\n", i));
+        }
+
+        let page = buffer.page(0, usize::MAX, None);
+        assert_eq!(line_data(&page), vec!["2\n", "3\n", "4\n"]);
+        assert_eq!(page.dropped, 2);
+        assert_eq!(page.first_offset, 2);
+        assert_eq!(page.next_offset, 5);
+    }
+
+    #[test]
+    fn test_ring_buffer_byte_cap() {
+        let mut buffer = OutputBuffer::new(OutputLimits {
+            max_bytes: 10,
+            max_lines: 100,
+        });
+        buffer.push(1, OutputStream::Stdout, "aaaa\n".to_string());
+        buffer.push(1, OutputStream::Stdout, "bbbb\n".to_string());
+        buffer.push(1, OutputStream::Stdout, "cccc\n".to_string());
+
+        let page = buffer.page(0, usize::MAX, None);
+        assert_eq!(line_data(&page), vec!["bbbb\n", "cccc\n"]);
+        assert_eq!(page.dropped, 1);
+
+        // A single oversized chunk is still kept
+        buffer.push(1, OutputStream::Stdout, "x".repeat(64));
+        let page = buffer.page(0, usize::MAX, None);
+        assert_eq!(page.lines.len(), 1);
+        assert_eq!(page.dropped, 3);
+    }
+
+    #[test]
+    fn test_page_offset_limit_and_stream_filter() {
+        let mut buffer = OutputBuffer::new(OutputLimits::default());
+        for i in 0..6 {
+            let stream = if i % 2 == 0 {
+                OutputStream::Stdout
+            } else {
+                OutputStream::Stderr
+            };
+            buffer.push(1, stream, format!("{}\n", i));
+        }
+
+        let page = buffer.page(1, 2, None);
+        assert_eq!(line_data(&page), vec!["1\n", "2\n"]);
+        assert_eq!(page.next_offset, 3);
+
+        let page = buffer.page(0, 2, Some(OutputStream::Stderr));
+        assert_eq!(line_data(&page), vec!["1\n", "3\n"]);
+        assert_eq!(page.next_offset, 5);
+
+        let page = buffer.page(page.next_offset, 2, Some(OutputStream::Stderr));
+        assert_eq!(line_data(&page), vec!["5\n"]);
+        assert_eq!(page.next_offset, 6);
+    }
+
+    #[test]
+    fn test_set_limits_trims_existing_buffer() {
+        let mut buffer = OutputBuffer::new(OutputLimits::default());
+        for i in 0..10 {
+            buffer.push(1, OutputStream::Stdout, format!("{}\n", i));
+        }
+
+        buffer.set_limits(OutputLimits {
+            max_bytes: 1024,
+            max_lines: 4,
+        });
+
+        let page = buffer.page(0, usize::MAX, None);
+        assert_eq!(page.lines.len(), 4);
+        assert_eq!(page.first_offset, 6);
+        assert_eq!(page.dropped, 6);
+    }
+
+    #[test]
+    fn test_push_to_unregistered_process_is_ignored() {
+        let pid = fake_pid();
+        push(pid, OutputStream::Stdout, "lost\n".to_string());
+        assert!(lines_from(pid, 0).is_none());
+    }
+
+    #[test]
+    fn test_exited_buffers_are_capped() {
+        let pids: Vec<u32> = (0..=MAX_EXITED_BUFFERS).map(|_| fake_pid()).collect();
+        for &pid in &pids {
+            register(pid).unwrap();
+        }
+        let reused = pids[1];
+        mark_exited(pids[0]);
+        mark_exited(reused);
+        register(reused).unwrap();
+        for &pid in &pids[2..] {
+            mark_exited(pid);
+        }
+        mark_exited(fake_pid());
+
+        // Other tests mark exits too, so only the oldest buffer is sure to be gone
+        assert!(lines_from(pids[0], 0).is_none());
+        assert!(lines_from(reused, 0).is_some());
+        for pid in pids {
+            unregister(pid);
+        }
+    }
+
+    #[test]
+    fn test_line_reader_splits_lines() {
+        let pid = fake_pid();
+        register(pid).unwrap();
+
+        spawn_line_readers(pid, vec![(OutputStream::Stderr, Box::new(&b"first\nsecond\npartial"[..]))]);
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while lines_from(pid, 0).unwrap().len() < 3 && std::time::Instant::now() < deadline {
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let lines = lines_from(pid, 0).unwrap();
+        let data: Vec<&str> = lines.iter().map(|l| l.data.as_str()).collect();
+        assert_eq!(data, vec!["first\n", "second\n", "partial"]);
+        assert!(lines.iter().all(|l| l.stream == OutputStream::Stderr));
+    }
+}
+
+#[cfg(test)]
+mod property_tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(50))]
+
+        // The buffer never exceeds its caps, keeps a contiguous run of the
+        // newest lines, and accounts for every evicted line
+        #[test]
+        fn property_ring_buffer_bounded(
+            chunks in prop::collection::vec("[a-z]{0,20}\n", 1..200),
+            max_bytes in 1usize..256,
+            max_lines in 1usize..32
+        ) {
+            let mut buffer = OutputBuffer::new(OutputLimits { max_bytes, max_lines });
+            for chunk in &chunks {
+                buffer.push(1, OutputStream::Stdout, chunk.clone());
+            }
+
+            let page = buffer.page(0, usize::MAX, None);
+            prop_assert!(page.lines.len() <= max_lines);
+            if page.lines.len() > 1 {
+                let bytes: usize = page.lines.iter().map(|l| l.data.len()).sum();
+                prop_assert!(bytes <= max_bytes);
+            }
+            prop_assert_eq!(page.dropped + page.lines.len() as u64, chunks.len() as u64);
+            prop_assert_eq!(page.next_offset, chunks.len() as u64);
+            for (i, line) in page.lines.iter().enumerate() {
+                prop_assert_eq!(line.seq, page.first_offset + i as u64);
+                prop_assert_eq!(&line.data, &chunks[line.seq as usize]);
+            }
+        }
+    }
+}
@@ -1,11 +1,48 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, OnceLock};
-use std::process::{Command, Stdio};
-use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::io::{Read, Write};
+use std::path::Path;
 use std::thread;
+use std::time::{Duration, Instant};
+use portable_pty::{native_pty_system, Child as PtyChild, CommandBuilder, MasterPty, PtySize};
 
+use crate::cli_adapter::get_available_adapters;
 use crate::error::AppError;
+use crate::events;
+use crate::input::{ControlKey, ProcessInput};
+use crate::limits::{self, LimitViolation, ResourceLimits};
+use crate::output::{self, OutputLine, OutputPage, OutputStream};
+use crate::process_store;
+use crate::readiness::{self, Readiness};
+use crate::recording::{self, CastEventKind};
+use crate::runtime_id::{RuntimeId, RuntimeKind};
+use crate::runtime_instance;
+use crate::runtime_logs;
+use crate::spawn_env::{self, EnvMappings, ResolvedEnv};
+use crate::supervisor;
+use crate::tool_detection;
+use crate::tool_events;
+
+/// Default terminal size used until the UI reports its real dimensions
+const DEFAULT_PTY_ROWS: u16 = 24;
+const DEFAULT_PTY_COLS: u16 = 80;
+
+/// Time a process gets to exit after SIGTERM before it is killed
+const DEFAULT_STOP_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Time to wait for a killed process to be reaped
+const KILL_REAP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Interval between checks for process exit
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Interval at which the reaper checks all children for exit
+const REAPER_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Event emitted whenever a process changes state
+pub const LIFECYCLE_EVENT: &str = "process://lifecycle";
 
 /// Process information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,12 +51,83 @@ pub struct ProcessInfo {
     pub tool_id: String,
     pub working_dir: String,
     pub status: ProcessStatus,
+    #[serde(default)]
+    pub runtime_id: Option<RuntimeId>,
+    #[serde(default)]
+    pub exit_status: Option<ExitStatusInfo>,
+    /// Start time in milliseconds since the Unix epoch
+    #[serde(default)]
+    pub started_at: u64,
+    /// Exit time in milliseconds since the Unix epoch
+    #[serde(default)]
+    pub ended_at: Option<u64>,
+    /// Number of times the runtime was restarted before this process
+    #[serde(default)]
+    pub restart_count: u32,
+    /// Executable followed by its arguments
+    #[serde(default)]
+    pub command_line: Vec<String>,
+    /// Resource limit that ended the process, if any
+    #[serde(default)]
+    pub limit_violation: Option<LimitViolation>,
+}
+
+/// Payload of a lifecycle event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleEvent {
+    pub previous: Option<ProcessStatus>,
+    pub process: ProcessInfo,
+}
+
+/// How a process exited: a normal exit code or the signal that ended it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExitStatusInfo {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+impl From<std::process::ExitStatus> for ExitStatusInfo {
+    fn from(status: std::process::ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+
+        Self {
+            code: status.code(),
+            signal,
+        }
+    }
+}
+
+impl ExitStatusInfo {
+    /// Whether the process exited cleanly with code 0
+    pub fn success(&self) -> bool {
+        self.code == Some(0) && self.signal.is_none()
+    }
+
+    /// Whether neither the exit code nor the signal is known
+    ///
+    /// This is the case for processes we didn't spawn ourselves, such as
+    /// runtimes adopted after an app restart.
+    pub fn is_unknown(&self) -> bool {
+        self.code.is_none() && self.signal.is_none()
+    }
 }
 
+/// Process lifecycle state
+///
+/// `Stopped` means the app stopped the process; `Exited` and `Crashed` mean it
+/// ended on its own, cleanly or with a non-zero code or signal.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ProcessStatus {
     Running,
+    Stopping,
     Stopped,
+    Exited,
+    Crashed,
+    /// Ended by one of its resource limits; see `ProcessInfo::limit_violation`
+    LimitExceeded,
     Error,
 }
 
@@ -29,10 +137,308 @@ fn process_registry() -> &'static Mutex<HashMap<u32, ProcessInfo>> {
     REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
-/// Output buffer for processes
-fn process_output() -> &'static Mutex<HashMap<u32, String>> {
-    static OUTPUT: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
-    OUTPUT.get_or_init(|| Mutex::new(HashMap::new()))
+/// Add a newly spawned process to the registry and announce it
+fn register_process(process_info: ProcessInfo) -> Result<(), String> {
+    let pid = process_info.pid;
+    process_registry()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(pid, process_info.clone());
+
+    events::emit(
+        LIFECYCLE_EVENT,
+        LifecycleEvent {
+            previous: None,
+            process: process_info,
+        },
+    );
+    process_store::record_identity(pid);
+    process_store::persist();
+    ensure_reaper();
+    Ok(())
+}
+
+/// Move a process to a new state and emit a lifecycle event
+fn set_status(pid: u32, status: ProcessStatus) -> Option<ProcessInfo> {
+    let mut registry = process_registry().lock().ok()?;
+    let process = registry.get_mut(&pid)?;
+    if process.status == status {
+        return Some(process.clone());
+    }
+
+    let previous = std::mem::replace(&mut process.status, status);
+    let process = process.clone();
+    drop(registry);
+
+    events::emit(
+        LIFECYCLE_EVENT,
+        LifecycleEvent {
+            previous: Some(previous),
+            process: process.clone(),
+        },
+    );
+    Some(process)
+}
+
+/// Current status of a registered process
+pub(crate) fn status_of(pid: u32) -> Option<ProcessStatus> {
+    let registry = process_registry().lock().ok()?;
+    registry.get(&pid).map(|p| p.status.clone())
+}
+
+/// Record how a process exited and move it to its final state
+///
+/// A process we were stopping ends up `Stopped`; anything else that exits is
+/// `LimitExceeded` if a resource limit ended it, `Exited` on a clean or unknown
+/// exit and `Crashed` otherwise.
+fn record_exit(pid: u32, exit: ExitStatusInfo) {
+    let violation = limits::take_violation(pid, &exit);
+
+    // The final status is set along with the exit status, so anyone who sees
+    // the process has exited also sees where it ended up
+    let (previous, process) = {
+        let Ok(mut registry) = process_registry().lock() else {
+            return;
+        };
+        let Some(process) = registry.get_mut(&pid) else {
+            return;
+        };
+
+        process.exit_status = Some(exit.clone());
+        process.ended_at = Some(output::now_millis());
+        process.limit_violation = violation;
+
+        let status = if process.status == ProcessStatus::Stopping {
+            ProcessStatus::Stopped
+        } else if violation.is_some() {
+            ProcessStatus::LimitExceeded
+        } else if exit.success() || exit.is_unknown() {
+            ProcessStatus::Exited
+        } else {
+            ProcessStatus::Crashed
+        };
+        (std::mem::replace(&mut process.status, status), process.clone())
+    };
+
+    // Closing the PTY master lets the output reader see EOF
+    if let Ok(mut handles) = pty_handles().lock() {
+        handles.remove(&pid);
+    }
+    if let Ok(mut writers) = input_writers().lock() {
+        writers.remove(&pid);
+    }
+    output::mark_exited(pid);
+
+    if previous != process.status {
+        events::emit(
+            LIFECYCLE_EVENT,
+            LifecycleEvent {
+                previous: Some(previous),
+                process: process.clone(),
+            },
+        );
+    }
+    process_store::persist();
+    supervisor::on_exit(&process);
+}
+
+/// Reap a child if it has exited, recording its exit status
+///
+/// Returns the exit status once the child is gone. A child that was already
+/// reaped elsewhere, or an adopted process that is no longer alive, is
+/// reported with an unknown status.
+fn reap_child(pid: u32) -> Option<ExitStatusInfo> {
+    let exit = match try_wait_child(pid) {
+        Some(exit) => exit,
+        None => check_adopted(pid)?,
+    };
+
+    record_exit(pid, exit.clone());
+    Some(exit)
+}
+
+/// Collect the exit status of one of our children without blocking
+fn try_wait_child(pid: u32) -> Option<ExitStatusInfo> {
+    let mut children = child_handles().lock().ok()?;
+    let child = children.get_mut(&pid)?;
+
+    let exit = match child.try_wait() {
+        Ok(Some(exit)) => exit,
+        Ok(None) => return None,
+        Err(_) => ExitStatusInfo {
+            code: None,
+            signal: None,
+        },
+    };
+    children.remove(&pid);
+    Some(exit)
+}
+
+/// Check whether an adopted process has gone away
+///
+/// Adopted processes aren't our children, so all we can observe is that the
+/// PID no longer belongs to the process we adopted.
+fn check_adopted(pid: u32) -> Option<ExitStatusInfo> {
+    let mut adopted = adopted_processes().lock().ok()?;
+    let os_start_time = *adopted.get(&pid)?;
+    if process_store::is_alive(pid, os_start_time) {
+        return None;
+    }
+
+    adopted.remove(&pid);
+    Some(ExitStatusInfo {
+        code: None,
+        signal: None,
+    })
+}
+
+/// Processes started by an earlier run of the app, by PID, with their OS start time
+fn adopted_processes() -> &'static Mutex<HashMap<u32, u64>> {
+    static ADOPTED: OnceLock<Mutex<HashMap<u32, u64>>> = OnceLock::new();
+    ADOPTED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Take over a runtime that survived an app restart
+///
+/// The process is registered as running so it can be listed and stopped, and
+/// the reaper watches it until it disappears. Its earlier output is not
+/// available.
+pub(crate) fn adopt_process(process_info: ProcessInfo, os_start_time: u64) -> Result<ProcessInfo, String> {
+    let pid = process_info.pid;
+    let process_info = ProcessInfo {
+        status: ProcessStatus::Running,
+        exit_status: None,
+        ended_at: None,
+        ..process_info
+    };
+
+    output::register(pid)?;
+    adopted_processes()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(pid, os_start_time);
+    register_process(process_info.clone())?;
+
+    Ok(process_info)
+}
+
+/// Processes that are still running or being stopped
+pub(crate) fn live_processes() -> Vec<ProcessInfo> {
+    process_registry()
+        .lock()
+        .map(|registry| {
+            registry
+                .values()
+                .filter(|p| matches!(p.status, ProcessStatus::Running | ProcessStatus::Stopping))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Start the background reaper if it isn't running yet
+///
+/// The reaper waits on every child we spawned so crashed processes don't stay
+/// "Running" and don't linger as zombies. Adopted processes are polled for
+/// liveness instead.
+fn ensure_reaper() {
+    static REAPER: OnceLock<()> = OnceLock::new();
+    REAPER.get_or_init(|| {
+        thread::spawn(|| loop {
+            let mut pids: Vec<u32> = match child_handles().lock() {
+                Ok(children) => children.keys().copied().collect(),
+                Err(_) => Vec::new(),
+            };
+            if let Ok(adopted) = adopted_processes().lock() {
+                pids.extend(adopted.keys());
+            }
+            for pid in pids {
+                reap_child(pid);
+            }
+            thread::sleep(REAPER_INTERVAL);
+        });
+    });
+}
+
+/// How a runtime was launched, kept so it can be started again
+#[derive(Debug, Clone, Default)]
+struct RuntimeLaunch {
+    executable_path: String,
+    args: Vec<String>,
+    working_dir: Option<String>,
+    env: EnvMappings,
+    limits: Option<ResourceLimits>,
+    readiness: Option<Readiness>,
+}
+
+/// Launch parameters of every runtime started so far
+fn runtime_launches() -> &'static Mutex<HashMap<RuntimeId, RuntimeLaunch>> {
+    static LAUNCHES: OnceLock<Mutex<HashMap<RuntimeId, RuntimeLaunch>>> = OnceLock::new();
+    LAUNCHES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Pseudo-terminal handle for a CLI process
+struct PtyHandle {
+    master: Box<dyn MasterPty + Send>,
+}
+
+/// PTY handles for CLI processes, keyed by OS PID
+fn pty_handles() -> &'static Mutex<HashMap<u32, PtyHandle>> {
+    static HANDLES: OnceLock<Mutex<HashMap<u32, PtyHandle>>> = OnceLock::new();
+    HANDLES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Writer for the PTY master or piped stdin of one process
+///
+/// Each has its own lock so a slow write blocks only input to that process.
+type InputWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Writers for the PTY master or piped stdin of each process, keyed by OS PID
+fn input_writers() -> &'static Mutex<HashMap<u32, InputWriter>> {
+    static WRITERS: OnceLock<Mutex<HashMap<u32, InputWriter>>> = OnceLock::new();
+    WRITERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Handle to a spawned child, kept so it can be signalled and reaped
+enum ManagedChild {
+    Pty(Box<dyn PtyChild + Send + Sync>),
+    Piped(Child),
+}
+
+impl ManagedChild {
+    /// Check whether the child has exited without blocking
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitStatusInfo>> {
+        match self {
+            ManagedChild::Piped(child) => Ok(child.try_wait()?.map(ExitStatusInfo::from)),
+            // portable-pty only reports signal names, so wait on the PID
+            // directly to get the signal number
+            #[cfg(unix)]
+            ManagedChild::Pty(child) => match child.process_id() {
+                Some(pid) => wait_pid_nohang(pid),
+                None => Ok(None),
+            },
+            #[cfg(not(unix))]
+            ManagedChild::Pty(child) => Ok(child.try_wait()?.map(|status| ExitStatusInfo {
+                code: Some(status.exit_code() as i32),
+                signal: None,
+            })),
+        }
+    }
+
+    /// Forcefully kill the child
+    #[cfg(not(unix))]
+    fn kill(&mut self) -> std::io::Result<()> {
+        match self {
+            ManagedChild::Piped(child) => child.kill(),
+            ManagedChild::Pty(child) => child.kill(),
+        }
+    }
+}
+
+/// Child handles for every process we spawned, keyed by OS PID
+fn child_handles() -> &'static Mutex<HashMap<u32, ManagedChild>> {
+    static CHILDREN: OnceLock<Mutex<HashMap<u32, ManagedChild>>> = OnceLock::new();
+    CHILDREN.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
 /// Spawn a CLI process for an AI tool
@@ -40,181 +446,680 @@ fn process_output() -> &'static Mutex<HashMap<u32, String>> {
 pub async fn spawn_cli_process(
     tool_id: String,
     working_dir: String,
-    _args: Vec<String>,
+    args: Vec<String>,
+    rows: Option<u16>,
+    cols: Option<u16>,
+    limits: Option<ResourceLimits>,
+    session_id: Option<String>,
 ) -> Result<u32, String> {
-    // For now, return a placeholder PID
-    // Actual implementation will use tauri_plugin_shell
-    let pid = generate_pid();
-    
+    let adapter = get_available_adapters()
+        .into_iter()
+        .find(|a| a.id == tool_id)
+        .ok_or_else(|| format!("Unknown tool: {}", tool_id))?;
+
+    let size = PtySize {
+        rows: rows.unwrap_or(DEFAULT_PTY_ROWS),
+        cols: cols.unwrap_or(DEFAULT_PTY_COLS),
+        pixel_width: 0,
+        pixel_height: 0,
+    };
+    let env = spawn_env::resolve(&adapter.env).await?;
+    let limits = limits.or(adapter.limits).unwrap_or_default();
+
+    let executable = tool_detection::resolve(&adapter.executable, &tool_detection::search_dirs());
+    let pid = spawn_pty_process(&tool_id, &executable, &working_dir, &args, size, &env, &limits)?;
+    if let Some(session_id) = session_id {
+        recording::set_session(pid, session_id.clone());
+        tool_events::set_session(pid, session_id);
+    }
+    Ok(pid)
+}
+
+/// Spawn an executable under a new pseudo-terminal and register it
+///
+/// Secret values in `env` are redacted from the recorded command line.
+pub(crate) fn spawn_pty_process(
+    tool_id: &str,
+    executable: &str,
+    working_dir: &str,
+    args: &[String],
+    size: PtySize,
+    env: &ResolvedEnv,
+    limits: &ResourceLimits,
+) -> Result<u32, String> {
+    if !Path::new(working_dir).is_dir() {
+        return Err(AppError::FileNotFound(format!(
+            "Working directory does not exist: {}",
+            working_dir
+        ))
+        .to_string());
+    }
+
+    let pair = native_pty_system()
+        .openpty(size)
+        .map_err(|e| format!("Failed to open pseudo-terminal: {}", e))?;
+
+    let (program, program_args) = limits::wrap_for_pty(executable, args, limits);
+    let mut command = CommandBuilder::new(program);
+    command.args(program_args);
+    command.cwd(working_dir);
+    // Interactive TUIs need a terminal type to pick their rendering mode
+    if command.get_env("TERM").is_none() {
+        command.env("TERM", "xterm-256color");
+    }
+    env.apply_pty(&mut command);
+
+    let child = pair
+        .slave
+        .spawn_command(command)
+        .map_err(|e| format!("Failed to start {}: {}", executable, e))?;
+    // The child holds its own copy of the slave side; dropping ours lets the
+    // reader see EOF once the child exits
+    drop(pair.slave);
+
+    let pid = child
+        .process_id()
+        .ok_or_else(|| format!("Failed to get PID for {}", executable))?;
+
+    limits::attach(pid, limits);
+
+    let reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to attach to pseudo-terminal: {}", e))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| format!("Failed to attach to pseudo-terminal: {}", e))?;
+
     let process_info = ProcessInfo {
         pid,
-        tool_id,
-        working_dir,
+        tool_id: tool_id.to_string(),
+        working_dir: working_dir.to_string(),
         status: ProcessStatus::Running,
+        runtime_id: None,
+        exit_status: None,
+        started_at: output::now_millis(),
+        ended_at: None,
+        restart_count: 0,
+        command_line: env.redact_command_line(
+            std::iter::once(executable.to_string())
+                .chain(args.iter().cloned())
+                .collect(),
+        ),
+        limit_violation: None,
     };
 
-    let mut registry = process_registry().lock().map_err(|e| e.to_string())?;
-    registry.insert(pid, process_info);
+    output::register(pid)?;
+    pty_handles()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(pid, PtyHandle { master: pair.master });
+    input_writers()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(pid, Arc::new(Mutex::new(writer)));
+    child_handles()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(pid, ManagedChild::Pty(child));
+    recording::start(pid, tool_id, &process_info.command_line, size.cols, size.rows);
+    tool_events::start(pid, tool_id, args);
+    register_process(process_info)?;
 
-    let mut output = process_output().lock().map_err(|e| e.to_string())?;
-    output.insert(pid, String::new());
+    thread::spawn(move || capture_pty_output(pid, reader));
 
     Ok(pid)
 }
 
-/// Send input to a running process
+/// Stream everything the PTY produces into the process output
+///
+/// A terminal merges stdout and stderr, so all PTY output is reported as stdout.
+fn capture_pty_output(pid: u32, mut reader: Box<dyn Read + Send>) {
+    let mut buf = [0u8; 4096];
+    let mut pending = Vec::new();
+
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let text = decode_utf8_chunk(&mut pending, &buf[..n]);
+                if !text.is_empty() {
+                    recording::record(pid, CastEventKind::Output, text.clone());
+                    tool_events::feed(pid, &text);
+                    output::push(pid, OutputStream::Stdout, text);
+                }
+            }
+        }
+    }
+
+    // All output has been read, so the transcript and events are complete
+    recording::finish(pid);
+    tool_events::finish(pid);
+}
+
+/// Decode a chunk of terminal output, holding back a trailing partial UTF-8
+/// sequence until the next chunk completes it
+pub(crate) fn decode_utf8_chunk(pending: &mut Vec<u8>, chunk: &[u8]) -> String {
+    pending.extend_from_slice(chunk);
+
+    let valid_up_to = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+
+    let text = String::from_utf8_lossy(&pending[..valid_up_to]).into_owned();
+    pending.drain(..valid_up_to);
+    text
+}
+
+/// Resize the pseudo-terminal of a CLI process
 #[tauri::command]
-pub async fn send_to_process(pid: u32, input: String) -> Result<(), String> {
-    let registry = process_registry().lock().map_err(|e| e.to_string())?;
-    
-    if !registry.contains_key(&pid) {
-        return Err(AppError::ProcessError {
+pub async fn resize_process(pid: u32, rows: u16, cols: u16) -> Result<(), String> {
+    let handles = pty_handles().lock().map_err(|e| e.to_string())?;
+
+    let handle = handles.get(&pid).ok_or_else(|| {
+        AppError::ProcessError {
             pid,
-            message: "Process not found".to_string(),
-        }.to_string());
+            message: "Process has no terminal".to_string(),
+        }
+        .to_string()
+    })?;
+
+    recording::record_resize(pid, cols, rows);
+    handle
+        .master
+        .resize(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| {
+            AppError::ProcessError {
+                pid,
+                message: format!("Failed to resize terminal: {}", e),
+            }
+            .to_string()
+        })
+}
+
+/// Send input to a running process
+///
+/// Input goes to the PTY master of CLI tools and to stdin of piped runtimes.
+/// A pipe has no terminal to turn control keys into signals, so for piped
+/// runtimes Ctrl-C and Ctrl-Z signal the process group and Ctrl-D closes stdin.
+#[tauri::command]
+pub async fn send_to_process(pid: u32, input: ProcessInput) -> Result<(), String> {
+    let process_error = |message: String| AppError::ProcessError { pid, message }.to_string();
+
+    let running = process_registry()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&pid)
+        .map(|p| p.status == ProcessStatus::Running);
+    match running {
+        None => return Err(process_error("Process not found".to_string())),
+        Some(false) => return Err(process_error("Process is not running".to_string())),
+        Some(true) => {}
     }
 
-    // Store input as simulated output for now
-    let mut output = process_output().lock().map_err(|e| e.to_string())?;
-    if let Some(buf) = output.get_mut(&pid) {
-        buf.push_str(&format!("Input: {}\n", input));
+    let terminal = pty_handles().lock().map_err(|e| e.to_string())?.contains_key(&pid);
+    if let (false, ProcessInput::Key { key }) = (terminal, &input) {
+        match key {
+            #[cfg(unix)]
+            ControlKey::CtrlC => {
+                return signal_process_group(pid, libc::SIGINT)
+                    .map_err(|e| process_error(format!("Failed to interrupt process: {}", e)));
+            }
+            #[cfg(unix)]
+            ControlKey::CtrlZ => {
+                return signal_process_group(pid, libc::SIGTSTP)
+                    .map_err(|e| process_error(format!("Failed to suspend process: {}", e)));
+            }
+            ControlKey::CtrlD => {
+                input_writers().lock().map_err(|e| e.to_string())?.remove(&pid);
+                return Ok(());
+            }
+            _ => {}
+        }
     }
 
+    let bytes = input.encode(terminal);
+    let writer = input_writers()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&pid)
+        .cloned()
+        .ok_or_else(|| process_error("Process has no open input".to_string()))?;
+    // A full pipe or PTY blocks the write, so keep it off the async runtime
+    let written = bytes.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut writer = writer.lock().map_err(|e| e.to_string())?;
+        writer
+            .write_all(&written)
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("Failed to write input: {}", e))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(process_error)?;
+
+    recording::record(pid, CastEventKind::Input, String::from_utf8_lossy(&bytes).into_owned());
     Ok(())
 }
 
 /// Kill a running process
+///
+/// Sends SIGTERM to the process group, waits `grace_period_ms` (5s by default)
+/// and then sends SIGKILL if the process is still alive.
 #[tauri::command]
-pub async fn kill_process(pid: u32) -> Result<(), String> {
-    let mut registry = process_registry().lock().map_err(|e| e.to_string())?;
-    
-    if let Some(process) = registry.get_mut(&pid) {
-        process.status = ProcessStatus::Stopped;
-        Ok(())
-    } else {
-        Err(AppError::ProcessError {
-            pid,
-            message: "Process not found".to_string(),
-        }.to_string())
+pub async fn kill_process(pid: u32, grace_period_ms: Option<u64>) -> Result<ProcessInfo, String> {
+    let running = {
+        let registry = process_registry().lock().map_err(|e| e.to_string())?;
+        match registry.get(&pid) {
+            Some(process) => process.status == ProcessStatus::Running,
+            None => {
+                return Err(AppError::ProcessError {
+                    pid,
+                    message: "Process not found".to_string(),
+                }.to_string())
+            }
+        }
+    };
+
+    if running {
+        let grace = grace_period_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_STOP_GRACE_PERIOD);
+        terminate_process(pid, grace).await?;
     }
-}
 
-/// Get output from a process
-#[tauri::command]
-pub fn get_process_output(pid: u32) -> Result<String, String> {
-    let output = process_output().lock().map_err(|e| e.to_string())?;
-    
-    output.get(&pid)
+    process_registry()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&pid)
         .cloned()
         .ok_or_else(|| AppError::ProcessError {
             pid,
-            message: "Process output not found".to_string(),
+            message: "Process not found".to_string(),
         }.to_string())
 }
 
-/// Generate a unique PID (placeholder implementation)
-pub(crate) fn generate_pid() -> u32 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    (duration.as_nanos() % u32::MAX as u128) as u32
+/// Stop a process with SIGTERM, escalating to SIGKILL after the grace period
+async fn terminate_process(pid: u32, grace: Duration) -> Result<ExitStatusInfo, String> {
+    set_status(pid, ProcessStatus::Stopping);
+
+    #[cfg(unix)]
+    let _ = signal_process_group(pid, libc::SIGTERM);
+
+    if let Some(exit) = wait_for_exit(pid, grace).await? {
+        return Ok(exit);
+    }
+
+    #[cfg(unix)]
+    let _ = signal_process_group(pid, libc::SIGKILL);
+    #[cfg(not(unix))]
+    if let Some(child) = child_handles().lock().map_err(|e| e.to_string())?.get_mut(&pid) {
+        let _ = child.kill();
+    }
+
+    wait_for_exit(pid, KILL_REAP_TIMEOUT).await?.ok_or_else(|| {
+        AppError::ProcessError {
+            pid,
+            message: "Process did not exit after SIGKILL".to_string(),
+        }
+        .to_string()
+    })
+}
+
+/// Poll until a child exits and has been reaped, or the timeout elapses
+///
+/// Returns `None` if the process is still running when the timeout is reached.
+/// The reaper may collect the child first, so the registry is checked as well.
+async fn wait_for_exit(pid: u32, timeout: Duration) -> Result<Option<ExitStatusInfo>, String> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        if let Some(exit) = reap_child(pid) {
+            return Ok(Some(exit));
+        }
+
+        let recorded = process_registry()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get(&pid)
+            .and_then(|p| p.exit_status.clone());
+        if recorded.is_some() {
+            return Ok(recorded);
+        }
+
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+    }
+}
+
+/// Send a signal to a process and every process in its group
+///
+/// Runtimes and PTY sessions lead their own process group, so the group ID is
+/// the PID. Falls back to signalling just the process if the group is gone.
+#[cfg(unix)]
+pub(crate) fn signal_process_group(pid: u32, signal: libc::c_int) -> std::io::Result<()> {
+    if unsafe { libc::kill(-(pid as libc::pid_t), signal) } == 0 {
+        return Ok(());
+    }
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } == 0 {
+        return Ok(());
+    }
+    Err(std::io::Error::last_os_error())
+}
+
+/// Non-blocking `waitpid` for a child of this process
+#[cfg(unix)]
+fn wait_pid_nohang(pid: u32) -> std::io::Result<Option<ExitStatusInfo>> {
+    let mut status: libc::c_int = 0;
+    let ret = unsafe { libc::waitpid(pid as libc::pid_t, &mut status, libc::WNOHANG) };
+
+    match ret {
+        0 => Ok(None),
+        -1 => Err(std::io::Error::last_os_error()),
+        _ if libc::WIFSIGNALED(status) => Ok(Some(ExitStatusInfo {
+            code: None,
+            signal: Some(libc::WTERMSIG(status)),
+        })),
+        _ => Ok(Some(ExitStatusInfo {
+            code: Some(libc::WEXITSTATUS(status)),
+            signal: None,
+        })),
+    }
+}
+
+/// Number of lines returned by `get_process_output` when no limit is given
+const DEFAULT_OUTPUT_PAGE_SIZE: usize = 1000;
+
+/// Get a page of output from a process
+///
+/// `offset` is the sequence number to start from; pass the returned
+/// `next_offset` to fetch the following page. Lines older than the buffer
+/// caps have been evicted and are counted in `dropped`.
+#[tauri::command]
+pub fn get_process_output(
+    pid: u32,
+    offset: Option<u64>,
+    limit: Option<usize>,
+    stream: Option<OutputStream>,
+) -> Result<OutputPage, String> {
+    output::page(
+        pid,
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_OUTPUT_PAGE_SIZE),
+        stream,
+    )
+    .ok_or_else(|| AppError::ProcessError {
+        pid,
+        message: "Process output not found".to_string(),
+    }.to_string())
 }
 
 /// Start a runtime process
+///
+/// `env` maps environment variables to literals or secure storage keys; it is
+/// resolved on every start so restarts pick up rotated credentials. `limits`
+/// caps the resources of the runtime's process tree. Resolves once the
+/// runtime passes its readiness probe.
 #[tauri::command]
 pub async fn start_runtime(
-    runtime_id: String,
+    runtime_id: RuntimeId,
     executable_path: String,
     args: Vec<String>,
     working_dir: Option<String>,
+    env: Option<EnvMappings>,
+    limits: Option<ResourceLimits>,
+    readiness: Option<Readiness>,
 ) -> Result<u32, String> {
-    // Parse runtime type from ID
-    let parts: Vec<&str> = runtime_id.split('_').collect();
-    if parts.is_empty() {
-        return Err("Invalid runtime ID".to_string());
-    }
+    let launch = RuntimeLaunch {
+        executable_path,
+        args,
+        working_dir,
+        env: env.unwrap_or_default(),
+        limits,
+        readiness,
+    };
+    launch_runtime(&runtime_id, launch, 0).await
+}
 
-    let runtime_type = parts[0];
+/// Spawn a runtime, remember how it was launched and wait until it is ready
+///
+/// The restart count is set right after the spawn, before the readiness wait,
+/// so the new process never shows up with a stale count.
+async fn launch_runtime(runtime_id: &RuntimeId, launch: RuntimeLaunch, restart_count: u32) -> Result<u32, String> {
+    runtime_launches()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(runtime_id.clone(), launch.clone());
+    let mut env = spawn_env::resolve(&launch.env).await?;
+    // A stored instance's address wins over the env mappings; without one the mappings decide
+    for (key, value) in runtime_instance::instance_env(runtime_id) {
+        env.set(key, value);
+    }
+    // Where the runtime will really bind, given its env and the app's own environment
+    let lookup = |name: &str| {
+        env.vars()
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| std::env::var(name).ok())
+    };
+    let endpoint = runtime_instance::launch_endpoint(runtime_id, &lookup);
+    let limits = launch.limits.unwrap_or_default();
+    if runtime_id.kind != RuntimeKind::Docker {
+        runtime_instance::ensure_port_free(runtime_id, endpoint.as_ref()).await?;
+    }
 
-    match runtime_type {
-        "ollama" => start_ollama_runtime().await,
-        "localai" => start_localai_runtime().await,
-        "docker" => {
-            if parts.len() >= 2 {
-                start_docker_runtime(parts[1]).await
+    let pid = match runtime_id.kind {
+        RuntimeKind::Ollama => start_ollama_runtime(runtime_id, &launch.executable_path, &env, &limits).await,
+        RuntimeKind::LocalAi => start_localai_runtime(runtime_id, &launch.executable_path, &env, &limits).await,
+        RuntimeKind::Docker => start_docker_runtime(&runtime_id.locator).await,
+        RuntimeKind::Python | RuntimeKind::Node | RuntimeKind::Custom => {
+            // Without an explicit path the locator names the executable
+            let executable_path = if launch.executable_path.is_empty() {
+                runtime_id.locator.clone()
             } else {
-                Err("Invalid Docker runtime ID".to_string())
-            }
+                launch.executable_path
+            };
+            start_generic_runtime(runtime_id, executable_path, launch.args, launch.working_dir, &env, &limits)
+                .await
         }
-        _ => {
-            // Generic process start
-            start_generic_runtime(executable_path, args, working_dir).await
+    }?;
+
+    if let Some(process) = process_registry().lock().map_err(|e| e.to_string())?.get_mut(&pid) {
+        process.restart_count = restart_count;
+    }
+
+    if runtime_id.kind != RuntimeKind::Docker {
+        runtime_instance::record_launch(runtime_id, endpoint.clone());
+    }
+
+    // Docker containers are started by the daemon, so there is no process to watch
+    let tracked_pid = (runtime_id.kind != RuntimeKind::Docker).then_some(pid);
+    let readiness = launch.readiness.unwrap_or_default();
+    await_readiness(runtime_id, tracked_pid, &readiness, endpoint).await?;
+    Ok(pid)
+}
+
+/// Wait for a just-started runtime to pass its readiness probe
+///
+/// A runtime that never becomes ready is stopped so it doesn't linger half-started.
+async fn await_readiness(
+    runtime_id: &RuntimeId,
+    pid: Option<u32>,
+    readiness: &Readiness,
+    endpoint: Option<runtime_instance::Endpoint>,
+) -> Result<(), String> {
+    let probe = readiness.probe_for(runtime_id, endpoint);
+    let timeout = Duration::from_millis(readiness.timeout_ms);
+    let Err(e) = readiness::wait_until_ready(runtime_id, pid, &probe, timeout).await else {
+        return Ok(());
+    };
+
+    if let Some(pid) = pid.filter(|pid| status_of(*pid) == Some(ProcessStatus::Running)) {
+        if let Err(stop_error) = terminate_process(pid, DEFAULT_STOP_GRACE_PERIOD).await {
+            eprintln!("Warning: failed to stop runtime {}: {}", runtime_id, stop_error);
         }
     }
+    Err(e)
 }
 
 /// Start Ollama runtime
-async fn start_ollama_runtime() -> Result<u32, String> {
-    let output = Command::new("ollama")
-        .arg("serve")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
+///
+/// The bind address and models directory come from `OLLAMA_HOST` and
+/// `OLLAMA_MODELS` in `env`.
+async fn start_ollama_runtime(
+    runtime_id: &RuntimeId,
+    executable_path: &str,
+    env: &ResolvedEnv,
+    limits: &ResourceLimits,
+) -> Result<u32, String> {
+    let mut command = Command::new(executable_or(executable_path, "ollama"));
+    command.arg("serve");
+
+    let child = spawn_limited(&mut command, env, limits)
         .map_err(|e| format!("Failed to start Ollama: {}", e))?;
 
-    let pid = output.id();
-    
-    // Store process info
-    let process_info = ProcessInfo {
-        pid,
-        tool_id: "ollama".to_string(),
-        working_dir: String::new(),
-        status: ProcessStatus::Running,
-    };
+    register_piped_child(child, "ollama", String::new(), Some(runtime_id))
+}
 
-    let mut registry = process_registry().lock().map_err(|e| e.to_string())?;
-    registry.insert(pid, process_info);
-
-    // Capture output in background
-    if let Some(stdout) = output.stdout {
-        let output_buffer = Arc::clone(&Arc::new(Mutex::new(String::new())));
-        let _pid_clone = pid;
-        thread::spawn(move || {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    if let Ok(mut buf) = output_buffer.lock() {
-                        buf.push_str(&line);
-                        buf.push('\n');
-                    }
-                }
-            }
-        });
+/// Start LocalAI runtime
+///
+/// The bind address and models path come from `LOCALAI_ADDRESS` and
+/// `LOCALAI_MODELS_PATH` in `env`.
+async fn start_localai_runtime(
+    runtime_id: &RuntimeId,
+    executable_path: &str,
+    env: &ResolvedEnv,
+    limits: &ResourceLimits,
+) -> Result<u32, String> {
+    let child = spawn_limited(&mut Command::new(executable_or(executable_path, "local-ai")), env, limits)
+        .map_err(|e| format!("Failed to start LocalAI: {}", e))?;
+
+    register_piped_child(child, "localai", String::new(), Some(runtime_id))
+}
+
+/// The configured executable, or `default` found on PATH
+fn executable_or<'a>(executable_path: &'a str, default: &'a str) -> &'a str {
+    if executable_path.is_empty() {
+        default
+    } else {
+        executable_path
     }
+}
 
-    Ok(pid)
+/// A runtime spawned with piped stdio, along with how it was invoked
+struct PipedChild {
+    child: Child,
+    command_line: Vec<String>,
 }
 
-/// Start LocalAI runtime
-async fn start_localai_runtime() -> Result<u32, String> {
-    let output = Command::new("local-ai")
+/// Spawn a runtime with piped output in its own process group
+///
+/// The separate group lets stop signals reach any workers the runtime forks.
+/// Secret values in `env` are redacted from the recorded command line.
+fn spawn_piped(command: &mut Command, env: &ResolvedEnv) -> std::io::Result<PipedChild> {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    env.apply(command);
+
+    let command_line = env.redact_command_line(
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect(),
+    );
+
+    let child = command
+        .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to start LocalAI: {}", e))?;
+        .spawn()?;
+
+    Ok(PipedChild {
+        child,
+        command_line,
+    })
+}
+
+/// Spawn a runtime under its resource limits
+///
+/// rlimits are set before exec; the cgroup is attached before the reaper can
+/// see the process, so a limit violation is never missed.
+fn spawn_limited(
+    command: &mut Command,
+    env: &ResolvedEnv,
+    limits: &ResourceLimits,
+) -> std::io::Result<PipedChild> {
+    limits::apply_to_command(command, limits);
+    let spawned = spawn_piped(command, env)?;
+    limits::attach(spawned.child.id(), limits);
+    Ok(spawned)
+}
+
+/// Register a runtime spawned with piped stdio and stream its output
+fn register_piped_child(
+    spawned: PipedChild,
+    tool_id: &str,
+    working_dir: String,
+    runtime_id: Option<&RuntimeId>,
+) -> Result<u32, String> {
+    let PipedChild {
+        mut child,
+        command_line,
+    } = spawned;
+    let pid = child.id();
 
-    let pid = output.id();
-    
     let process_info = ProcessInfo {
         pid,
-        tool_id: "localai".to_string(),
-        working_dir: String::new(),
+        tool_id: tool_id.to_string(),
+        working_dir,
         status: ProcessStatus::Running,
+        runtime_id: runtime_id.cloned(),
+        exit_status: None,
+        started_at: output::now_millis(),
+        ended_at: None,
+        restart_count: 0,
+        command_line,
+        limit_violation: None,
     };
 
-    let mut registry = process_registry().lock().map_err(|e| e.to_string())?;
-    registry.insert(pid, process_info);
+    output::register(pid)?;
+    if let Some(runtime_id) = runtime_id {
+        runtime_logs::attach(pid, runtime_id);
+    }
+
+    if let Some(stdin) = child.stdin.take() {
+        input_writers()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(pid, Arc::new(Mutex::new(Box::new(stdin))));
+    }
+
+    // Separate readers per stream; sequence numbers keep the merged order
+    let mut streams: Vec<(OutputStream, Box<dyn Read + Send>)> = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        streams.push((OutputStream::Stdout, Box::new(stdout)));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        streams.push((OutputStream::Stderr, Box::new(stderr)));
+    }
+    output::spawn_line_readers(pid, streams);
+
+    child_handles()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(pid, ManagedChild::Piped(child));
+    register_process(process_info)?;
 
     Ok(pid)
 }
@@ -239,68 +1144,69 @@ async fn start_docker_runtime(container_id: &str) -> Result<u32, String> {
 
 /// Start generic runtime
 async fn start_generic_runtime(
+    runtime_id: &RuntimeId,
     executable_path: String,
     args: Vec<String>,
     working_dir: Option<String>,
+    env: &ResolvedEnv,
+    limits: &ResourceLimits,
 ) -> Result<u32, String> {
+    if executable_path.is_empty() {
+        return Err(format!("No executable configured for runtime {}", runtime_id));
+    }
+
     let mut command = Command::new(&executable_path);
     command.args(&args);
     
-    if let Some(dir) = working_dir {
+    if let Some(dir) = &working_dir {
         command.current_dir(dir);
     }
 
-    let output = command
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
+    let child = spawn_limited(&mut command, env, limits)
         .map_err(|e| format!("Failed to start process: {}", e))?;
 
-    let pid = output.id();
-    
-    let process_info = ProcessInfo {
-        pid,
-        tool_id: executable_path,
-        working_dir: String::new(),
-        status: ProcessStatus::Running,
-    };
-
-    let mut registry = process_registry().lock().map_err(|e| e.to_string())?;
-    registry.insert(pid, process_info);
-
-    Ok(pid)
+    register_piped_child(
+        child,
+        &env.redact(&executable_path),
+        working_dir.unwrap_or_default(),
+        Some(runtime_id),
+    )
 }
 
 /// Stop a runtime process
+///
+/// Docker containers are stopped through `docker stop`; every other runtime is
+/// sent SIGTERM and, after `grace_period_ms` (5s by default), SIGKILL.
 #[tauri::command]
-pub async fn stop_runtime(runtime_id: String) -> Result<(), String> {
-    let parts: Vec<&str> = runtime_id.split('_').collect();
-    if parts.is_empty() {
-        return Err("Invalid runtime ID".to_string());
-    }
-
-    let runtime_type = parts[0];
+pub async fn stop_runtime(runtime_id: RuntimeId, grace_period_ms: Option<u64>) -> Result<(), String> {
+    let grace = grace_period_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_STOP_GRACE_PERIOD);
 
-    match runtime_type {
-        "docker" => {
-            if parts.len() >= 2 {
-                stop_docker_runtime(parts[1]).await
-            } else {
-                Err("Invalid Docker runtime ID".to_string())
-            }
-        }
+    match runtime_id.kind {
+        RuntimeKind::Docker => stop_docker_runtime(&runtime_id.locator, grace).await,
         _ => {
-            // For other runtimes, we need to find the PID
-            // This is a simplified implementation
-            Err("Stopping non-Docker runtimes not yet implemented".to_string())
+            let pid = find_runtime_pid(&runtime_id)?
+                .ok_or_else(|| format!("Runtime is not running: {}", runtime_id))?;
+            terminate_process(pid, grace).await.map(|_| ())
         }
     }
 }
 
+/// Find the PID of the running process started for a runtime
+pub(crate) fn find_runtime_pid(runtime_id: &RuntimeId) -> Result<Option<u32>, String> {
+    let registry = process_registry().lock().map_err(|e| e.to_string())?;
+    Ok(registry
+        .values()
+        .find(|p| p.runtime_id.as_ref() == Some(runtime_id) && p.status == ProcessStatus::Running)
+        .map(|p| p.pid))
+}
+
 /// Stop Docker container
-async fn stop_docker_runtime(container_id: &str) -> Result<(), String> {
+async fn stop_docker_runtime(container_id: &str, grace: Duration) -> Result<(), String> {
+    let timeout = grace.as_secs().to_string();
     let output = Command::new("docker")
-        .args(&["stop", container_id])
+        .args(["stop", "--time", &timeout, container_id])
         .output()
         .map_err(|e| format!("Failed to stop Docker container: {}", e))?;
 
@@ -315,31 +1221,55 @@ async fn stop_docker_runtime(container_id: &str) -> Result<(), String> {
 }
 
 /// Restart a runtime process
+///
+/// Resolves once the new process passes its readiness probe.
 #[tauri::command]
-pub async fn restart_runtime(runtime_id: String) -> Result<u32, String> {
-    // Stop the runtime first
-    if let Err(e) = stop_runtime(runtime_id.clone()).await {
+pub async fn restart_runtime(runtime_id: RuntimeId) -> Result<u32, String> {
+    // Stopping waits for the old process to exit, so its ports are free again
+    if let Err(e) = stop_runtime(runtime_id.clone(), None).await {
         // If stop fails, it might already be stopped, continue anyway
         eprintln!("Warning: stop failed: {}", e);
     }
 
-    // Wait a bit for cleanup
-    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    relaunch_runtime(&runtime_id).await
+}
 
-    // Start it again
-    start_runtime(runtime_id, String::new(), vec![], None).await
+/// Start a runtime again with the parameters it was last started with
+pub(crate) async fn relaunch_runtime(runtime_id: &RuntimeId) -> Result<u32, String> {
+    let restart_count = last_restart_count(runtime_id)?.map_or(0, |count| count + 1);
+    let launch = runtime_launches()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(runtime_id)
+        .cloned()
+        .unwrap_or_default();
+
+    launch_runtime(runtime_id, launch, restart_count).await
 }
 
-/// Stream process output (placeholder for event-based streaming)
+/// Restart count of the most recently started process for a runtime
+fn last_restart_count(runtime_id: &RuntimeId) -> Result<Option<u32>, String> {
+    let registry = process_registry().lock().map_err(|e| e.to_string())?;
+    Ok(registry
+        .values()
+        .filter(|p| p.runtime_id.as_ref() == Some(runtime_id))
+        .max_by_key(|p| p.started_at)
+        .map(|p| p.restart_count))
+}
+
+/// Get process output from a sequence number onwards
+///
+/// Live output is pushed as `process://output` events; a webview that reloads
+/// calls this with the sequence after the last event it saw to catch up.
 #[tauri::command]
-pub async fn stream_process_output(pid: u32) -> Result<Vec<String>, String> {
-    let output = process_output().lock().map_err(|e| e.to_string())?;
-    
-    if let Some(buf) = output.get(&pid) {
-        Ok(buf.lines().map(|s| s.to_string()).collect())
-    } else {
-        Ok(vec![])
-    }
+pub async fn resume_process_output(pid: u32, from_seq: u64) -> Result<Vec<OutputLine>, String> {
+    output::lines_from(pid, from_seq).ok_or_else(|| {
+        AppError::ProcessError {
+            pid,
+            message: "Process output not found".to_string(),
+        }
+        .to_string()
+    })
 }
 
 #[cfg(test)]
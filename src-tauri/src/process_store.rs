@@ -0,0 +1,319 @@
+// Process Store - persists the process registry so runtimes survive an app restart
+// On startup the saved entries are matched against live processes and survivors can be adopted
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use sysinfo::{Pid, ProcessStatus as OsStatus, System};
+
+use crate::error::AppError;
+use crate::process::{self, ProcessInfo, ProcessStatus};
+use crate::runtime_id::RuntimeId;
+
+/// File in the app data dir holding the persisted registry
+pub const PROCESS_FILE: &str = "processes.json";
+
+/// Allowed difference between recorded and observed start times, in seconds
+///
+/// The OS reports start times relative to boot, so the computed wall-clock
+/// time can drift by a second between reads.
+const START_TIME_TOLERANCE_SECS: u64 = 1;
+
+/// A process as written to processes.json
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PersistedProcess {
+    pub pid: u32,
+    pub tool_id: String,
+    pub runtime_id: Option<RuntimeId>,
+    pub working_dir: String,
+    pub command_line: Vec<String>,
+    /// Start time in milliseconds since the Unix epoch, as recorded by the app
+    pub started_at: u64,
+    /// Start time in seconds since the Unix epoch, as reported by the OS
+    pub os_start_time: u64,
+}
+
+impl PersistedProcess {
+    fn to_process_info(&self) -> ProcessInfo {
+        ProcessInfo {
+            pid: self.pid,
+            tool_id: self.tool_id.clone(),
+            working_dir: self.working_dir.clone(),
+            status: ProcessStatus::Running,
+            runtime_id: self.runtime_id.clone(),
+            exit_status: None,
+            started_at: self.started_at,
+            ended_at: None,
+            restart_count: 0,
+            command_line: self.command_line.clone(),
+            limit_violation: None,
+        }
+    }
+}
+
+/// Location of processes.json, set once during app setup
+static STORE_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// OS start times of the processes we registered, by PID
+fn identities() -> &'static Mutex<HashMap<u32, u64>> {
+    static IDENTITIES: OnceLock<Mutex<HashMap<u32, u64>>> = OnceLock::new();
+    IDENTITIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Processes from an earlier run that are still alive and not yet adopted
+fn orphans() -> &'static Mutex<HashMap<u32, PersistedProcess>> {
+    static ORPHANS: OnceLock<Mutex<HashMap<u32, PersistedProcess>>> = OnceLock::new();
+    ORPHANS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Start time of a live process in seconds since the Unix epoch
+pub fn os_start_time(pid: u32) -> Option<u64> {
+    let mut system = System::new();
+    let pid = Pid::from_u32(pid);
+    if !system.refresh_process(pid) {
+        return None;
+    }
+    system
+        .process(pid)
+        .filter(|p| p.status() != OsStatus::Zombie)
+        .map(|p| p.start_time())
+}
+
+/// Whether `pid` still belongs to the process that started at `os_start_time`
+///
+/// A PID recycled by an unrelated process has a different start time and is
+/// rejected.
+pub fn is_alive(pid: u32, os_start_time: u64) -> bool {
+    os_start_time_matches(self::os_start_time(pid), os_start_time)
+}
+
+fn os_start_time_matches(observed: Option<u64>, recorded: u64) -> bool {
+    observed.is_some_and(|t| t.abs_diff(recorded) <= START_TIME_TOLERANCE_SECS)
+}
+
+/// Remember the OS start time of a newly registered process
+pub(crate) fn record_identity(pid: u32) {
+    if let Some(start_time) = os_start_time(pid) {
+        if let Ok(mut identities) = identities().lock() {
+            identities.insert(pid, start_time);
+        }
+    }
+}
+
+/// Read persisted processes from a file, treating a missing file as empty
+pub fn load_from(path: &Path) -> Result<Vec<PersistedProcess>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| AppError::IoError(format!("Failed to read {}: {}", path.display(), e)).to_string())?;
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::SerializationError(format!("Invalid process file: {}", e)).to_string())
+}
+
+/// Write persisted processes to a file
+pub fn save_to(path: &Path, processes: &[PersistedProcess]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::IoError(e.to_string()).to_string())?;
+    }
+    let content = serde_json::to_string_pretty(processes).map_err(|e| e.to_string())?;
+    fs::write(path, content)
+        .map_err(|e| AppError::IoError(format!("Failed to write {}: {}", path.display(), e)).to_string())
+}
+
+/// Split persisted processes into those still alive and those that are gone
+pub fn reconcile(processes: Vec<PersistedProcess>) -> (Vec<PersistedProcess>, Vec<PersistedProcess>) {
+    processes
+        .into_iter()
+        .partition(|p| is_alive(p.pid, p.os_start_time))
+}
+
+/// Load processes.json, keep the survivors as orphans and rewrite the file
+///
+/// Called once during app setup; until then nothing is persisted.
+pub fn init(path: PathBuf) -> Result<(), String> {
+    let (survivors, gone) = reconcile(load_from(&path)?);
+    if !gone.is_empty() {
+        eprintln!("Dropped {} process(es) that exited while the app was closed", gone.len());
+    }
+
+    orphans()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .extend(survivors.into_iter().map(|p| (p.pid, p)));
+
+    let _ = STORE_PATH.set(path);
+    persist();
+    Ok(())
+}
+
+/// Write live and orphaned processes to processes.json
+///
+/// Does nothing until `init` has been called.
+pub(crate) fn persist() {
+    let Some(path) = STORE_PATH.get() else {
+        return;
+    };
+
+    let mut processes: Vec<PersistedProcess> = {
+        let Ok(identities) = identities().lock() else {
+            return;
+        };
+        process::live_processes()
+            .into_iter()
+            .filter_map(|p| {
+                Some(PersistedProcess {
+                    os_start_time: *identities.get(&p.pid)?,
+                    pid: p.pid,
+                    tool_id: p.tool_id,
+                    runtime_id: p.runtime_id,
+                    working_dir: p.working_dir,
+                    command_line: p.command_line,
+                    started_at: p.started_at,
+                })
+            })
+            .collect()
+    };
+    if let Ok(orphans) = orphans().lock() {
+        processes.extend(orphans.values().cloned());
+    }
+    processes.sort_by_key(|p| p.pid);
+
+    if let Err(e) = save_to(path, &processes) {
+        eprintln!("Warning: failed to persist process registry: {}", e);
+    }
+}
+
+/// Take an orphan out of the list if it is still the same process
+fn take_orphan(pid: u32) -> Result<PersistedProcess, String> {
+    let orphan = orphans()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&pid)
+        .ok_or_else(|| {
+            AppError::ProcessError {
+                pid,
+                message: "No orphaned process with this PID".to_string(),
+            }
+            .to_string()
+        })?;
+
+    if !is_alive(orphan.pid, orphan.os_start_time) {
+        persist();
+        return Err(AppError::ProcessError {
+            pid,
+            message: "Orphaned process has already exited".to_string(),
+        }
+        .to_string());
+    }
+    Ok(orphan)
+}
+
+/// List runtimes from an earlier run of the app that are still running
+#[tauri::command]
+pub fn list_orphaned_processes() -> Result<Vec<PersistedProcess>, String> {
+    let mut orphans: Vec<PersistedProcess> = orphans()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .values()
+        .cloned()
+        .collect();
+    orphans.sort_by_key(|p| p.pid);
+    Ok(orphans)
+}
+
+/// Adopt an orphaned process so it is managed like one we started
+#[tauri::command]
+pub fn adopt_orphaned_process(pid: u32) -> Result<ProcessInfo, String> {
+    let orphan = take_orphan(pid)?;
+    process::adopt_process(orphan.to_process_info(), orphan.os_start_time)
+}
+
+/// Stop an orphaned process with SIGTERM, escalating to SIGKILL after the grace period
+#[tauri::command]
+pub async fn cleanup_orphaned_process(pid: u32, grace_period_ms: Option<u64>) -> Result<ProcessInfo, String> {
+    adopt_orphaned_process(pid)?;
+    process::kill_process(pid, grace_period_ms).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fake_pid;
+    use tempfile::TempDir;
+
+    fn persisted(pid: u32, os_start_time: u64) -> PersistedProcess {
+        PersistedProcess {
+            pid,
+            tool_id: "ollama".to_string(),
+            runtime_id: Some("ollama_default".parse().unwrap()),
+            working_dir: String::new(),
+            command_line: vec!["ollama".to_string(), "serve".to_string()],
+            started_at: os_start_time * 1000,
+            os_start_time,
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join(PROCESS_FILE);
+
+        let processes = vec![persisted(100, 1_700_000_000), persisted(200, 1_700_000_100)];
+        save_to(&path, &processes).unwrap();
+
+        assert_eq!(load_from(&path).unwrap(), processes);
+    }
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let dir = TempDir::new().unwrap();
+        assert!(load_from(&dir.path().join(PROCESS_FILE)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_start_time_tolerance() {
+        assert!(os_start_time_matches(Some(1_000), 1_000));
+        assert!(os_start_time_matches(Some(1_001), 1_000));
+        assert!(!os_start_time_matches(Some(1_005), 1_000));
+        assert!(!os_start_time_matches(None, 1_000));
+    }
+
+    #[test]
+    fn test_reconcile_rejects_recycled_pid() {
+        let pid = std::process::id();
+        let start_time = os_start_time(pid).unwrap();
+
+        let (alive, gone) = reconcile(vec![
+            persisted(pid, start_time),
+            // Same PID, different process
+            persisted(pid, start_time - 3_600),
+            // PID that no longer exists
+            persisted(fake_pid(), start_time),
+        ]);
+
+        assert_eq!(alive.len(), 1);
+        assert_eq!(alive[0].os_start_time, start_time);
+        assert_eq!(gone.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_adopt_and_stop_orphan() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id();
+        let start_time = os_start_time(pid).unwrap();
+        orphans().lock().unwrap().insert(pid, persisted(pid, start_time));
+
+        assert!(list_orphaned_processes().unwrap().iter().any(|p| p.pid == pid));
+
+        let info = cleanup_orphaned_process(pid, Some(2_000)).await.unwrap();
+        assert_eq!(info.status, ProcessStatus::Stopped);
+        assert_eq!(info.runtime_id.map(|id| id.to_string()).as_deref(), Some("ollama_default"));
+        assert!(!list_orphaned_processes().unwrap().iter().any(|p| p.pid == pid));
+
+        let _ = child.wait();
+        assert!(adopt_orphaned_process(pid).is_err());
+    }
+}
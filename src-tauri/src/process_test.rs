@@ -2,6 +2,41 @@
 mod property_tests {
     use super::super::*;
 
+    /// Spawn a long-lived `cat` under a PTY to stand in for an AI tool
+    fn spawn_test_process(tool_id: &str) -> Result<u32, String> {
+        spawn_pty_process(
+            tool_id,
+            "cat",
+            "/tmp",
+            &[],
+            PtySize {
+                rows: DEFAULT_PTY_ROWS,
+                cols: DEFAULT_PTY_COLS,
+                pixel_width: 0,
+                pixel_height: 0,
+            },
+            &ResolvedEnv::default(),
+            &ResourceLimits::default(),
+        )
+    }
+
+    /// Line input for `send_to_process`
+    fn line(text: &str) -> ProcessInput {
+        ProcessInput::Line {
+            text: text.to_string(),
+        }
+    }
+
+    /// Concatenate all retained output of a process
+    fn output_text(pid: u32) -> String {
+        get_process_output(pid, None, Some(usize::MAX), None)
+            .unwrap()
+            .lines
+            .iter()
+            .map(|l| l.data.as_str())
+            .collect()
+    }
+
     /// **Property 3: Runtime Control Commands**
     /// 
     /// For any AI runtime, executing a start command SHALL result in a running process,
@@ -10,15 +45,10 @@ mod property_tests {
     /// **Validates: Requirements 3.1**
     #[tokio::test]
     async fn property_3_runtime_control_commands() {
-        // Test that spawn_cli_process creates a process
-        let result = spawn_cli_process(
-            "test_tool".to_string(),
-            "/tmp".to_string(),
-            vec![],
-        )
-        .await;
+        // Test that spawning creates a process
+        let result = spawn_test_process("test_tool");
 
-        assert!(result.is_ok(), "spawn_cli_process should succeed");
+        assert!(result.is_ok(), "spawn_pty_process should succeed");
         let pid = result.unwrap();
         assert!(pid > 0, "PID should be positive");
 
@@ -37,7 +67,7 @@ mod property_tests {
         drop(registry);
 
         // Test that kill_process stops the process
-        let kill_result = kill_process(pid).await;
+        let kill_result = kill_process(pid, None).await;
         assert!(kill_result.is_ok(), "kill_process should succeed");
 
         // Verify process status changed
@@ -57,7 +87,7 @@ mod property_tests {
         // Note: In a real environment, this would test actual runtime restart
         
         // For Docker containers, we can test the restart logic
-        let runtime_id = "docker_test_container".to_string();
+        let runtime_id: RuntimeId = "docker_test_container".parse().unwrap();
         
         // Restart should handle non-existent containers gracefully
         let result = restart_runtime(runtime_id).await;
@@ -79,88 +109,103 @@ mod property_tests {
     #[tokio::test]
     async fn property_4_process_output_capture() {
         // Spawn a process
-        let result = spawn_cli_process(
-            "test_tool".to_string(),
-            "/tmp".to_string(),
-            vec![],
-        )
-        .await;
+        let result = spawn_test_process("test_tool");
 
-        assert!(result.is_ok(), "spawn_cli_process should succeed");
+        assert!(result.is_ok(), "spawn_pty_process should succeed");
         let pid = result.unwrap();
 
-        // Send input to process (simulates output capture)
-        let input_result = send_to_process(pid, "test input".to_string()).await;
+        // cat echoes the input back through the terminal
+        let input_result = send_to_process(pid, line("test input")).await;
         assert!(input_result.is_ok(), "send_to_process should succeed");
 
         // Retrieve output
-        let output_result = get_process_output(pid);
+        let output_result = get_process_output(pid, None, None, None);
         assert!(output_result.is_ok(), "get_process_output should succeed");
 
-        let output = output_result.unwrap();
+        let output = wait_for_output(pid, "test input").await;
         assert!(
             output.contains("test input"),
             "Output should contain the input we sent"
         );
     }
 
-    /// Test stream_process_output returns output lines
+    /// Test resume_process_output replays output from a sequence number
     #[tokio::test]
-    async fn test_stream_process_output() {
-        // Spawn a process
-        let result = spawn_cli_process(
-            "test_tool".to_string(),
-            "/tmp".to_string(),
-            vec![],
-        )
-        .await;
-
-        assert!(result.is_ok());
-        let pid = result.unwrap();
+    async fn test_resume_process_output() {
+        let pid = spawn_test_process("test_tool").unwrap();
 
         // Send some input
-        let _ = send_to_process(pid, "line 1".to_string()).await;
-        let _ = send_to_process(pid, "line 2".to_string()).await;
+        let _ = send_to_process(pid, line("line 1")).await;
+        let _ = send_to_process(pid, line("line 2")).await;
+        wait_for_output(pid, "line 2").await;
 
-        // Stream output
-        let stream_result = stream_process_output(pid).await;
-        assert!(stream_result.is_ok(), "stream_process_output should succeed");
-
-        let lines = stream_result.unwrap();
+        let lines = resume_process_output(pid, 0).await.unwrap();
         assert!(!lines.is_empty(), "Should have output lines");
+        assert!(
+            lines.windows(2).all(|w| w[0].seq < w[1].seq),
+            "Sequence numbers should be strictly increasing"
+        );
+
+        // Resuming past the last sequence yields only newer output
+        let last_seq = lines.last().unwrap().seq;
+        let resumed = resume_process_output(pid, last_seq + 1).await.unwrap();
+        assert!(resumed.iter().all(|l| l.seq > last_seq));
+
+        kill_process(pid, None).await.unwrap();
+    }
+
+    /// Test that piped runtimes stream stdout and stderr separately
+    #[tokio::test]
+    async fn test_piped_runtime_streams() {
+        let child = spawn_piped(Command::new("sh").args(["-c", "echo out; echo err >&2"]), &ResolvedEnv::default()).unwrap();
+        let pid = register_piped_child(child, "test_runtime", "/tmp".to_string(), None).unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let mut lines = Vec::new();
+        while lines.len() < 2 && std::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            lines = resume_process_output(pid, 0).await.unwrap();
+        }
+
+        let stdout: Vec<&str> = lines
+            .iter()
+            .filter(|l| l.stream == OutputStream::Stdout)
+            .map(|l| l.data.as_str())
+            .collect();
+        let stderr: Vec<&str> = lines
+            .iter()
+            .filter(|l| l.stream == OutputStream::Stderr)
+            .map(|l| l.data.as_str())
+            .collect();
+        assert_eq!(stdout, vec!["out\n"]);
+        assert_eq!(stderr, vec!["err\n"]);
+
+        // The stream filter returns only the requested stream
+        let page = get_process_output(pid, None, None, Some(OutputStream::Stderr)).unwrap();
+        assert_eq!(page.lines.len(), 1);
+        assert_eq!(page.lines[0].data, "err\n");
+        assert_eq!(page.dropped, 0);
     }
 
     /// Test that process output is isolated per PID
     #[tokio::test]
     async fn test_process_output_isolation() {
         // Spawn two processes
-        let pid1 = spawn_cli_process(
-            "tool1".to_string(),
-            "/tmp".to_string(),
-            vec![],
-        )
-        .await
-        .unwrap();
+        let pid1 = spawn_test_process("tool1").unwrap();
 
-        let pid2 = spawn_cli_process(
-            "tool2".to_string(),
-            "/tmp".to_string(),
-            vec![],
-        )
-        .await
-        .unwrap();
+        let pid2 = spawn_test_process("tool2").unwrap();
 
         // Send different input to each
-        send_to_process(pid1, "output from tool1".to_string())
+        send_to_process(pid1, line("output from tool1"))
             .await
             .unwrap();
-        send_to_process(pid2, "output from tool2".to_string())
+        send_to_process(pid2, line("output from tool2"))
             .await
             .unwrap();
 
         // Verify outputs are isolated
-        let output1 = get_process_output(pid1).unwrap();
-        let output2 = get_process_output(pid2).unwrap();
+        let output1 = wait_for_output(pid1, "tool1").await;
+        let output2 = wait_for_output(pid2, "tool2").await;
 
         assert!(
             output1.contains("tool1") && !output1.contains("tool2"),
@@ -172,28 +217,471 @@ mod property_tests {
         );
     }
 
+    /// Wait until a process has produced output containing `needle`
+    async fn wait_for_output(pid: u32, needle: &str) -> String {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        loop {
+            let text = output_text(pid);
+            if text.contains(needle) || std::time::Instant::now() >= deadline {
+                return text;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    /// Test that control keys reach a terminal process
+    #[tokio::test]
+    async fn test_send_control_keys_to_pty() {
+        let pid = spawn_test_process("test_tool").unwrap();
+
+        send_to_process(pid, ProcessInput::Raw { bytes: b"partial".to_vec() }).await.unwrap();
+        send_to_process(pid, ProcessInput::Key { key: ControlKey::Enter }).await.unwrap();
+        assert!(wait_for_output(pid, "partial\r\n").await.contains("partial\r\n"));
+
+        // Ctrl-D at the start of a line is EOF for cat
+        send_to_process(pid, ProcessInput::Key { key: ControlKey::CtrlD }).await.unwrap();
+        let info = wait_for_final_status(pid).await;
+        assert_eq!(info.status, ProcessStatus::Exited);
+        assert!(send_to_process(pid, line("too late")).await.is_err());
+    }
+
+    /// Test that piped runtimes read input from stdin
+    #[tokio::test]
+    async fn test_send_to_piped_stdin() {
+        let child = spawn_piped(
+            Command::new("sh").args(["-c", "read answer; echo got=$answer; cat"]),
+            &ResolvedEnv::default(),
+        )
+        .unwrap();
+        let pid = register_piped_child(child, "test_runtime", "/tmp".to_string(), None).unwrap();
+
+        send_to_process(pid, line("yes")).await.unwrap();
+        assert!(wait_for_output(pid, "got=yes").await.contains("got=yes\n"));
+
+        // Ctrl-D closes stdin, so cat sees EOF
+        send_to_process(pid, ProcessInput::Key { key: ControlKey::CtrlD }).await.unwrap();
+        let info = wait_for_final_status(pid).await;
+        assert_eq!(info.status, ProcessStatus::Exited);
+    }
+
+    /// Test that Ctrl-C interrupts a piped runtime
+    #[tokio::test]
+    async fn test_ctrl_c_interrupts_piped_runtime() {
+        let child = spawn_piped(Command::new("sleep").arg("30"), &ResolvedEnv::default()).unwrap();
+        let pid = register_piped_child(child, "test_runtime", "/tmp".to_string(), None).unwrap();
+
+        send_to_process(pid, ProcessInput::Key { key: ControlKey::CtrlC }).await.unwrap();
+        let info = wait_for_final_status(pid).await;
+        assert_eq!(info.status, ProcessStatus::Crashed);
+        assert_eq!(info.exit_status.unwrap().signal, Some(libc::SIGINT));
+    }
+
+    /// Test that kill_process terminates gracefully with SIGTERM
+    #[tokio::test]
+    async fn test_kill_process_reports_exit_status() {
+        let pid = spawn_test_process("test_tool").unwrap();
+
+        let info = kill_process(pid, None).await.unwrap();
+        assert_eq!(info.status, ProcessStatus::Stopped);
+        assert_eq!(
+            info.exit_status,
+            Some(ExitStatusInfo {
+                code: None,
+                signal: Some(libc::SIGTERM),
+            })
+        );
+    }
+
+    /// Test that a process ignoring SIGTERM is killed after the grace period
+    #[tokio::test]
+    async fn test_kill_process_escalates_to_sigkill() {
+        let child = spawn_piped(Command::new("sh").args([
+            "-c",
+            "trap '' TERM; echo ready; while true; do sleep 0.1; done",
+        ]), &ResolvedEnv::default())
+        .unwrap();
+        let pid = register_piped_child(child, "test_runtime", "/tmp".to_string(), None).unwrap();
+        wait_for_output(pid, "ready").await;
+
+        let started = std::time::Instant::now();
+        let info = kill_process(pid, Some(300)).await.unwrap();
+
+        assert!(started.elapsed() >= std::time::Duration::from_millis(300));
+        assert_eq!(info.status, ProcessStatus::Stopped);
+        assert_eq!(info.exit_status.unwrap().signal, Some(libc::SIGKILL));
+    }
+
+    /// Wait until the reaper has moved a process out of the running states
+    async fn wait_for_final_status(pid: u32) -> ProcessInfo {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        loop {
+            let info = process_registry().lock().unwrap().get(&pid).cloned().unwrap();
+            if info.ended_at.is_some() || std::time::Instant::now() >= deadline {
+                return info;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    }
+
+    /// Test that a process exiting non-zero on its own is marked as crashed
+    #[tokio::test]
+    async fn test_reaper_records_crash() {
+        let child = spawn_piped(Command::new("sh").args(["-c", "exit 3"]), &ResolvedEnv::default()).unwrap();
+        let pid = register_piped_child(child, "test_runtime", "/tmp".to_string(), None).unwrap();
+
+        let info = wait_for_final_status(pid).await;
+        assert_eq!(info.status, ProcessStatus::Crashed);
+        assert_eq!(
+            info.exit_status,
+            Some(ExitStatusInfo {
+                code: Some(3),
+                signal: None,
+            })
+        );
+        assert!(info.started_at > 0);
+        assert!(info.ended_at.unwrap() >= info.started_at);
+        assert!(!child_handles().lock().unwrap().contains_key(&pid));
+    }
+
+    /// Test that a clean exit is distinguished from a crash
+    #[tokio::test]
+    async fn test_reaper_records_clean_exit() {
+        let child = spawn_piped(Command::new("sh").args(["-c", "exit 0"]), &ResolvedEnv::default()).unwrap();
+        let pid = register_piped_child(child, "test_runtime", "/tmp".to_string(), None).unwrap();
+
+        let info = wait_for_final_status(pid).await;
+        assert_eq!(info.status, ProcessStatus::Exited);
+        assert!(info.exit_status.unwrap().success());
+        assert_eq!(info.command_line, ["sh", "-c", "exit 0"]);
+    }
+
+    /// Test that a process killed from outside the app is marked as crashed
+    #[tokio::test]
+    async fn test_reaper_records_external_signal() {
+        let pid = spawn_test_process("test_tool").unwrap();
+        unsafe {
+            libc::kill(pid as i32, libc::SIGKILL);
+        }
+
+        let info = wait_for_final_status(pid).await;
+        assert_eq!(info.status, ProcessStatus::Crashed);
+        assert_eq!(info.exit_status.unwrap().signal, Some(libc::SIGKILL));
+
+        // Killing an already-exited process leaves its final state alone
+        let info = kill_process(pid, None).await.unwrap();
+        assert_eq!(info.status, ProcessStatus::Crashed);
+    }
+
+    /// Test that env mappings reach a runtime's environment
+    #[tokio::test]
+    async fn test_start_runtime_injects_env() {
+        let env = EnvMappings::from([(
+            "AITM_TEST_VALUE".to_string(),
+            crate::spawn_env::EnvSource::Literal {
+                value: "injected".to_string(),
+            },
+        )]);
+        let pid = start_runtime(
+            "custom_env_test".parse().unwrap(),
+            "sh".to_string(),
+            vec!["-c".to_string(), "echo value=$AITM_TEST_VALUE".to_string()],
+            None,
+            Some(env),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let text = wait_for_output(pid, "value=").await;
+        assert!(text.contains("value=injected"), "Unexpected output: {:?}", text);
+    }
+
+    /// Test that rlimits are in place when a runtime starts
+    #[tokio::test]
+    async fn test_start_runtime_applies_rlimits() {
+        let limits = ResourceLimits {
+            max_open_files: Some(64),
+            ..Default::default()
+        };
+        let pid = start_runtime(
+            "custom_rlimit_test".parse().unwrap(),
+            "sh".to_string(),
+            vec!["-c".to_string(), "echo files=$(ulimit -n)".to_string()],
+            None,
+            None,
+            Some(limits),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let text = wait_for_output(pid, "files=").await;
+        assert!(text.contains("files=64"), "Unexpected output: {:?}", text);
+    }
+
+    /// Test that a runtime hitting its CPU time limit is reported as such
+    #[tokio::test]
+    async fn test_cpu_limit_violation() {
+        let limits = ResourceLimits {
+            max_cpu_seconds: Some(1),
+            ..Default::default()
+        };
+        let pid = start_runtime(
+            "custom_cpu_limit_test".parse().unwrap(),
+            "sh".to_string(),
+            vec!["-c".to_string(), "while :; do :; done".to_string()],
+            None,
+            None,
+            Some(limits),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let info = wait_for_final_status(pid).await;
+        assert_eq!(info.status, ProcessStatus::LimitExceeded);
+        assert_eq!(info.limit_violation, Some(LimitViolation::CpuTime));
+    }
+
+    /// Test that pseudo-terminal processes get their limits after spawn
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_pty_process_applies_rlimits() {
+        let limits = ResourceLimits {
+            max_open_files: Some(48),
+            max_cpu_seconds: Some(600),
+            ..Default::default()
+        };
+        let pid = spawn_pty_process(
+            "test_tool",
+            "cat",
+            "/tmp",
+            &[],
+            PtySize::default(),
+            &ResolvedEnv::default(),
+            &limits,
+        )
+        .unwrap();
+
+        // The limits are set by the shell that execs the tool under the same PID
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let proc_limits = loop {
+            let proc_limits = std::fs::read_to_string(format!("/proc/{}/limits", pid)).unwrap();
+            let exe = std::fs::read_link(format!("/proc/{}/exe", pid)).unwrap_or_default();
+            if exe.ends_with("cat") || std::time::Instant::now() >= deadline {
+                break proc_limits;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        };
+        let fields = |name: &str| -> Vec<String> {
+            let line = proc_limits.lines().find(|line| line.starts_with(name)).unwrap();
+            line[name.len()..].split_whitespace().map(str::to_string).collect()
+        };
+        assert_eq!(fields("Max open files")[..2], ["48", "48"]);
+        assert_eq!(fields("Max cpu time")[..2], ["600", "605"]);
+
+        let info = process_registry().lock().unwrap().get(&pid).cloned().unwrap();
+        assert_eq!(info.command_line, ["cat"]);
+        kill_process(pid, None).await.unwrap();
+    }
+
+    /// Readiness gated on a log line, with a short timeout
+    fn log_readiness(pattern: &str, timeout_ms: u64) -> Option<Readiness> {
+        Some(Readiness {
+            probe: Some(readiness::ReadinessProbe::LogLine {
+                pattern: pattern.to_string(),
+            }),
+            timeout_ms,
+        })
+    }
+
+    /// Test that start_runtime resolves only once the readiness probe passes
+    #[tokio::test]
+    async fn test_start_runtime_waits_for_readiness() {
+        let pid = start_runtime(
+            "custom_ready_test".parse().unwrap(),
+            "sh".to_string(),
+            vec!["-c".to_string(), "sleep 0.3; echo listening on 4000; sleep 30".to_string()],
+            None,
+            None,
+            None,
+            log_readiness(r"listening on \d+", 5_000),
+        )
+        .await
+        .unwrap();
+
+        assert!(output_text(pid).contains("listening on 4000"));
+        kill_process(pid, Some(1_000)).await.unwrap();
+    }
+
+    /// Test that a runtime exiting before it is ready reports its stderr
+    #[tokio::test]
+    async fn test_start_runtime_reports_early_exit() {
+        let err = start_runtime(
+            "custom_early_exit_test".parse().unwrap(),
+            "sh".to_string(),
+            vec!["-c".to_string(), "echo bind: address already in use >&2; exit 1".to_string()],
+            None,
+            None,
+            None,
+            log_readiness("listening", 5_000),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.contains("exited before it was ready"), "{}", err);
+        assert!(err.contains("address already in use"), "{}", err);
+    }
+
+    /// Test that a runtime that never becomes ready times out and is stopped
+    #[tokio::test]
+    async fn test_start_runtime_readiness_timeout() {
+        let runtime_id: RuntimeId = "custom_never_ready_test".parse().unwrap();
+        let err = start_runtime(
+            runtime_id.clone(),
+            "sh".to_string(),
+            vec!["-c".to_string(), "echo loading >&2; sleep 30".to_string()],
+            None,
+            None,
+            None,
+            log_readiness("listening", 500),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.contains("was not ready after 500 ms"), "{}", err);
+        assert!(err.contains("loading"), "{}", err);
+        assert_eq!(find_runtime_pid(&runtime_id).unwrap(), None);
+    }
+
+    /// Test that stop_runtime signals the whole process group of a runtime
+    #[tokio::test]
+    async fn test_stop_runtime_kills_process_group() {
+        use sysinfo::{Pid, ProcessStatus as OsStatus, System};
+
+        let runtime_id: RuntimeId = "custom_group_test".parse().unwrap();
+        let pid = start_runtime(
+            runtime_id.clone(),
+            "sh".to_string(),
+            vec!["-c".to_string(), "sleep 30 & echo $!; wait".to_string()],
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let text = wait_for_output(pid, "\n").await;
+        let worker_pid: u32 = text.trim().parse().unwrap();
+
+        stop_runtime(runtime_id.clone(), Some(2000)).await.unwrap();
+
+        let info = process_registry().lock().unwrap().get(&pid).cloned().unwrap();
+        assert_eq!(info.status, ProcessStatus::Stopped);
+        assert!(info.exit_status.is_some());
+
+        // The forked worker must be gone too (or at most an unreaped zombie)
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let mut system = System::new();
+        system.refresh_process(Pid::from_u32(worker_pid));
+        let worker_status = system.process(Pid::from_u32(worker_pid)).map(|p| p.status());
+        assert!(matches!(worker_status, None | Some(OsStatus::Zombie)));
+
+        // Nothing left to stop
+        assert!(stop_runtime(runtime_id, None).await.is_err());
+    }
+
+    /// Test that the registered PID belongs to a live OS process
+    #[tokio::test]
+    async fn test_spawned_pid_is_real_process() {
+        use sysinfo::{Pid, System};
+
+        let pid = spawn_test_process("test_tool").unwrap();
+
+        let mut system = System::new();
+        assert!(
+            system.refresh_process(Pid::from_u32(pid)),
+            "Spawned PID should exist in the OS process table"
+        );
+
+        kill_process(pid, None).await.unwrap();
+    }
+
+    /// Test that a CLI process terminal can be resized
+    #[tokio::test]
+    async fn test_resize_process() {
+        let pid = spawn_test_process("test_tool").unwrap();
+
+        assert!(resize_process(pid, 40, 120).await.is_ok());
+        let size = pty_handles()
+            .lock()
+            .unwrap()
+            .get(&pid)
+            .unwrap()
+            .master
+            .get_size()
+            .unwrap();
+        assert_eq!((size.rows, size.cols), (40, 120));
+
+        kill_process(pid, None).await.unwrap();
+        assert!(resize_process(pid, 40, 120).await.is_err());
+    }
+
+    /// Test that tools without an adapter are rejected
+    #[tokio::test]
+    async fn test_spawn_unknown_tool() {
+        let result = spawn_cli_process(
+            "not_a_tool".to_string(),
+            "/tmp".to_string(),
+            vec![],
+            None,
+            None,
+            None,
+            None,
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    /// Test that a missing working directory is reported instead of falling back
+    #[test]
+    fn test_spawn_missing_working_dir() {
+        let result = spawn_pty_process(
+            "test_tool",
+            "cat",
+            "/nonexistent/working/dir",
+            &[],
+            PtySize::default(),
+            &ResolvedEnv::default(),
+            &ResourceLimits::default(),
+        );
+        assert!(result.is_err());
+    }
+
     /// Test error handling for non-existent process
     #[tokio::test]
     async fn test_nonexistent_process_error() {
-        let fake_pid = 999999u32;
+        let fake_pid = crate::test_support::fake_pid();
 
         // Try to send to non-existent process
-        let result = send_to_process(fake_pid, "test".to_string()).await;
+        let result = send_to_process(fake_pid, line("test")).await;
         assert!(result.is_err(), "Should fail for non-existent process");
 
         // Try to kill non-existent process
-        let result = kill_process(fake_pid).await;
+        let result = kill_process(fake_pid, None).await;
         assert!(result.is_err(), "Should fail for non-existent process");
 
         // Try to get output from non-existent process
-        let result = get_process_output(fake_pid);
+        let result = get_process_output(fake_pid, None, None, None);
         assert!(result.is_err(), "Should fail for non-existent process");
     }
 }
 
 #[cfg(test)]
 mod unit_tests {
-    use super::super::{ProcessInfo, ProcessStatus, generate_pid};
+    use super::super::{ProcessInfo, ProcessStatus, decode_utf8_chunk};
 
     #[test]
     fn test_process_status_serialization() {
@@ -208,6 +696,10 @@ mod unit_tests {
         let status = ProcessStatus::Error;
         let json = serde_json::to_string(&status).unwrap();
         assert_eq!(json, "\"Error\"");
+
+        let status = ProcessStatus::Crashed;
+        let json = serde_json::to_string(&status).unwrap();
+        assert_eq!(json, "\"Crashed\"");
     }
 
     #[test]
@@ -217,6 +709,13 @@ mod unit_tests {
             tool_id: "test_tool".to_string(),
             working_dir: "/tmp".to_string(),
             status: ProcessStatus::Running,
+            runtime_id: None,
+            exit_status: None,
+            started_at: 1_700_000_000_000,
+            ended_at: None,
+            restart_count: 0,
+            command_line: vec!["test_tool".to_string()],
+            limit_violation: None,
         };
 
         let json = serde_json::to_string(&info).unwrap();
@@ -226,18 +725,17 @@ mod unit_tests {
     }
 
     #[test]
-    fn test_generate_pid_uniqueness() {
-        let pid1 = generate_pid();
-        let pid2 = generate_pid();
-
-        // PIDs should be positive
-        assert!(pid1 > 0);
-        assert!(pid2 > 0);
-
-        // PIDs generated in quick succession might be different
-        // (though not guaranteed due to timing)
-        // At minimum, they should be valid u32 values
-        assert!(pid1 <= u32::MAX);
-        assert!(pid2 <= u32::MAX);
+    fn test_decode_utf8_chunk_split_sequence() {
+        let bytes = "héllo".as_bytes();
+        let mut pending = Vec::new();
+
+        // Split in the middle of the two-byte 'é'
+        let first = decode_utf8_chunk(&mut pending, &bytes[..2]);
+        assert_eq!(first, "h");
+        assert_eq!(pending.len(), 1);
+
+        let second = decode_utf8_chunk(&mut pending, &bytes[2..]);
+        assert_eq!(second, "éllo");
+        assert!(pending.is_empty());
     }
 }
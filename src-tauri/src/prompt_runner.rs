@@ -0,0 +1,731 @@
+// Prompt Runner - runs one prompt through a CLI tool without a terminal
+// Builds the argv from the adapter's invocation template and collects the answer, usage and changed files
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Notify;
+
+use crate::cli_adapter::{get_available_adapters, CLIAdapter};
+use crate::database::{self, Message};
+use crate::limits;
+use crate::output;
+use crate::spawn_env::{self, ResolvedEnv};
+use crate::tool_detection;
+use crate::tool_events::{self, assistant_text, EventParser, ToolEvent};
+
+/// How long a prompt may run when the request doesn't say
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Placeholders an invocation template can use
+const PLACEHOLDERS: &[&str] = &["prompt", "model", "file", "project_dir"];
+
+/// Lines of stderr kept for the error of a failed run
+const STDERR_TAIL_LINES: usize = 20;
+
+/// How an adapter's tool is run for a single prompt
+///
+/// Each argument may contain `{prompt}`, `{model}`, `{file}` and
+/// `{project_dir}`. An argument naming a value the request doesn't have,
+/// such as `--model={model}` without a model, is left out. An argument with
+/// `{file}` is repeated for every context file; without one the files are
+/// listed at the end of the prompt.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Invocation {
+    /// Arguments after the executable
+    pub args: Vec<String>,
+    /// Write the prompt to stdin instead of passing it as an argument
+    #[serde(default)]
+    pub prompt_on_stdin: bool,
+}
+
+impl Invocation {
+    pub fn new(args: &[&str]) -> Self {
+        Self {
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            prompt_on_stdin: false,
+        }
+    }
+
+    /// Problems with the template, empty if it is usable
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for arg in &self.args {
+            for name in placeholders(arg) {
+                if !PLACEHOLDERS.contains(&name) {
+                    problems.push(format!("unknown placeholder '{{{}}}' in '{}'", name, arg));
+                }
+            }
+        }
+        let has_prompt = self.args.iter().any(|arg| arg.contains("{prompt}"));
+        if !has_prompt && !self.prompt_on_stdin {
+            problems.push("needs a '{prompt}' argument or prompt_on_stdin".to_string());
+        }
+        problems
+    }
+
+    /// The arguments for a request, and the prompt to write to stdin if the template asks for that
+    pub fn build(&self, request: &PromptRequest) -> (Vec<String>, Option<String>) {
+        let mut prompt = request.prompt.clone();
+        let files_as_args = self.args.iter().any(|arg| arg.contains("{file}"));
+        if !files_as_args && !request.context_files.is_empty() {
+            prompt.push_str("\n\nContext files:");
+            for file in &request.context_files {
+                prompt.push_str("\n- ");
+                prompt.push_str(file);
+            }
+        }
+
+        let inline_prompt = (!self.prompt_on_stdin).then_some(prompt.as_str());
+        let mut values = HashMap::from([("project_dir", Some(request.project_dir.as_str()))]);
+        values.insert("prompt", inline_prompt);
+        values.insert("model", request.model.as_deref());
+
+        let mut args = Vec::new();
+        for arg in &self.args {
+            if arg.contains("{file}") {
+                for file in &request.context_files {
+                    values.insert("file", Some(file.as_str()));
+                    args.extend(substitute(arg, &values));
+                }
+                values.remove("file");
+            } else {
+                args.extend(substitute(arg, &values));
+            }
+        }
+        args.extend(request.extra_args.iter().cloned());
+
+        let stdin = self.prompt_on_stdin.then_some(prompt);
+        (args, stdin)
+    }
+}
+
+/// Names of the `{placeholders}` in an argument
+fn placeholders(arg: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        names.push(&rest[start + 1..start + len]);
+        rest = &rest[start + len + 1..];
+    }
+    names
+}
+
+/// Fill in an argument, or `None` if a value it needs is missing
+fn substitute(arg: &str, values: &HashMap<&str, Option<&str>>) -> Option<String> {
+    let mut filled = arg.to_string();
+    for name in placeholders(arg) {
+        let value = values.get(name).copied().flatten()?;
+        filled = filled.replace(&format!("{{{}}}", name), value);
+    }
+    Some(filled)
+}
+
+/// A prompt to run through a tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptRequest {
+    /// Caller-chosen ID, used to cancel the run
+    pub run_id: String,
+    pub tool_id: String,
+    /// Directory the tool runs in
+    pub project_dir: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Files the tool should look at, relative to the project or absolute
+    #[serde(default)]
+    pub context_files: Vec<String>,
+    /// Arguments appended after the template's
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// Time allowed before the tool is killed; `DEFAULT_TIMEOUT` if unset
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Session the prompt and answer are saved to
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+/// Tokens used by a run, summed over its turns
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PromptUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub cost_usd: Option<f64>,
+}
+
+/// Outcome of a prompt run that finished on its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptResult {
+    pub run_id: String,
+    pub pid: u32,
+    /// The tool's final answer
+    pub answer: String,
+    /// Token usage, when the tool reports it
+    pub usage: Option<PromptUsage>,
+    /// Files the run changed, relative to the project directory where possible
+    pub modified_files: Vec<String>,
+    /// Parsed events, empty when the tool only writes text
+    pub events: Vec<ToolEvent>,
+    pub exit_code: Option<i32>,
+    /// Errors the tool reported, plus the end of stderr if it failed
+    pub errors: Vec<String>,
+    pub duration_ms: u64,
+}
+
+/// Cancel signals of running prompts, by run ID
+fn active_runs() -> &'static Mutex<HashMap<String, Arc<Notify>>> {
+    static RUNS: OnceLock<Mutex<HashMap<String, Arc<Notify>>>> = OnceLock::new();
+    RUNS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Removes a run from `active_runs` however it ends
+struct RunGuard(String);
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        if let Ok(mut runs) = active_runs().lock() {
+            runs.remove(&self.0);
+        }
+    }
+}
+
+/// Run a single prompt through a tool and wait for its answer
+///
+/// Fails if the tool can't be started, times out or is cancelled; a tool that
+/// exits with an error still returns a result, with `errors` filled in.
+#[tauri::command]
+pub async fn run_prompt(request: PromptRequest) -> Result<PromptResult, String> {
+    let adapter = get_available_adapters()
+        .into_iter()
+        .find(|a| a.id == request.tool_id)
+        .ok_or_else(|| format!("Unknown tool: {}", request.tool_id))?;
+    run_with_adapter(&adapter, request).await
+}
+
+/// Cancel a running prompt; the tool is killed and `run_prompt` fails
+#[tauri::command]
+pub fn cancel_prompt(run_id: String) -> Result<(), String> {
+    let runs = active_runs().lock().map_err(|e| e.to_string())?;
+    let cancel = runs
+        .get(&run_id)
+        .ok_or_else(|| format!("No running prompt with ID {}", run_id))?;
+    cancel.notify_one();
+    Ok(())
+}
+
+pub(crate) async fn run_with_adapter(adapter: &CLIAdapter, request: PromptRequest) -> Result<PromptResult, String> {
+    let invocation = adapter
+        .invocation
+        .as_ref()
+        .ok_or_else(|| format!("{} cannot run prompts non-interactively", adapter.name))?;
+    let project_dir = Path::new(&request.project_dir);
+    if !project_dir.is_dir() {
+        return Err(format!("Project directory does not exist: {}", request.project_dir));
+    }
+    for file in &request.context_files {
+        if !project_dir.join(file).is_file() {
+            return Err(format!("Context file not found: {}", file));
+        }
+    }
+
+    let cancel = Arc::new(Notify::new());
+    {
+        let mut runs = active_runs().lock().map_err(|e| e.to_string())?;
+        if runs.contains_key(&request.run_id) {
+            return Err(format!("A prompt with ID {} is already running", request.run_id));
+        }
+        runs.insert(request.run_id.clone(), cancel.clone());
+    }
+    let _guard = RunGuard(request.run_id.clone());
+
+    let (args, stdin) = invocation.build(&request);
+    let format = adapter.output_format.filter(|format| format.is_enabled_by(&args));
+    let env = spawn_env::resolve(&adapter.env).await?;
+    let timeout = request.timeout_ms.map(Duration::from_millis).unwrap_or(DEFAULT_TIMEOUT);
+    let changes_before = git_changes(project_dir).await;
+    let started = Instant::now();
+
+    let mut child = spawn(adapter, project_dir, &args, stdin.is_some(), &env)?;
+    let pid = child.id().unwrap_or_default();
+    let input = child.stdin.take().zip(stdin);
+    // Written alongside reading the output, so a tool that doesn't drain its
+    // input can't block the run past its timeout
+    let feed = async move {
+        if let Some((mut pipe, prompt)) = input {
+            // A tool that exits without reading its input is reported by its exit status
+            let _ = pipe.write_all(prompt.as_bytes()).await;
+        }
+    };
+
+    let mut run = Run {
+        parser: format.map(EventParser::new),
+        events: Vec::new(),
+        stdout: String::new(),
+        stderr: String::new(),
+    };
+    let outcome = tokio::select! {
+        (status, ()) = async { tokio::join!(run.collect(&mut child, pid), feed) } => {
+            status.map_err(|e| format!("Failed to read output of {}: {}", adapter.name, e))
+        }
+        _ = tokio::time::sleep(timeout) => Err(format!(
+            "Prompt {} timed out after {} s",
+            request.run_id,
+            timeout.as_secs_f32()
+        )),
+        _ = cancel.notified() => Err(format!("Prompt {} was cancelled", request.run_id)),
+    };
+    let status = match outcome {
+        Ok(status) => status,
+        Err(e) => {
+            kill(&mut child, pid).await;
+            return Err(e);
+        }
+    };
+
+    let answer = if run.parser.is_some() {
+        assistant_text(&run.events)
+    } else {
+        run.stdout.trim().to_string()
+    };
+    let mut errors: Vec<String> = run
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            ToolEvent::Error { message } => Some(message.clone()),
+            _ => None,
+        })
+        .collect();
+    if !status.success() {
+        errors.push(format!("{} exited with {}: {}", adapter.name, status, env.redact(&tail(&run.stderr))));
+    }
+
+    let mut modified_files = edited_files(&run.events, project_dir);
+    if let (Some(before), Some(after)) = (changes_before, git_changes(project_dir).await) {
+        modified_files.extend(changed_between(&before, &after));
+    }
+    let modified_files: Vec<String> = modified_files.into_iter().collect::<BTreeSet<_>>().into_iter().collect();
+
+    if let Some(session_id) = request.session_id.clone() {
+        save_prompt(pid, &request, session_id.clone());
+        tool_events::save_run(pid, &adapter.id, session_id, answer.clone(), &run.events);
+    }
+
+    Ok(PromptResult {
+        run_id: request.run_id,
+        pid,
+        answer,
+        usage: total_usage(&run.events),
+        modified_files,
+        events: run.events,
+        exit_code: status.code(),
+        errors,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Start the tool with piped output in its own process group
+fn spawn(
+    adapter: &CLIAdapter,
+    project_dir: &Path,
+    args: &[String],
+    piped_stdin: bool,
+    env: &ResolvedEnv,
+) -> Result<Child, String> {
+    let executable = tool_detection::resolve(&adapter.executable, &tool_detection::search_dirs());
+    let mut command = Command::new(&executable);
+    command
+        .args(args)
+        .current_dir(project_dir)
+        .stdin(if piped_stdin { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command.as_std_mut(), 0);
+    env.apply(command.as_std_mut());
+    if let Some(limits) = &adapter.limits {
+        limits::apply_to_command(command.as_std_mut(), limits);
+    }
+    command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", executable, e))
+}
+
+/// Kill the tool and anything it started, then reap it
+async fn kill(child: &mut Child, pid: u32) {
+    #[cfg(unix)]
+    let _ = crate::process::signal_process_group(pid, libc::SIGKILL);
+    #[cfg(not(unix))]
+    let _ = pid;
+    let _ = child.kill().await;
+}
+
+/// Output gathered from a running tool
+struct Run {
+    parser: Option<EventParser>,
+    events: Vec<ToolEvent>,
+    stdout: String,
+    stderr: String,
+}
+
+impl Run {
+    /// Read both output streams to the end and wait for the tool to exit
+    async fn collect(&mut self, child: &mut Child, pid: u32) -> std::io::Result<ExitStatus> {
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let (parser, events, stdout_text) = (&mut self.parser, &mut self.events, &mut self.stdout);
+
+        let read_stdout = async {
+            let Some(stdout) = stdout else {
+                return Ok(());
+            };
+            let mut reader = BufReader::new(stdout);
+            let mut line = Vec::new();
+            while reader.read_until(b'\n', &mut line).await? > 0 {
+                let text = String::from_utf8_lossy(&line);
+                match parser {
+                    Some(parser) => {
+                        let parsed = parser.feed(&text);
+                        events.extend(parsed.iter().cloned());
+                        tool_events::emit_all(pid, parsed);
+                    }
+                    None => stdout_text.push_str(&text),
+                }
+                line.clear();
+            }
+            if let Some(parser) = parser {
+                let parsed = parser.finish();
+                events.extend(parsed.iter().cloned());
+                tool_events::emit_all(pid, parsed);
+            }
+            Ok::<_, std::io::Error>(())
+        };
+        let read_stderr = read_to_string(stderr, &mut self.stderr);
+
+        let (stdout_result, stderr_result) = tokio::join!(read_stdout, read_stderr);
+        stdout_result?;
+        stderr_result?;
+        child.wait().await
+    }
+}
+
+async fn read_to_string(stream: Option<impl AsyncRead + Unpin>, text: &mut String) -> std::io::Result<()> {
+    let Some(mut stream) = stream else {
+        return Ok(());
+    };
+    let mut bytes = Vec::new();
+    stream.read_to_end(&mut bytes).await?;
+    text.push_str(&String::from_utf8_lossy(&bytes));
+    Ok(())
+}
+
+/// The last lines of a tool's stderr
+fn tail(text: &str) -> String {
+    let lines: Vec<&str> = text.trim().lines().collect();
+    lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n")
+}
+
+/// Usage summed over every usage event, `None` if there were none
+fn total_usage(events: &[ToolEvent]) -> Option<PromptUsage> {
+    let mut total: Option<PromptUsage> = None;
+    for event in events {
+        if let ToolEvent::Usage {
+            input_tokens,
+            output_tokens,
+            cached_input_tokens,
+            cost_usd,
+        } = event
+        {
+            let sum = total.get_or_insert_with(PromptUsage::default);
+            sum.input_tokens += input_tokens;
+            sum.output_tokens += output_tokens;
+            sum.cached_input_tokens += cached_input_tokens;
+            if let Some(cost) = cost_usd {
+                sum.cost_usd = Some(sum.cost_usd.unwrap_or(0.0) + cost);
+            }
+        }
+    }
+    total
+}
+
+/// Files named by edit events, relative to the project where possible
+fn edited_files(events: &[ToolEvent], project_dir: &Path) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            ToolEvent::FileEdit { path, .. } => Some(
+                Path::new(path)
+                    .strip_prefix(project_dir)
+                    .map(|relative| relative.display().to_string())
+                    .unwrap_or_else(|_| path.clone()),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Files git sees as changed or untracked, with their modification times
+///
+/// `None` outside a git work tree or without git. Tools also change files
+/// through shell commands, which no edit event reports.
+async fn git_changes(project_dir: &Path) -> Option<HashMap<String, Option<SystemTime>>> {
+    let output = Command::new("git")
+        .args(["status", "--porcelain=v1", "-z", "--untracked-files=all"])
+        .current_dir(project_dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let root = git_root(project_dir).await.unwrap_or_else(|| project_dir.to_path_buf());
+    let mut changes = HashMap::new();
+    let mut entries = output.stdout.split(|&b| b == 0);
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let path = String::from_utf8_lossy(&entry[3..]).into_owned();
+        // A rename is followed by the path it was renamed from
+        if matches!(entry[0], b'R' | b'C') {
+            entries.next();
+        }
+        let modified = std::fs::metadata(root.join(&path)).and_then(|m| m.modified()).ok();
+        let path = root
+            .join(&path)
+            .strip_prefix(project_dir)
+            .map(|relative| relative.display().to_string())
+            .unwrap_or(path);
+        changes.insert(path, modified);
+    }
+    Some(changes)
+}
+
+async fn git_root(project_dir: &Path) -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(project_dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.status.success())?;
+    Some(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/// Files that became dirty, or were touched again, between two snapshots
+fn changed_between(
+    before: &HashMap<String, Option<SystemTime>>,
+    after: &HashMap<String, Option<SystemTime>>,
+) -> Vec<String> {
+    let mut changed: HashSet<&String> = after
+        .iter()
+        .filter(|(path, modified)| before.get(*path) != Some(modified))
+        .map(|(path, _)| path)
+        .collect();
+    // Dirty before and clean now: the run reverted it
+    changed.extend(before.keys().filter(|path| !after.contains_key(*path)));
+    changed.into_iter().cloned().collect()
+}
+
+/// Store the prompt as the user message the answer replies to
+fn save_prompt(pid: u32, request: &PromptRequest, session_id: String) {
+    let Some(db_path) = database::current_database() else {
+        return;
+    };
+    let timestamp = output::now_millis();
+    let message = Message {
+        id: format!("{}-{}-prompt", timestamp, pid),
+        session_id,
+        role: "user".to_string(),
+        content: request.prompt.clone(),
+        timestamp,
+        metadata: Some(
+            serde_json::json!({
+                "tool_id": request.tool_id,
+                "model": request.model,
+                "context_files": request.context_files,
+            })
+            .to_string(),
+        ),
+    };
+    if let Err(e) = database::insert_message(&db_path, &message) {
+        eprintln!("Warning: failed to save prompt {}: {}", request.run_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::test_support::{adapter_named, fake_executable};
+
+    fn request(prompt: &str) -> PromptRequest {
+        PromptRequest {
+            run_id: "run-1".to_string(),
+            tool_id: "codex".to_string(),
+            project_dir: "/work".to_string(),
+            prompt: prompt.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_args() {
+        let invocation = Invocation::new(&["exec", "--json", "--model={model}", "{prompt}"]);
+        let (args, stdin) = invocation.build(&request("fix it"));
+        assert_eq!(args, vec!["exec", "--json", "fix it"]);
+        assert_eq!(stdin, None);
+
+        let mut with_model = request("fix it");
+        with_model.model = Some("o4-mini".to_string());
+        with_model.context_files = vec!["src/lib.rs".to_string()];
+        with_model.extra_args = vec!["--full-auto".to_string()];
+        let (args, _) = invocation.build(&with_model);
+        assert_eq!(
+            args,
+            vec!["exec", "--json", "--model=o4-mini", "fix it\n\nContext files:\n- src/lib.rs", "--full-auto"]
+        );
+
+        let files = Invocation {
+            args: vec!["--message-file=-".to_string(), "--file".to_string(), "{file}".to_string()],
+            prompt_on_stdin: true,
+        };
+        with_model.context_files.push("README.md".to_string());
+        let (args, stdin) = files.build(&with_model);
+        assert_eq!(args, vec!["--message-file=-", "--file", "src/lib.rs", "README.md", "--full-auto"]);
+        assert_eq!(stdin.as_deref(), Some("fix it"));
+    }
+
+    #[test]
+    fn test_template_problems() {
+        assert!(Invocation::new(&["-p", "{prompt}"]).problems().is_empty());
+        let problems = Invocation::new(&["--model", "{modle}"]).problems();
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].contains("{modle}"));
+    }
+
+    #[test]
+    fn test_usage_and_changes() {
+        let usage = |input, cost| ToolEvent::Usage {
+            input_tokens: input,
+            output_tokens: 10,
+            cached_input_tokens: 0,
+            cost_usd: cost,
+        };
+        let total = total_usage(&[usage(100, None), usage(50, Some(0.5))]).unwrap();
+        assert_eq!((total.input_tokens, total.output_tokens, total.cost_usd), (150, 20, Some(0.5)));
+        assert_eq!(total_usage(&[]), None);
+
+        let time = SystemTime::UNIX_EPOCH;
+        let later = time + Duration::from_secs(1);
+        let before = HashMap::from([("a".to_string(), Some(time)), ("b".to_string(), Some(time))]);
+        let after = HashMap::from([("a".to_string(), Some(time)), ("b".to_string(), Some(later)), ("c".to_string(), None)]);
+        let mut changed = changed_between(&before, &after);
+        changed.sort();
+        assert_eq!(changed, vec!["b", "c"]);
+    }
+
+    #[cfg(unix)]
+    fn fake_tool(dir: &Path, script: &str) -> CLIAdapter {
+        let executable = fake_executable(dir, "fake-codex", script);
+        let mut adapter = adapter_named("codex");
+        adapter.executable = executable.display().to_string();
+        adapter.env.clear();
+        adapter
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_prompt_with_json_tool() {
+        let tool_dir = tempfile::TempDir::new().unwrap();
+        let project = tempfile::TempDir::new().unwrap();
+        std::fs::write(project.path().join("notes.md"), "todo").unwrap();
+        let adapter = fake_tool(
+            tool_dir.path(),
+            r#"echo "$@" > args.txt
+echo '{"type":"item.completed","item":{"id":"i1","type":"file_change","changes":[{"path":"src/new.rs","kind":"add"}],"status":"completed"}}'
+echo '{"type":"item.completed","item":{"id":"i2","type":"agent_message","text":"All done."}}'
+echo '{"type":"turn.completed","usage":{"input_tokens":900,"cached_input_tokens":100,"output_tokens":60}}'
+"#,
+        );
+
+        let mut request = request("summarize");
+        request.run_id = "prompt-runner-json-test".to_string();
+        request.project_dir = project.path().display().to_string();
+        request.context_files = vec!["notes.md".to_string()];
+        let result = run_with_adapter(&adapter, request).await.unwrap();
+
+        assert_eq!(result.answer, "All done.");
+        assert_eq!(result.exit_code, Some(0));
+        assert!(result.errors.is_empty());
+        assert_eq!(result.usage.unwrap().input_tokens, 900);
+        assert_eq!(result.modified_files, vec!["src/new.rs"]);
+        let args = std::fs::read_to_string(project.path().join("args.txt")).unwrap();
+        assert!(args.starts_with("exec --json summarize"), "{}", args);
+        assert!(args.contains("- notes.md"), "{}", args);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_prompt_failures() {
+        let tool_dir = tempfile::TempDir::new().unwrap();
+        let project = tempfile::TempDir::new().unwrap();
+        let project_dir = project.path().display().to_string();
+
+        let failing = fake_tool(tool_dir.path(), "echo 'no credentials' >&2\nexit 3\n");
+        let mut failed = request("hi");
+        failed.run_id = "prompt-runner-failing-test".to_string();
+        failed.project_dir = project_dir.clone();
+        let result = run_with_adapter(&failing, failed).await.unwrap();
+        assert_eq!(result.exit_code, Some(3));
+        assert!(result.errors[0].contains("no credentials"), "{:?}", result.errors);
+
+        let mut missing = request("hi");
+        missing.project_dir = project_dir.clone();
+        missing.context_files = vec!["missing.rs".to_string()];
+        let err = run_with_adapter(&failing, missing).await.unwrap_err();
+        assert!(err.contains("missing.rs"), "{}", err);
+
+        let slow = fake_tool(tool_dir.path(), "sleep 30\n");
+        let mut timed_out = request("hi");
+        timed_out.run_id = "prompt-runner-timeout-test".to_string();
+        timed_out.project_dir = project_dir.clone();
+        timed_out.timeout_ms = Some(200);
+        let err = run_with_adapter(&slow, timed_out).await.unwrap_err();
+        assert!(err.contains("timed out"), "{}", err);
+
+        // A prompt larger than the pipe buffer, sent to a tool that never reads it
+        let mut deaf = slow.clone();
+        deaf.invocation.as_mut().unwrap().prompt_on_stdin = true;
+        let mut unread = request(&"x".repeat(1 << 20));
+        unread.run_id = "prompt-runner-unread-stdin-test".to_string();
+        unread.project_dir = project_dir.clone();
+        unread.timeout_ms = Some(200);
+        let err = run_with_adapter(&deaf, unread).await.unwrap_err();
+        assert!(err.contains("timed out"), "{}", err);
+
+        let mut cancelled = request("hi");
+        cancelled.run_id = "prompt-runner-cancel-test".to_string();
+        cancelled.project_dir = project_dir;
+        let run = tokio::spawn(async move { run_with_adapter(&slow, cancelled).await });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        cancel_prompt("prompt-runner-cancel-test".to_string()).unwrap();
+        let err = run.await.unwrap().unwrap_err();
+        assert!(err.contains("cancelled"), "{}", err);
+        assert!(cancel_prompt("prompt-runner-cancel-test".to_string()).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+// Readiness - decides when a freshly started runtime can take requests
+// Runtimes are probed over HTTP, TCP or their log output until they pass or time out
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+use crate::output::{self, OutputStream};
+use crate::process::{self, ProcessStatus};
+use crate::runtime_id::{RuntimeId, RuntimeKind};
+use crate::runtime_instance::Endpoint;
+
+/// Interval between probe attempts
+const PROBE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Time a single HTTP request or TCP connect may take
+const PROBE_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Time the output readers get to catch up after a runtime exits
+const OUTPUT_SETTLE_TIME: Duration = Duration::from_millis(100);
+
+/// Number of stderr lines included in a readiness error
+const STDERR_TAIL_LINES: usize = 20;
+
+/// How to tell that a runtime is ready
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ReadinessProbe {
+    /// Ready as soon as the process has been spawned
+    Started,
+    /// Ready once a GET request returns a success status
+    Http { url: String },
+    /// Ready once a TCP connection to `address` ("host:port") is accepted
+    Tcp { address: String },
+    /// Ready once a line of stdout or stderr matches the regex
+    LogLine { pattern: String },
+}
+
+impl ReadinessProbe {
+    /// Probe used for a runtime that doesn't configure its own
+    ///
+    /// Servers are probed on `endpoint`, where they were told to bind; a
+    /// custom runtime with a configured port is ready once that port accepts
+    /// connections.
+    pub fn default_for(runtime_id: &RuntimeId, endpoint: Option<Endpoint>) -> ReadinessProbe {
+        match (runtime_id.kind, endpoint) {
+            (RuntimeKind::Ollama, Some(endpoint)) => ReadinessProbe::Http {
+                url: endpoint.url("/api/version"),
+            },
+            (RuntimeKind::LocalAi, Some(endpoint)) => ReadinessProbe::Http {
+                url: endpoint.url("/readyz"),
+            },
+            (RuntimeKind::Custom, Some(endpoint)) => ReadinessProbe::Tcp {
+                address: endpoint.connect_address(),
+            },
+            _ => ReadinessProbe::Started,
+        }
+    }
+}
+
+/// Readiness settings for one runtime
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Readiness {
+    /// Probe to run; `None` uses the default for the runtime type
+    pub probe: Option<ReadinessProbe>,
+    /// Time the runtime has to become ready after it is spawned
+    #[serde(rename = "timeoutMs")]
+    pub timeout_ms: u64,
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Self {
+            probe: None,
+            timeout_ms: 30_000,
+        }
+    }
+}
+
+impl Readiness {
+    /// The probe to run for `runtime_id`, which binds to `endpoint`
+    pub fn probe_for(&self, runtime_id: &RuntimeId, endpoint: Option<Endpoint>) -> ReadinessProbe {
+        self.probe
+            .clone()
+            .unwrap_or_else(|| ReadinessProbe::default_for(runtime_id, endpoint))
+    }
+}
+
+/// Poll a runtime until its probe passes
+///
+/// `pid` is the runtime's process if we started one; if it exits first, or
+/// the timeout passes, the error includes the last lines of its stderr. A PID
+/// without a process entry, such as output fed in by a test, only times out.
+pub async fn wait_until_ready(
+    runtime_id: &RuntimeId,
+    pid: Option<u32>,
+    probe: &ReadinessProbe,
+    timeout: Duration,
+) -> Result<(), String> {
+    let pattern = match probe {
+        ReadinessProbe::LogLine { pattern } => Some(
+            Regex::new(pattern).map_err(|e| format!("Invalid readiness pattern '{}': {}", pattern, e))?,
+        ),
+        _ => None,
+    };
+    let client = reqwest::Client::builder()
+        .timeout(PROBE_ATTEMPT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let deadline = Instant::now() + timeout;
+    let mut next_seq = 0;
+    loop {
+        let ready = match probe {
+            ReadinessProbe::Started => true,
+            ReadinessProbe::Http { url } => client
+                .get(url)
+                .send()
+                .await
+                .is_ok_and(|response| response.status().is_success()),
+            ReadinessProbe::Tcp { address } => {
+                tokio::time::timeout(PROBE_ATTEMPT_TIMEOUT, TcpStream::connect(address.as_str()))
+                    .await
+                    .is_ok_and(|connected| connected.is_ok())
+            }
+            ReadinessProbe::LogLine { .. } => match (pid, &pattern) {
+                (Some(pid), Some(pattern)) => log_matches(pid, pattern, &mut next_seq),
+                _ => false,
+            },
+        };
+        if ready {
+            return Ok(());
+        }
+
+        if let Some(pid) = pid {
+            if process::status_of(pid).is_some_and(|status| status != ProcessStatus::Running) {
+                tokio::time::sleep(OUTPUT_SETTLE_TIME).await;
+                return Err(format!(
+                    "Runtime {} exited before it was ready{}",
+                    runtime_id,
+                    stderr_tail(pid)
+                ));
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "Runtime {} was not ready after {} ms{}",
+                runtime_id,
+                timeout.as_millis(),
+                pid.map(stderr_tail).unwrap_or_default()
+            ));
+        }
+        tokio::time::sleep(PROBE_INTERVAL).await;
+    }
+}
+
+/// Check output produced since `next_seq` for a line matching `pattern`
+fn log_matches(pid: u32, pattern: &Regex, next_seq: &mut u64) -> bool {
+    let Some(lines) = output::lines_from(pid, *next_seq) else {
+        return false;
+    };
+    if let Some(last) = lines.last() {
+        *next_seq = last.seq + 1;
+    }
+    lines.iter().any(|line| pattern.is_match(&line.data))
+}
+
+/// The last stderr lines of a process, formatted for an error message
+fn stderr_tail(pid: u32) -> String {
+    let lines = output::tail(pid, OutputStream::Stderr, STDERR_TAIL_LINES);
+    if lines.is_empty() {
+        return String::new();
+    }
+    format!("\nLast stderr output:\n{}", lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime_instance;
+    use crate::test_support::fake_pid;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    fn runtime_id() -> RuntimeId {
+        "custom_readiness_test".parse().unwrap()
+    }
+
+    #[test]
+    fn test_probe_serialization() {
+        let readiness: Readiness =
+            serde_json::from_str(r#"{"probe":{"type":"log-line","pattern":"listening"},"timeoutMs":5000}"#)
+                .unwrap();
+        assert_eq!(
+            readiness.probe,
+            Some(ReadinessProbe::LogLine {
+                pattern: "listening".to_string()
+            })
+        );
+        assert_eq!(readiness.timeout_ms, 5_000);
+
+        let readiness: Readiness = serde_json::from_str("{}").unwrap();
+        assert_eq!(readiness, Readiness::default());
+    }
+
+    #[test]
+    fn test_default_probes() {
+        let readiness = Readiness::default();
+        let id = |id: &str| id.parse::<RuntimeId>().unwrap();
+        let probe = |runtime: &str, env: &[(&str, &str)]| {
+            let runtime = id(runtime);
+            let lookup = |name: &str| env.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string());
+            readiness.probe_for(&runtime, runtime_instance::launch_endpoint(&runtime, &lookup))
+        };
+        assert_eq!(
+            probe("ollama_default_probe_test", &[]),
+            ReadinessProbe::Http {
+                url: "http://127.0.0.1:11434/api/version".to_string()
+            }
+        );
+        // An explicit OLLAMA_HOST is where the server binds, so it is probed there
+        assert_eq!(
+            probe("ollama_default_probe_test", &[("OLLAMA_HOST", "0.0.0.0:11500")]),
+            ReadinessProbe::Http {
+                url: "http://127.0.0.1:11500/api/version".to_string()
+            }
+        );
+        assert_eq!(
+            probe("localai_default_probe_test", &[]),
+            ReadinessProbe::Http {
+                url: "http://127.0.0.1:8080/readyz".to_string()
+            }
+        );
+        assert_eq!(probe("custom_default_probe_test", &[]), ReadinessProbe::Started);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_probe() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let probe = ReadinessProbe::Tcp { address };
+        wait_until_ready(&runtime_id(), None, &probe, Duration::from_secs(2))
+            .await
+            .unwrap();
+
+        drop(listener);
+        let err = wait_until_ready(&runtime_id(), None, &probe, Duration::from_millis(300))
+            .await
+            .unwrap_err();
+        assert!(err.contains("was not ready after 300 ms"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_http_probe() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/readyz", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+
+        let probe = ReadinessProbe::Http { url };
+        wait_until_ready(&runtime_id(), None, &probe, Duration::from_secs(5))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_log_line_probe() {
+        // Only the output buffer is needed
+        let pid = fake_pid();
+        output::register(pid).unwrap();
+        output::push(pid, OutputStream::Stderr, "starting\n".to_string());
+
+        let probe = ReadinessProbe::LogLine {
+            pattern: r"listening on :\d+".to_string(),
+        };
+        let err = wait_until_ready(&runtime_id(), Some(pid), &probe, Duration::from_millis(200))
+            .await
+            .unwrap_err();
+        assert!(err.contains("not ready"), "{}", err);
+        assert!(err.ends_with("Last stderr output:\nstarting"), "{}", err);
+
+        let waiting = tokio::spawn(async move {
+            wait_until_ready(&runtime_id(), Some(pid), &probe, Duration::from_secs(5)).await
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        output::push(pid, OutputStream::Stdout, "listening on :8080\n".to_string());
+        waiting.await.unwrap().unwrap();
+
+        let invalid = ReadinessProbe::LogLine {
+            pattern: "(".to_string(),
+        };
+        assert!(wait_until_ready(&runtime_id(), Some(pid), &invalid, Duration::from_millis(100))
+            .await
+            .is_err());
+        output::unregister(pid);
+    }
+}
@@ -0,0 +1,445 @@
+// Recording - captures PTY sessions as asciicast v2 transcripts
+// Output, input and resizes are timestamped in memory and saved to the database when the session ends
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::database::{self, RecordingSummary};
+use crate::error::AppError;
+use crate::events;
+use crate::output;
+
+/// Event emitted for every step of a replay
+pub const REPLAY_EVENT: &str = "recording://replay";
+
+/// Recorded bytes after which further events are dropped
+const MAX_RECORDING_BYTES: usize = 64 * 1024 * 1024;
+
+/// Type of an asciicast event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CastEventKind {
+    /// Data written by the process to the terminal
+    #[serde(rename = "o")]
+    Output,
+    /// Data sent to the process
+    #[serde(rename = "i")]
+    Input,
+    /// Terminal resize, encoded as "COLSxROWS"
+    #[serde(rename = "r")]
+    Resize,
+    /// Annotation added by the recorder
+    #[serde(rename = "m")]
+    Marker,
+}
+
+/// One timestamped event, encoded as `[time, kind, data]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEvent {
+    /// Seconds since the start of the recording
+    pub time: f64,
+    pub kind: CastEventKind,
+    pub data: String,
+}
+
+impl Serialize for CastEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.time, self.kind, &self.data).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CastEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (time, kind, data) = <(f64, CastEventKind, String)>::deserialize(deserializer)?;
+        if !time.is_finite() || time < 0.0 {
+            return Err(D::Error::custom(format!("invalid event time {}", time)));
+        }
+        Ok(CastEvent { time, kind, data })
+    }
+}
+
+/// First line of an asciicast v2 file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CastHeader {
+    pub version: u8,
+    pub width: u16,
+    pub height: u16,
+    /// Start of the recording in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+/// A complete terminal transcript
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub header: CastHeader,
+    pub events: Vec<CastEvent>,
+}
+
+impl Recording {
+    /// Encode as an asciicast v2 document: the header line followed by one line per event
+    pub fn to_cast(&self) -> Result<String, String> {
+        let mut cast = serde_json::to_string(&self.header).map_err(|e| e.to_string())?;
+        cast.push('\n');
+        for event in &self.events {
+            cast.push_str(&serde_json::to_string(event).map_err(|e| e.to_string())?);
+            cast.push('\n');
+        }
+        Ok(cast)
+    }
+
+    /// Parse an asciicast v2 document
+    pub fn parse(cast: &str) -> Result<Recording, String> {
+        let mut lines = cast.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+
+        let (_, header_line) = lines
+            .next()
+            .ok_or_else(|| AppError::SerializationError("Recording is empty".to_string()).to_string())?;
+        let header: CastHeader = serde_json::from_str(header_line).map_err(|e| {
+            AppError::SerializationError(format!("Invalid recording header: {}", e)).to_string()
+        })?;
+        if header.version != 2 {
+            return Err(AppError::SerializationError(format!(
+                "Unsupported asciicast version {}",
+                header.version
+            ))
+            .to_string());
+        }
+
+        let events = lines
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    AppError::SerializationError(format!("Invalid event on line {}: {}", index + 1, e)).to_string()
+                })
+            })
+            .collect::<Result<Vec<CastEvent>, String>>()?;
+
+        Ok(Recording { header, events })
+    }
+
+    /// Time of the last event
+    pub fn duration(&self) -> Duration {
+        self.events
+            .last()
+            .map(|e| Duration::from_secs_f64(e.time))
+            .unwrap_or_default()
+    }
+}
+
+/// A recording still being captured
+struct ActiveRecording {
+    session_id: Option<String>,
+    tool_id: String,
+    started: Instant,
+    started_at: u64,
+    recording: Recording,
+    bytes: usize,
+    truncated: bool,
+}
+
+/// Recordings of live PTY sessions, keyed by PID
+fn active_recordings() -> &'static Mutex<HashMap<u32, ActiveRecording>> {
+    static RECORDINGS: OnceLock<Mutex<HashMap<u32, ActiveRecording>>> = OnceLock::new();
+    RECORDINGS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Start recording a PTY session
+pub(crate) fn start(pid: u32, tool_id: &str, command_line: &[String], cols: u16, rows: u16) {
+    let started_at = output::now_millis();
+    let header = CastHeader {
+        version: 2,
+        width: cols,
+        height: rows,
+        timestamp: Some(started_at / 1000),
+        command: Some(command_line.join(" ")),
+        title: Some(tool_id.to_string()),
+        env: HashMap::from([("TERM".to_string(), "xterm-256color".to_string())]),
+    };
+
+    if let Ok(mut recordings) = active_recordings().lock() {
+        recordings.insert(
+            pid,
+            ActiveRecording {
+                session_id: None,
+                tool_id: tool_id.to_string(),
+                started: Instant::now(),
+                started_at,
+                recording: Recording {
+                    header,
+                    events: Vec::new(),
+                },
+                bytes: 0,
+                truncated: false,
+            },
+        );
+    }
+}
+
+/// Link a recording to the chat session it belongs to
+pub(crate) fn set_session(pid: u32, session_id: String) {
+    if let Ok(mut recordings) = active_recordings().lock() {
+        if let Some(active) = recordings.get_mut(&pid) {
+            active.session_id = Some(session_id);
+        }
+    }
+}
+
+/// Append an event to the recording of a process, if it has one
+///
+/// Once a recording reaches `MAX_RECORDING_BYTES` a marker is added and
+/// later events are dropped.
+pub(crate) fn record(pid: u32, kind: CastEventKind, data: String) {
+    let Ok(mut recordings) = active_recordings().lock() else {
+        return;
+    };
+    let Some(active) = recordings.get_mut(&pid) else {
+        return;
+    };
+    if active.truncated {
+        return;
+    }
+
+    let time = active.started.elapsed().as_secs_f64();
+    active.bytes += data.len();
+    if active.bytes > MAX_RECORDING_BYTES {
+        active.truncated = true;
+        active.recording.events.push(CastEvent {
+            time,
+            kind: CastEventKind::Marker,
+            data: "recording truncated".to_string(),
+        });
+        return;
+    }
+    active.recording.events.push(CastEvent { time, kind, data });
+}
+
+/// Record a terminal resize
+pub(crate) fn record_resize(pid: u32, cols: u16, rows: u16) {
+    record(pid, CastEventKind::Resize, format!("{}x{}", cols, rows));
+}
+
+/// Stop recording a process and save the transcript to the database
+///
+/// The recording is dropped with a warning if no database has been set up.
+pub(crate) fn finish(pid: u32) {
+    let Some(active) = active_recordings().lock().ok().and_then(|mut r| r.remove(&pid)) else {
+        return;
+    };
+    let Some(db_path) = database::current_database() else {
+        eprintln!("Warning: no database for the recording of process {}", pid);
+        return;
+    };
+
+    if let Err(e) = save(&db_path, pid, active) {
+        eprintln!("Warning: failed to save recording of process {}: {}", pid, e);
+    }
+}
+
+fn save(db_path: &Path, pid: u32, active: ActiveRecording) -> Result<(), String> {
+    let cast = active.recording.to_cast()?;
+    let summary = RecordingSummary {
+        id: format!("{}-{}", active.started_at, pid),
+        session_id: active.session_id,
+        pid,
+        tool_id: active.tool_id,
+        started_at: active.started_at,
+        duration_ms: active.recording.duration().as_millis() as u64,
+        width: active.recording.header.width,
+        height: active.recording.header.height,
+        event_count: active.recording.events.len() as u64,
+    };
+    database::save_recording(db_path, &summary, &cast)
+}
+
+/// Payload of `REPLAY_EVENT`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayEvent {
+    pub recording_id: String,
+    /// The event to play, or `None` once the replay has finished
+    pub event: Option<CastEvent>,
+}
+
+/// Delay before each event of a replay
+///
+/// Gaps are divided by `speed` and then capped at `max_idle`, so long pauses
+/// don't stall the replay.
+pub fn replay_delays(events: &[CastEvent], speed: f64, max_idle: Option<Duration>) -> Vec<Duration> {
+    let mut previous = 0.0;
+    events
+        .iter()
+        .map(|event| {
+            let gap = Duration::from_secs_f64((event.time - previous).max(0.0) / speed);
+            previous = event.time;
+            max_idle.map_or(gap, |max| gap.min(max))
+        })
+        .collect()
+}
+
+/// Replay a recording by emitting its events with their original timing
+///
+/// Returns the replay's duration in milliseconds; events are emitted in the
+/// background as `REPLAY_EVENT`, followed by one with no event at the end.
+#[tauri::command]
+pub async fn replay_recording(
+    db_path: String,
+    recording_id: String,
+    speed: Option<f64>,
+    max_idle_ms: Option<u64>,
+) -> Result<u64, String> {
+    let speed = speed.unwrap_or(1.0);
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(format!("Invalid replay speed: {}", speed));
+    }
+
+    let cast = database::load_recording_cast(Path::new(&db_path), &recording_id)?;
+    let recording = Recording::parse(&cast)?;
+    let delays = replay_delays(&recording.events, speed, max_idle_ms.map(Duration::from_millis));
+    let total: Duration = delays.iter().sum();
+
+    tauri::async_runtime::spawn(async move {
+        for (event, delay) in recording.events.into_iter().zip(delays) {
+            tokio::time::sleep(delay).await;
+            events::emit(
+                REPLAY_EVENT,
+                ReplayEvent {
+                    recording_id: recording_id.clone(),
+                    event: Some(event),
+                },
+            );
+        }
+        events::emit(
+            REPLAY_EVENT,
+            ReplayEvent {
+                recording_id,
+                event: None,
+            },
+        );
+    });
+
+    Ok(total.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fake_pid;
+    use tempfile::tempdir;
+
+    fn event(time: f64, kind: CastEventKind, data: &str) -> CastEvent {
+        CastEvent {
+            time,
+            kind,
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cast_format() {
+        let recording = Recording {
+            header: CastHeader {
+                version: 2,
+                width: 80,
+                height: 24,
+                timestamp: Some(1_700_000_000),
+                command: None,
+                title: None,
+                env: HashMap::new(),
+            },
+            events: vec![
+                event(0.25, CastEventKind::Output, "hello\r\n"),
+                event(1.5, CastEventKind::Input, "ls\r"),
+                event(2.0, CastEventKind::Resize, "100x30"),
+            ],
+        };
+
+        let cast = recording.to_cast().unwrap();
+        let lines: Vec<&str> = cast.lines().collect();
+        assert_eq!(lines[0], r#"{"version":2,"width":80,"height":24,"timestamp":1700000000}"#);
+        assert_eq!(lines[1], r#"[0.25,"o","hello\r\n"]"#);
+        assert_eq!(lines[2], r#"[1.5,"i","ls\r"]"#);
+        assert_eq!(lines[3], r#"[2.0,"r","100x30"]"#);
+
+        assert_eq!(Recording::parse(&cast).unwrap(), recording);
+        assert_eq!(recording.duration(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_casts() {
+        assert!(Recording::parse("").is_err());
+        assert!(Recording::parse(r#"{"version":1,"width":80,"height":24}"#).is_err());
+
+        let err = Recording::parse("{\"version\":2,\"width\":80,\"height\":24}\n[0.1,\"x\",\"?\"]\n").unwrap_err();
+        assert!(err.contains("line 2"), "{}", err);
+        assert!(Recording::parse("{\"version\":2,\"width\":80,\"height\":24}\n[-1,\"o\",\"\"]\n").is_err());
+    }
+
+    #[test]
+    fn test_replay_delays() {
+        let events = vec![
+            event(1.0, CastEventKind::Output, "a"),
+            event(1.5, CastEventKind::Output, "b"),
+            event(31.5, CastEventKind::Output, "c"),
+        ];
+
+        assert_eq!(
+            replay_delays(&events, 2.0, None),
+            [Duration::from_millis(500), Duration::from_millis(250), Duration::from_secs(15)]
+        );
+        assert_eq!(
+            replay_delays(&events, 1.0, Some(Duration::from_secs(2))),
+            [Duration::from_secs(1), Duration::from_millis(500), Duration::from_secs(2)]
+        );
+    }
+
+    #[test]
+    fn test_recording_is_saved_on_finish() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db_state = database::DatabaseState::new(db_path.clone()).unwrap();
+        db_state.init_schema().unwrap();
+
+        let pid = fake_pid();
+        start(pid, "claude", &["claude".to_string()], 80, 24);
+        set_session(pid, "session-1".to_string());
+        record(pid, CastEventKind::Output, "hello".to_string());
+        record(pid, CastEventKind::Input, "q".to_string());
+        record_resize(pid, 120, 40);
+
+        let active = active_recordings().lock().unwrap().remove(&pid).unwrap();
+        save(&db_path, pid, active).unwrap();
+
+        let summaries = database::query_recordings(&db_path, Some("session-1")).unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].tool_id, "claude");
+        assert_eq!(summaries[0].event_count, 3);
+
+        let cast = database::load_recording_cast(&db_path, &summaries[0].id).unwrap();
+        let recording = Recording::parse(&cast).unwrap();
+        assert_eq!(recording.header.title.as_deref(), Some("claude"));
+        let kinds: Vec<CastEventKind> = recording.events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, [CastEventKind::Output, CastEventKind::Input, CastEventKind::Resize]);
+        assert_eq!(recording.events[2].data, "120x40");
+    }
+
+    #[test]
+    fn test_oversized_recording_is_truncated() {
+        let pid = fake_pid();
+        start(pid, "codex", &[], 80, 24);
+        record(pid, CastEventKind::Output, "x".repeat(MAX_RECORDING_BYTES));
+        record(pid, CastEventKind::Output, "over the limit".to_string());
+        record(pid, CastEventKind::Output, "dropped".to_string());
+
+        let active = active_recordings().lock().unwrap().remove(&pid).unwrap();
+        let kinds: Vec<CastEventKind> = active.recording.events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, [CastEventKind::Output, CastEventKind::Marker]);
+    }
+}
@@ -0,0 +1,211 @@
+// Runtime ID - structured identifier for AI runtimes
+// Encoded as "<kind>_<locator>"; only the first underscore separates the two parts
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Kind of runtime, which decides how it is started, stopped and monitored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuntimeKind {
+    Ollama,
+    LocalAi,
+    Python,
+    Node,
+    Docker,
+    Custom,
+}
+
+impl RuntimeKind {
+    /// All kinds, in the order used by the runtime scanner
+    pub const ALL: [RuntimeKind; 6] = [
+        RuntimeKind::Ollama,
+        RuntimeKind::LocalAi,
+        RuntimeKind::Python,
+        RuntimeKind::Node,
+        RuntimeKind::Docker,
+        RuntimeKind::Custom,
+    ];
+
+    /// Prefix used in the string encoding
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuntimeKind::Ollama => "ollama",
+            RuntimeKind::LocalAi => "localai",
+            RuntimeKind::Python => "python",
+            RuntimeKind::Node => "node",
+            RuntimeKind::Docker => "docker",
+            RuntimeKind::Custom => "custom",
+        }
+    }
+}
+
+impl FromStr for RuntimeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RuntimeKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| format!("Unknown runtime type: {}", s))
+    }
+}
+
+impl fmt::Display for RuntimeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Identifier of a runtime: its kind plus a kind-specific locator
+///
+/// The locator is the executable name for local runtimes, the container ID
+/// for Docker and a user-chosen name for custom runtimes. It may contain
+/// underscores; the encoding splits on the first one only. An empty locator
+/// is encoded as the bare kind, e.g. "ollama".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RuntimeId {
+    pub kind: RuntimeKind,
+    pub locator: String,
+}
+
+impl RuntimeId {
+    pub fn new(kind: RuntimeKind, locator: impl Into<String>) -> Self {
+        Self {
+            kind,
+            locator: locator.into(),
+        }
+    }
+}
+
+impl FromStr for RuntimeId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, locator) = s.split_once('_').unwrap_or((s, ""));
+        let kind: RuntimeKind = kind
+            .parse()
+            .map_err(|e| format!("Invalid runtime ID '{}': {}", s, e))?;
+
+        if kind == RuntimeKind::Docker && locator.is_empty() {
+            return Err(format!("Invalid Docker runtime ID '{}': missing container ID", s));
+        }
+
+        Ok(RuntimeId::new(kind, locator))
+    }
+}
+
+impl fmt::Display for RuntimeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.locator.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "{}_{}", self.kind, self.locator)
+        }
+    }
+}
+
+impl Serialize for RuntimeId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RuntimeId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_known_ids() {
+        let id: RuntimeId = "ollama_ollama".parse().unwrap();
+        assert_eq!(id, RuntimeId::new(RuntimeKind::Ollama, "ollama"));
+
+        let id: RuntimeId = "localai_local-ai".parse().unwrap();
+        assert_eq!(id, RuntimeId::new(RuntimeKind::LocalAi, "local-ai"));
+    }
+
+    #[test]
+    fn test_locator_keeps_underscores() {
+        let id: RuntimeId = "docker_my_ollama_container".parse().unwrap();
+        assert_eq!(id.kind, RuntimeKind::Docker);
+        assert_eq!(id.locator, "my_ollama_container");
+
+        let id: RuntimeId = "custom_llama_server".parse().unwrap();
+        assert_eq!(id.locator, "llama_server");
+    }
+
+    #[test]
+    fn test_empty_locator() {
+        let id: RuntimeId = "ollama".parse().unwrap();
+        assert_eq!(id, RuntimeId::new(RuntimeKind::Ollama, ""));
+        assert_eq!(id.to_string(), "ollama");
+        assert_eq!("ollama_".parse::<RuntimeId>().unwrap(), id);
+    }
+
+    #[test]
+    fn test_invalid_ids() {
+        assert!("".parse::<RuntimeId>().is_err());
+        assert!("unknown_thing".parse::<RuntimeId>().is_err());
+        assert!("docker".parse::<RuntimeId>().is_err());
+        assert!("docker_".parse::<RuntimeId>().is_err());
+    }
+
+    #[test]
+    fn test_serde_as_string() {
+        let id = RuntimeId::new(RuntimeKind::Custom, "my_model");
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, "\"custom_my_model\"");
+        assert_eq!(serde_json::from_str::<RuntimeId>(&json).unwrap(), id);
+        assert!(serde_json::from_str::<RuntimeId>("\"bogus\"").is_err());
+    }
+}
+
+#[cfg(test)]
+mod property_tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn kind_strategy() -> impl Strategy<Value = RuntimeKind> {
+        prop::sample::select(RuntimeKind::ALL.to_vec())
+    }
+
+    fn runtime_id_strategy() -> impl Strategy<Value = RuntimeId> {
+        (kind_strategy(), "[a-zA-Z0-9_.:/-]{0,40}")
+            .prop_filter("Docker IDs need a container ID", |(kind, locator)| {
+                *kind != RuntimeKind::Docker || !locator.is_empty()
+            })
+            .prop_map(|(kind, locator)| RuntimeId::new(kind, locator))
+    }
+
+    proptest! {
+        /// Encoding a runtime ID and parsing it again yields the same ID
+        #[test]
+        fn property_runtime_id_round_trip(id in runtime_id_strategy()) {
+            let encoded = id.to_string();
+            let decoded: RuntimeId = encoded.parse().unwrap();
+            prop_assert_eq!(&decoded, &id);
+            prop_assert_eq!(decoded.to_string(), encoded);
+        }
+
+        /// The JSON encoding round-trips as well
+        #[test]
+        fn property_runtime_id_serde_round_trip(id in runtime_id_strategy()) {
+            let json = serde_json::to_string(&id).unwrap();
+            let decoded: RuntimeId = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(decoded, id);
+        }
+
+        /// Parsing never panics on arbitrary input
+        #[test]
+        fn property_runtime_id_parse_total(s in ".{0,60}") {
+            let _ = s.parse::<RuntimeId>();
+        }
+    }
+}
@@ -0,0 +1,353 @@
+// Runtime Instance - where each runtime instance listens and which models it serves
+// Lets several Ollama or LocalAI servers run side by side, each on its own address
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+use crate::runtime_id::{RuntimeId, RuntimeKind};
+
+/// Address a runtime binds to unless its instance says otherwise
+pub const DEFAULT_HOST: &str = "127.0.0.1";
+
+/// Time allowed to find out whether something already listens on an endpoint
+const PORT_CHECK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Per-instance settings for a runtime, stored in runtimes.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RuntimeInstance {
+    /// Address to bind; `None` uses `DEFAULT_HOST`
+    pub host: Option<String>,
+    /// Port to listen on; `None` uses the runtime type's default port
+    pub port: Option<u16>,
+    /// Directory the instance loads models from
+    pub models_dir: Option<String>,
+}
+
+/// Host and port a runtime instance listens on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Endpoint {
+    /// "host:port" for the runtime to bind, with IPv6 hosts in brackets
+    pub fn bind_address(&self) -> String {
+        join_host_port(&self.host, self.port)
+    }
+
+    /// "host:port" to connect to; wildcard binds are reached over loopback
+    pub fn connect_address(&self) -> String {
+        let host = match self.host.as_str() {
+            "0.0.0.0" | "" => "127.0.0.1",
+            "::" | "[::]" => "::1",
+            host => host,
+        };
+        join_host_port(host, self.port)
+    }
+
+    /// URL of `path` on the instance's HTTP API
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.connect_address(), path)
+    }
+}
+
+fn join_host_port(host: &str, port: u16) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// Port a runtime type listens on out of the box
+pub fn default_port(kind: RuntimeKind) -> Option<u16> {
+    match kind {
+        RuntimeKind::Ollama => Some(11434),
+        RuntimeKind::LocalAi => Some(8080),
+        RuntimeKind::Python | RuntimeKind::Node | RuntimeKind::Docker | RuntimeKind::Custom => None,
+    }
+}
+
+/// Instance settings by runtime
+fn instances() -> &'static Mutex<HashMap<RuntimeId, RuntimeInstance>> {
+    static INSTANCES: OnceLock<Mutex<HashMap<RuntimeId, RuntimeInstance>>> = OnceLock::new();
+    INSTANCES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Replace all instance settings, keyed by runtime ID
+pub fn set_instances(new_instances: HashMap<String, RuntimeInstance>) {
+    let new_instances = parse_instances(new_instances);
+    if let Ok(mut current) = instances().lock() {
+        *current = new_instances;
+    }
+}
+
+/// Key instance settings by runtime ID, skipping entries with an invalid ID
+fn parse_instances(instances: HashMap<String, RuntimeInstance>) -> HashMap<RuntimeId, RuntimeInstance> {
+    instances
+        .into_iter()
+        .filter_map(|(id, instance)| match id.parse() {
+            Ok(id) => Some((id, instance)),
+            Err(e) => {
+                eprintln!("Warning: ignoring runtime instance: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Set or clear the instance settings of one runtime, leaving the others alone
+#[cfg(test)]
+pub(crate) fn set_instance(runtime_id: &RuntimeId, instance: Option<RuntimeInstance>) {
+    if let Ok(mut current) = instances().lock() {
+        match instance {
+            Some(instance) => current.insert(runtime_id.clone(), instance),
+            None => current.remove(runtime_id),
+        };
+    }
+}
+
+/// Endpoints runtimes were last started on, as their launch environment set them
+fn launched_endpoints() -> &'static Mutex<HashMap<RuntimeId, Endpoint>> {
+    static LAUNCHED: OnceLock<Mutex<HashMap<RuntimeId, Endpoint>>> = OnceLock::new();
+    LAUNCHED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Settings stored for a runtime, if any
+fn stored_instance(runtime_id: &RuntimeId) -> Option<RuntimeInstance> {
+    instances().lock().ok().and_then(|i| i.get(runtime_id).cloned())
+}
+
+/// Settings for a runtime, falling back to the defaults
+pub fn instance_for(runtime_id: &RuntimeId) -> RuntimeInstance {
+    stored_instance(runtime_id).unwrap_or_default()
+}
+
+/// Where a runtime listens, if it is a server we know how to reach
+///
+/// A runtime we started is reached where it was told to bind. Otherwise
+/// Ollama and LocalAI have an endpoint from their instance or default port;
+/// other runtimes only when a port is configured for them.
+pub fn endpoint_for(runtime_id: &RuntimeId) -> Option<Endpoint> {
+    if let Some(endpoint) = launched_endpoints().lock().ok().and_then(|l| l.get(runtime_id).cloned()) {
+        return Some(endpoint);
+    }
+    configured_endpoint(runtime_id)
+}
+
+fn configured_endpoint(runtime_id: &RuntimeId) -> Option<Endpoint> {
+    let instance = instance_for(runtime_id);
+    let port = instance.port.or_else(|| default_port(runtime_id.kind))?;
+    Some(Endpoint {
+        host: instance.host.unwrap_or_else(|| DEFAULT_HOST.to_string()),
+        port,
+    })
+}
+
+/// Variables a runtime type reads its bind address and models directory from
+fn instance_vars(kind: RuntimeKind) -> Option<(&'static str, &'static str)> {
+    match kind {
+        RuntimeKind::Ollama => Some(("OLLAMA_HOST", "OLLAMA_MODELS")),
+        RuntimeKind::LocalAi => Some(("LOCALAI_ADDRESS", "LOCALAI_MODELS_PATH")),
+        RuntimeKind::Python | RuntimeKind::Node | RuntimeKind::Docker | RuntimeKind::Custom => None,
+    }
+}
+
+/// Environment variables that point a runtime at its stored instance's address and models
+///
+/// Nothing is set for a runtime without stored settings, so its own env
+/// mappings, or the runtime's defaults, decide where it binds.
+pub fn instance_env(runtime_id: &RuntimeId) -> Vec<(String, String)> {
+    let (Some(instance), Some((address_var, models_var))) = (stored_instance(runtime_id), instance_vars(runtime_id.kind))
+    else {
+        return Vec::new();
+    };
+
+    let mut vars = Vec::new();
+    if instance.host.is_some() || instance.port.is_some() {
+        if let Some(endpoint) = configured_endpoint(runtime_id) {
+            vars.push((address_var.to_string(), endpoint.bind_address()));
+        }
+    }
+    if let Some(models_dir) = instance.models_dir {
+        vars.push((models_var.to_string(), models_dir));
+    }
+    vars
+}
+
+/// Where a runtime started with the environment `lookup` reads will bind
+///
+/// The address variable, such as `OLLAMA_HOST`, wins when it is set; it can
+/// be "host:port", "host", ":port" or a URL. Otherwise the configured
+/// endpoint applies.
+pub fn launch_endpoint(runtime_id: &RuntimeId, lookup: &dyn Fn(&str) -> Option<String>) -> Option<Endpoint> {
+    let configured = configured_endpoint(runtime_id);
+    let Some((address_var, _)) = instance_vars(runtime_id.kind) else {
+        return configured;
+    };
+    let Some(address) = lookup(address_var).filter(|a| !a.trim().is_empty()) else {
+        return configured;
+    };
+    let address = address.trim();
+    let address = address.split_once("://").map_or(address, |(_, rest)| rest).trim_end_matches('/');
+    let default_port = default_port(runtime_id.kind);
+    let (host, port) = match address.rsplit_once(':') {
+        // A bare IPv6 address has colons but no port
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => (host, port.parse().ok()),
+        _ => (address, default_port),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Some(Endpoint {
+        host: if host.is_empty() { DEFAULT_HOST.to_string() } else { host.to_string() },
+        port: port.or(default_port)?,
+    })
+}
+
+/// Remember where a runtime was started, so status checks reach it there
+pub fn record_launch(runtime_id: &RuntimeId, endpoint: Option<Endpoint>) {
+    if let Ok(mut launched) = launched_endpoints().lock() {
+        match endpoint {
+            Some(endpoint) => launched.insert(runtime_id.clone(), endpoint),
+            None => launched.remove(runtime_id),
+        };
+    }
+}
+
+/// Fail if something already accepts connections on `endpoint`
+///
+/// Otherwise a second instance on a taken port would look ready because the
+/// first one answers its readiness probe.
+pub async fn ensure_port_free(runtime_id: &RuntimeId, endpoint: Option<&Endpoint>) -> Result<(), String> {
+    let Some(endpoint) = endpoint else {
+        return Ok(());
+    };
+    let address = endpoint.connect_address();
+    let connected = tokio::time::timeout(PORT_CHECK_TIMEOUT, TcpStream::connect(address.as_str()))
+        .await
+        .is_ok_and(|connected| connected.is_ok());
+    if connected {
+        return Err(format!(
+            "Cannot start runtime {}: {} is already in use",
+            runtime_id, address
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_addresses() {
+        let endpoint = Endpoint {
+            host: "0.0.0.0".to_string(),
+            port: 11500,
+        };
+        assert_eq!(endpoint.bind_address(), "0.0.0.0:11500");
+        assert_eq!(endpoint.url("/api/version"), "http://127.0.0.1:11500/api/version");
+
+        let endpoint = Endpoint {
+            host: "::".to_string(),
+            port: 8081,
+        };
+        assert_eq!(endpoint.bind_address(), "[::]:8081");
+        assert_eq!(endpoint.connect_address(), "[::1]:8081");
+    }
+
+    #[test]
+    fn test_instance_deserialization() {
+        let instance: RuntimeInstance =
+            serde_json::from_str(r#"{"port":11500,"modelsDir":"/models/gpu1"}"#).unwrap();
+        assert_eq!(instance.host, None);
+        assert_eq!(instance.port, Some(11500));
+        assert_eq!(instance.models_dir.as_deref(), Some("/models/gpu1"));
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_and_env() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let taken_port = listener.local_addr().unwrap().port();
+        let configured: RuntimeId = "ollama_instance_test".parse().unwrap();
+        let taken: RuntimeId = "custom_taken_port_test".parse().unwrap();
+        set_instance(
+            &configured,
+            Some(RuntimeInstance {
+                host: Some("0.0.0.0".to_string()),
+                port: Some(11500),
+                models_dir: Some("/models/gpu1".to_string()),
+            }),
+        );
+        set_instance(
+            &taken,
+            Some(RuntimeInstance {
+                port: Some(taken_port),
+                ..Default::default()
+            }),
+        );
+
+        assert_eq!(
+            instance_env(&configured),
+            vec![
+                ("OLLAMA_HOST".to_string(), "0.0.0.0:11500".to_string()),
+                ("OLLAMA_MODELS".to_string(), "/models/gpu1".to_string()),
+            ]
+        );
+
+        // Without stored settings nothing is injected, so an explicit env mapping stands
+        let unconfigured: RuntimeId = "localai_default_instance_test".parse().unwrap();
+        assert_eq!(endpoint_for(&unconfigured).unwrap().connect_address(), "127.0.0.1:8080");
+        assert!(instance_env(&unconfigured).is_empty());
+
+        let custom: RuntimeId = "custom_instance_test".parse().unwrap();
+        assert_eq!(endpoint_for(&custom), None);
+        assert!(instance_env(&custom).is_empty());
+        ensure_port_free(&custom, None).await.unwrap();
+
+        let endpoint = launch_endpoint(&taken, &|_| None);
+        let err = ensure_port_free(&taken, endpoint.as_ref()).await.unwrap_err();
+        assert!(err.contains("already in use"), "{}", err);
+        drop(listener);
+        ensure_port_free(&taken, endpoint.as_ref()).await.unwrap();
+
+        set_instance(&configured, None);
+        set_instance(&taken, None);
+        assert!(instance_env(&configured).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_instance_ids_are_skipped() {
+        let parsed = parse_instances(HashMap::from([
+            ("ollama_gpu".to_string(), RuntimeInstance::default()),
+            ("not a runtime".to_string(), RuntimeInstance::default()),
+        ]));
+        assert_eq!(parsed.keys().map(|id| id.to_string()).collect::<Vec<_>>(), ["ollama_gpu"]);
+    }
+
+    #[test]
+    fn test_launch_endpoint_follows_env() {
+        let ollama: RuntimeId = "ollama_launch_endpoint_test".parse().unwrap();
+        let endpoint = |address: &str| {
+            let address = address.to_string();
+            launch_endpoint(&ollama, &move |name| (name == "OLLAMA_HOST").then(|| address.clone()))
+                .map(|e| e.bind_address())
+        };
+        assert_eq!(endpoint(""), Some("127.0.0.1:11434".to_string()));
+        assert_eq!(endpoint("0.0.0.0:11500"), Some("0.0.0.0:11500".to_string()));
+        assert_eq!(endpoint("http://10.0.0.5:9000/"), Some("10.0.0.5:9000".to_string()));
+        assert_eq!(endpoint("0.0.0.0"), Some("0.0.0.0:11434".to_string()));
+        assert_eq!(endpoint(":11600"), Some("127.0.0.1:11600".to_string()));
+        assert_eq!(endpoint("[::1]:11500"), Some("[::1]:11500".to_string()));
+        assert_eq!(endpoint("::1"), Some("[::1]:11434".to_string()));
+
+        record_launch(&ollama, launch_endpoint(&ollama, &|_| Some("127.0.0.1:11999".to_string())));
+        assert_eq!(endpoint_for(&ollama).unwrap().port, 11999);
+        record_launch(&ollama, None);
+        assert_eq!(endpoint_for(&ollama).unwrap().port, 11434);
+    }
+}
@@ -0,0 +1,615 @@
+// Runtime Logs - persists the output of managed runtimes to rotating log files
+// Each runtime gets a directory under the app data dir; files rotate by size and age and old ones are pruned
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::error::AppError;
+use crate::output::{self, OutputStream};
+use crate::runtime_id::RuntimeId;
+
+/// Directory under the app data dir holding all runtime logs
+pub const LOG_DIR: &str = "logs";
+
+/// File the live output of a runtime is appended to
+const CURRENT_LOG: &str = "current.log";
+
+/// Lines returned by a tail when no count is given
+const DEFAULT_TAIL_LINES: usize = 200;
+
+/// Matches returned by a search when no limit is given
+const DEFAULT_SEARCH_LIMIT: usize = 500;
+
+/// When log files are rotated and how long rotated files are kept
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LogPolicy {
+    /// Rotate once the current file would grow past this size
+    pub max_file_bytes: u64,
+    /// Rotate once the current file is older than this
+    pub max_file_age_ms: u64,
+    /// Rotated files kept per runtime; older ones are deleted
+    pub max_files: usize,
+    /// Rotated files older than this are deleted
+    pub retention_ms: u64,
+}
+
+impl Default for LogPolicy {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 10 * 1024 * 1024,
+            max_file_age_ms: 24 * 60 * 60 * 1000,
+            max_files: 10,
+            retention_ms: 7 * 24 * 60 * 60 * 1000,
+        }
+    }
+}
+
+/// A log file of a runtime
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogFileInfo {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    /// Last modification in milliseconds since the Unix epoch
+    pub modified_at: u64,
+    /// Whether this is the file currently being written
+    pub current: bool,
+}
+
+/// A log line matching a search
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogMatch {
+    pub file: String,
+    /// 1-based line number within the file
+    pub line_number: usize,
+    pub line: String,
+}
+
+/// Open log file of one runtime
+struct LogWriter {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    /// Time the current file was started, in milliseconds since the Unix epoch
+    opened_at: u64,
+}
+
+impl LogWriter {
+    /// Open the current log file in `dir`, creating it if needed
+    fn open(dir: PathBuf) -> std::io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(CURRENT_LOG);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        // A file left by an earlier run keeps aging from when it was started
+        let opened_at = metadata
+            .created()
+            .or_else(|_| metadata.modified())
+            .ok()
+            .map(system_time_millis)
+            .unwrap_or_else(output::now_millis);
+
+        Ok(Self {
+            dir,
+            file,
+            size: metadata.len(),
+            opened_at,
+        })
+    }
+
+    /// Append a line, rotating first if the policy says so
+    fn append(&mut self, line: &str, now: u64, policy: &LogPolicy) -> std::io::Result<()> {
+        let too_big = self.size > 0 && self.size + line.len() as u64 > policy.max_file_bytes;
+        let too_old = now.saturating_sub(self.opened_at) >= policy.max_file_age_ms;
+        if too_big || (too_old && self.size > 0) {
+            self.rotate(now, policy)?;
+        }
+
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Move the current file aside, start a new one and prune old files
+    fn rotate(&mut self, now: u64, policy: &LogPolicy) -> std::io::Result<()> {
+        fs::rename(self.dir.join(CURRENT_LOG), self.dir.join(rotated_name(self.opened_at)))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(CURRENT_LOG))?;
+        self.size = 0;
+        self.opened_at = now;
+        prune(&self.dir, now, policy)
+    }
+}
+
+/// Name of a rotated file; names sort in the order the files were started
+fn rotated_name(opened_at: u64) -> String {
+    let started = DateTime::<Utc>::from_timestamp_millis(opened_at as i64).unwrap_or_default();
+    format!("{}.log", started.format("%Y%m%d-%H%M%S%.3f"))
+}
+
+/// Delete rotated files beyond the retention count or age
+fn prune(dir: &Path, now: u64, policy: &LogPolicy) -> std::io::Result<()> {
+    let rotated: Vec<LogFileInfo> = list_in(dir)?.into_iter().filter(|f| !f.current).collect();
+    for (index, file) in rotated.iter().enumerate() {
+        let expired = now.saturating_sub(file.modified_at) > policy.retention_ms;
+        if index >= policy.max_files || expired {
+            fs::remove_file(&file.path)?;
+        }
+    }
+    Ok(())
+}
+
+fn system_time_millis(time: std::time::SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Format one chunk of output as a log line
+fn format_line(timestamp: u64, stream: OutputStream, data: &str) -> String {
+    let time = DateTime::<Utc>::from_timestamp_millis(timestamp as i64)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Millis, true);
+    let stream = match stream {
+        OutputStream::Stdout => "stdout",
+        OutputStream::Stderr => "stderr",
+    };
+    format!("{} {} {}\n", time, stream, data.trim_end_matches(['\r', '\n']))
+}
+
+/// Root of the log directories, set once during app setup
+static LOG_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Open writers, keyed by runtime
+fn writers() -> &'static Mutex<HashMap<RuntimeId, LogWriter>> {
+    static WRITERS: OnceLock<Mutex<HashMap<RuntimeId, LogWriter>>> = OnceLock::new();
+    WRITERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Runtime each logged process belongs to, keyed by PID
+fn log_targets() -> &'static Mutex<HashMap<u32, RuntimeId>> {
+    static TARGETS: OnceLock<Mutex<HashMap<u32, RuntimeId>>> = OnceLock::new();
+    TARGETS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn log_policy() -> &'static Mutex<LogPolicy> {
+    static POLICY: OnceLock<Mutex<LogPolicy>> = OnceLock::new();
+    POLICY.get_or_init(|| Mutex::new(LogPolicy::default()))
+}
+
+/// Set the directory runtime logs are written to
+///
+/// Called once during app setup; until then no logs are written.
+pub fn init(root: PathBuf) {
+    let _ = LOG_ROOT.set(root);
+}
+
+/// Directory holding the logs of one runtime
+fn runtime_dir(root: &Path, runtime_id: &RuntimeId) -> PathBuf {
+    let name: String = runtime_id
+        .to_string()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    root.join(name)
+}
+
+fn log_root() -> Result<&'static PathBuf, String> {
+    LOG_ROOT
+        .get()
+        .ok_or_else(|| AppError::IoError("Runtime logs are not set up".to_string()).to_string())
+}
+
+/// Start logging the output of a runtime's process
+///
+/// Restarts of the same runtime keep appending to the same files.
+pub(crate) fn attach(pid: u32, runtime_id: &RuntimeId) {
+    if let Some(root) = LOG_ROOT.get() {
+        attach_in(root, pid, runtime_id);
+    }
+}
+
+fn attach_in(root: &Path, pid: u32, runtime_id: &RuntimeId) {
+    let Ok(mut writers) = writers().lock() else {
+        return;
+    };
+    if !writers.contains_key(runtime_id) {
+        match LogWriter::open(runtime_dir(root, runtime_id)) {
+            Ok(writer) => {
+                writers.insert(runtime_id.clone(), writer);
+            }
+            Err(e) => {
+                eprintln!("Warning: failed to open log for runtime {}: {}", runtime_id, e);
+                return;
+            }
+        }
+    }
+    if let Ok(mut targets) = log_targets().lock() {
+        targets.insert(pid, runtime_id.clone());
+    }
+}
+
+/// Stop logging the output of a process once all of it has been read
+///
+/// Its PID may be reused by an unrelated process, whose output must not end
+/// up in the runtime's log. The log file is closed once no process of the
+/// runtime is logged any more.
+pub(crate) fn detach(pid: u32) {
+    let Ok(mut targets) = log_targets().lock() else {
+        return;
+    };
+    let Some(runtime_id) = targets.remove(&pid) else {
+        return;
+    };
+    if !targets.values().any(|id| *id == runtime_id) {
+        if let Ok(mut writers) = writers().lock() {
+            writers.remove(&runtime_id);
+        }
+    }
+}
+
+/// Append a chunk of output to the log of the runtime it came from, if any
+pub(crate) fn append(pid: u32, stream: OutputStream, data: &str, timestamp: u64) {
+    let Some(runtime_id) = log_targets().lock().ok().and_then(|t| t.get(&pid).cloned()) else {
+        return;
+    };
+    let policy = log_policy().lock().map(|p| *p).unwrap_or_default();
+    let Ok(mut writers) = writers().lock() else {
+        return;
+    };
+    if let Some(writer) = writers.get_mut(&runtime_id) {
+        if let Err(e) = writer.append(&format_line(timestamp, stream, data), timestamp, &policy) {
+            eprintln!("Warning: failed to write log for runtime {}: {}", runtime_id, e);
+        }
+    }
+}
+
+/// Log files in a runtime's directory, current file first, then newest to oldest
+fn list_in(dir: &Path) -> std::io::Result<Vec<LogFileInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.ends_with(".log") {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        files.push(LogFileInfo {
+            current: name == CURRENT_LOG,
+            path: entry.path().to_string_lossy().into_owned(),
+            size_bytes: metadata.len(),
+            modified_at: metadata.modified().map(system_time_millis).unwrap_or_default(),
+            name,
+        });
+    }
+    files.sort_by(|a, b| b.current.cmp(&a.current).then_with(|| b.name.cmp(&a.name)));
+    Ok(files)
+}
+
+/// Resolve a file name from `list_in`, rejecting anything else
+fn log_file(dir: &Path, name: Option<&str>) -> Result<PathBuf, String> {
+    let name = name.unwrap_or(CURRENT_LOG);
+    let known = list_in(dir)
+        .map_err(|e| AppError::IoError(e.to_string()).to_string())?
+        .into_iter()
+        .any(|f| f.name == name);
+    if !known {
+        return Err(AppError::FileNotFound(format!("No log file named {}", name)).to_string());
+    }
+    Ok(dir.join(name))
+}
+
+/// The last `count` lines of a log file
+fn tail_in(dir: &Path, name: Option<&str>, count: usize) -> Result<Vec<String>, String> {
+    let path = log_file(dir, name)?;
+    let file = File::open(&path).map_err(|e| AppError::IoError(e.to_string()).to_string())?;
+
+    let mut lines = std::collections::VecDeque::with_capacity(count.min(DEFAULT_TAIL_LINES));
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| AppError::IoError(e.to_string()).to_string())?;
+        if lines.len() == count {
+            lines.pop_front();
+        }
+        if count > 0 {
+            lines.push_back(line);
+        }
+    }
+    Ok(lines.into())
+}
+
+/// Lines matching `query` across all log files, newest files first
+fn search_in(dir: &Path, query: &str, regex: bool, limit: usize) -> Result<Vec<LogMatch>, String> {
+    let pattern = if regex { query.to_string() } else { regex::escape(query) };
+    let pattern = RegexBuilder::new(&pattern)
+        .case_insensitive(!regex)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))?;
+
+    let mut matches = Vec::new();
+    for info in list_in(dir).map_err(|e| AppError::IoError(e.to_string()).to_string())? {
+        let file = File::open(&info.path).map_err(|e| AppError::IoError(e.to_string()).to_string())?;
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| AppError::IoError(e.to_string()).to_string())?;
+            if pattern.is_match(&line) {
+                matches.push(LogMatch {
+                    file: info.name.clone(),
+                    line_number: index + 1,
+                    line,
+                });
+                if matches.len() == limit {
+                    return Ok(matches);
+                }
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// List the log files of a runtime, current file first
+#[tauri::command]
+pub fn list_runtime_logs(runtime_id: RuntimeId) -> Result<Vec<LogFileInfo>, String> {
+    list_in(&runtime_dir(log_root()?, &runtime_id)).map_err(|e| AppError::IoError(e.to_string()).to_string())
+}
+
+/// Get the last lines of a runtime's log, from the current file unless `file` names another
+#[tauri::command]
+pub fn tail_runtime_log(
+    runtime_id: RuntimeId,
+    file: Option<String>,
+    lines: Option<usize>,
+) -> Result<Vec<String>, String> {
+    tail_in(
+        &runtime_dir(log_root()?, &runtime_id),
+        file.as_deref(),
+        lines.unwrap_or(DEFAULT_TAIL_LINES),
+    )
+}
+
+/// Search all log files of a runtime
+///
+/// `query` is matched case-insensitively as plain text, or as a regex if
+/// `regex` is set.
+#[tauri::command]
+pub fn search_runtime_logs(
+    runtime_id: RuntimeId,
+    query: String,
+    regex: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<LogMatch>, String> {
+    search_in(
+        &runtime_dir(log_root()?, &runtime_id),
+        &query,
+        regex.unwrap_or(false),
+        limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+    )
+}
+
+/// Set the rotation and retention policy for runtime logs
+#[tauri::command]
+pub fn set_log_policy(policy: LogPolicy) -> Result<(), String> {
+    if policy.max_file_bytes == 0 || policy.max_file_age_ms == 0 {
+        return Err("Log rotation limits must be greater than zero".to_string());
+    }
+    *log_policy().lock().map_err(|e| e.to_string())? = policy;
+    Ok(())
+}
+
+/// Get the rotation and retention policy for runtime logs
+#[tauri::command]
+pub fn get_log_policy() -> Result<LogPolicy, String> {
+    log_policy().lock().map(|p| *p).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fake_pid;
+    use tempfile::TempDir;
+
+    const T0: u64 = 1_700_000_000_000;
+
+    fn write_lines(writer: &mut LogWriter, policy: &LogPolicy, lines: &[(u64, &str)]) {
+        for (time, text) in lines {
+            writer
+                .append(&format_line(*time, OutputStream::Stdout, text), *time, policy)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_format_line() {
+        assert_eq!(
+            format_line(T0, OutputStream::Stderr, "listening on :11434\n"),
+            "2023-11-14T22:13:20.000Z stderr listening on :11434\n"
+        );
+    }
+
+    #[test]
+    fn test_rotates_by_size_and_prunes_by_count() {
+        let dir = TempDir::new().unwrap();
+        let policy = LogPolicy {
+            max_file_bytes: 100,
+            max_files: 2,
+            ..LogPolicy::default()
+        };
+        let mut writer = LogWriter::open(dir.path().to_path_buf()).unwrap();
+        writer.opened_at = T0;
+
+        // Each line is 39 bytes, so a file holds two
+        let lines: Vec<(u64, String)> = (0..9).map(|i| (T0 + i * 1_000, format!("line {}", i))).collect();
+        let lines: Vec<(u64, &str)> = lines.iter().map(|(t, s)| (*t, s.as_str())).collect();
+        write_lines(&mut writer, &policy, &lines);
+
+        let files = list_in(dir.path()).unwrap();
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names.len(), 3, "{:?}", names);
+        assert_eq!(names[0], CURRENT_LOG);
+        assert!(files.iter().all(|f| f.size_bytes <= 100));
+
+        let tail = tail_in(dir.path(), None, 10).unwrap();
+        assert!(tail.last().unwrap().ends_with("line 8"));
+        // The oldest rotated files have been pruned
+        assert!(search_in(dir.path(), "line 0", false, 10).unwrap().is_empty());
+        assert_eq!(search_in(dir.path(), "line 4", false, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rotates_by_age_and_prunes_expired_files() {
+        let dir = TempDir::new().unwrap();
+        let policy = LogPolicy {
+            max_file_age_ms: 60_000,
+            retention_ms: 24 * 60 * 60 * 1000,
+            ..LogPolicy::default()
+        };
+
+        // A rotated file from a month ago is past retention
+        let expired = File::create(dir.path().join("20200101-000000.000.log")).unwrap();
+        let month = std::time::Duration::from_secs(30 * 24 * 60 * 60);
+        expired.set_modified(std::time::SystemTime::now() - month).unwrap();
+
+        let now = output::now_millis();
+        let mut writer = LogWriter::open(dir.path().to_path_buf()).unwrap();
+        writer.opened_at = now - 120_000;
+        write_lines(&mut writer, &policy, &[(now - 120_000, "old"), (now, "new")]);
+
+        let files = list_in(dir.path()).unwrap();
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, [CURRENT_LOG, rotated_name(now - 120_000).as_str()]);
+        assert_eq!(tail_in(dir.path(), None, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_tail_and_search() {
+        let dir = TempDir::new().unwrap();
+        let policy = LogPolicy::default();
+        let mut writer = LogWriter::open(dir.path().to_path_buf()).unwrap();
+        write_lines(
+            &mut writer,
+            &policy,
+            &[(T0, "Loading model"), (T0 + 1, "ERROR: out of memory"), (T0 + 2, "retrying")],
+        );
+
+        assert_eq!(tail_in(dir.path(), None, 2).unwrap().len(), 2);
+        assert!(tail_in(dir.path(), None, 0).unwrap().is_empty());
+
+        let matches = search_in(dir.path(), "error", false, 10).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line_number, 2);
+        assert_eq!(matches[0].file, CURRENT_LOG);
+
+        assert_eq!(search_in(dir.path(), r"out of \w+", true, 10).unwrap().len(), 1);
+        assert_eq!(search_in(dir.path(), "o", false, 2).unwrap().len(), 2);
+        assert!(search_in(dir.path(), "(", true, 10).is_err());
+    }
+
+    #[test]
+    fn test_only_listed_files_can_be_read() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(CURRENT_LOG), "x\n").unwrap();
+
+        assert!(tail_in(dir.path(), Some("../secrets.log"), 10).is_err());
+        assert!(tail_in(dir.path(), Some("missing.log"), 10).is_err());
+        assert_eq!(tail_in(dir.path(), Some(CURRENT_LOG), 10).unwrap(), ["x"]);
+    }
+
+    #[test]
+    fn test_detached_pid_is_not_logged() {
+        let root = TempDir::new().unwrap();
+        let id: RuntimeId = "custom_detach_test".parse().unwrap();
+        let pid = fake_pid();
+
+        attach_in(root.path(), pid, &id);
+        append(pid, OutputStream::Stdout, "from the runtime\n", T0);
+        detach(pid);
+        // The PID now belongs to some other process
+        append(pid, OutputStream::Stdout, "from a stranger\n", T0 + 1);
+
+        let dir = runtime_dir(root.path(), &id);
+        assert_eq!(tail_in(&dir, None, 10).unwrap().len(), 1);
+        assert!(!writers().lock().unwrap().contains_key(&id));
+        assert!(!log_targets().lock().unwrap().contains_key(&pid));
+    }
+
+    #[test]
+    fn test_output_is_logged_until_readers_finish() {
+        let root = TempDir::new().unwrap();
+        let id: RuntimeId = "custom_drain_test".parse().unwrap();
+        let pid = fake_pid();
+        output::register(pid).unwrap();
+        attach_in(root.path(), pid, &id);
+
+        output::spawn_line_readers(
+            pid,
+            vec![
+                (OutputStream::Stdout, Box::new("ready\n".as_bytes())),
+                (OutputStream::Stderr, Box::new("panic: out of memory\n".as_bytes())),
+            ],
+        );
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while log_targets().lock().unwrap().contains_key(&pid) && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let lines = tail_in(&runtime_dir(root.path(), &id), None, 10).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().any(|line| line.ends_with("stderr panic: out of memory")), "{:?}", lines);
+        assert!(!log_targets().lock().unwrap().contains_key(&pid));
+        output::unregister(pid);
+    }
+
+    #[test]
+    fn test_log_follows_output_order() {
+        let root = TempDir::new().unwrap();
+        let id: RuntimeId = "custom_order_test".parse().unwrap();
+        let pid = fake_pid();
+        output::register(pid).unwrap();
+        attach_in(root.path(), pid, &id);
+
+        let lines = |name: &str| (0..200).map(|i| format!("{} {}\n", name, i)).collect::<String>();
+        output::spawn_line_readers(
+            pid,
+            vec![
+                (OutputStream::Stdout, Box::new(std::io::Cursor::new(lines("out")))),
+                (OutputStream::Stderr, Box::new(std::io::Cursor::new(lines("err")))),
+            ],
+        );
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while log_targets().lock().unwrap().contains_key(&pid) && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let logged: Vec<String> = tail_in(&runtime_dir(root.path(), &id), None, 1000)
+            .unwrap()
+            .iter()
+            .map(|line| line.splitn(3, ' ').nth(2).unwrap().to_string())
+            .collect();
+        let buffered: Vec<String> = output::lines_from(pid, 0)
+            .unwrap()
+            .iter()
+            .map(|line| line.data.trim_end().to_string())
+            .collect();
+        assert_eq!(logged.len(), 400);
+        assert_eq!(logged, buffered);
+        output::unregister(pid);
+    }
+
+    #[test]
+    fn test_runtime_dir_is_sanitized() {
+        let root = Path::new("/logs");
+        let id: RuntimeId = "custom_../../etc".parse().unwrap();
+        assert_eq!(runtime_dir(root, &id), Path::new("/logs/custom_.._.._etc"));
+    }
+}
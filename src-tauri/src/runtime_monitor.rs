@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::process::Command;
-use sysinfo::System;
+use sysinfo::{Pid, System};
+
+use crate::process;
+use crate::runtime_id::{RuntimeId, RuntimeKind};
+use crate::runtime_instance::{self, Endpoint, RuntimeInstance};
 
 /// Detected AI runtime information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,7 +29,7 @@ pub struct RuntimeStatus {
 }
 
 /// Resource usage information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResourceUsage {
     pub memory_mb: f64,
     pub vram_mb: Option<f64>,
@@ -43,7 +47,7 @@ pub struct RuntimeInfo {
 /// Runtime detector configuration
 struct RuntimeDetector {
     name: &'static str,
-    runtime_type: &'static str,
+    kind: RuntimeKind,
     executables: &'static [&'static str],
     version_command: &'static [&'static str],
     default_port: Option<u16>,
@@ -52,35 +56,35 @@ struct RuntimeDetector {
 const RUNTIME_DETECTORS: &[RuntimeDetector] = &[
     RuntimeDetector {
         name: "Ollama",
-        runtime_type: "ollama",
+        kind: RuntimeKind::Ollama,
         executables: &["ollama"],
         version_command: &["ollama", "--version"],
         default_port: Some(11434),
     },
     RuntimeDetector {
         name: "LocalAI",
-        runtime_type: "localai",
+        kind: RuntimeKind::LocalAi,
         executables: &["local-ai", "localai"],
         version_command: &["local-ai", "--version"],
         default_port: Some(8080),
     },
     RuntimeDetector {
         name: "Python",
-        runtime_type: "python",
+        kind: RuntimeKind::Python,
         executables: &["python3", "python"],
         version_command: &["python3", "--version"],
         default_port: None,
     },
     RuntimeDetector {
         name: "Node.js",
-        runtime_type: "node",
+        kind: RuntimeKind::Node,
         executables: &["node"],
         version_command: &["node", "--version"],
         default_port: None,
     },
     RuntimeDetector {
         name: "Docker",
-        runtime_type: "docker",
+        kind: RuntimeKind::Docker,
         executables: &["docker"],
         version_command: &["docker", "--version"],
         default_port: None,
@@ -113,9 +117,9 @@ async fn detect_runtime(detector: &RuntimeDetector) -> Option<DetectedRuntime> {
             let version = get_version(&path, detector.version_command).await;
             
             return Some(DetectedRuntime {
-                id: format!("{}_{}", detector.runtime_type, executable),
+                id: RuntimeId::new(detector.kind, *executable).to_string(),
                 name: detector.name.to_string(),
-                runtime_type: detector.runtime_type.to_string(),
+                runtime_type: detector.kind.as_str().to_string(),
                 executable_path: path,
                 version,
                 auto_detected: true,
@@ -204,9 +208,9 @@ async fn scan_docker_containers() -> Result<Vec<DetectedRuntime>, String> {
             // Check if it's an AI service container
             if is_ai_container(image) {
                 runtimes.push(DetectedRuntime {
-                    id: format!("docker_{}", container_id),
+                    id: RuntimeId::new(RuntimeKind::Docker, container_id).to_string(),
                     name: format!("Docker: {}", name),
-                    runtime_type: "docker".to_string(),
+                    runtime_type: RuntimeKind::Docker.as_str().to_string(),
                     executable_path: format!("docker:{}", container_id),
                     version: Some(image.to_string()),
                     auto_detected: true,
@@ -225,27 +229,15 @@ pub(crate) fn is_ai_container(image: &str) -> bool {
 }
 
 /// Get runtime status
+///
+/// Ollama and LocalAI are checked on the endpoint of the instance `runtime_id` names.
 #[tauri::command]
-pub async fn get_runtime_status(runtime_id: String) -> Result<RuntimeStatus, String> {
-    // Parse runtime type from ID
-    let parts: Vec<&str> = runtime_id.split('_').collect();
-    if parts.is_empty() {
-        return Err("Invalid runtime ID".to_string());
-    }
-
-    let runtime_type = parts[0];
-
-    match runtime_type {
-        "ollama" => check_ollama_status().await,
-        "localai" => check_localai_status().await,
-        "docker" => {
-            if parts.len() >= 2 {
-                check_docker_status(parts[1]).await
-            } else {
-                Err("Invalid Docker runtime ID".to_string())
-            }
-        }
-        "python" | "node" => {
+pub async fn get_runtime_status(runtime_id: RuntimeId) -> Result<RuntimeStatus, String> {
+    match (runtime_id.kind, runtime_instance::endpoint_for(&runtime_id)) {
+        (RuntimeKind::Ollama, Some(endpoint)) => check_ollama_status(&endpoint).await,
+        (RuntimeKind::LocalAi, Some(endpoint)) => check_localai_status(&endpoint).await,
+        (RuntimeKind::Docker, _) => check_docker_status(&runtime_id.locator).await,
+        (RuntimeKind::Python | RuntimeKind::Node, _) => {
             // These are interpreters, not services
             Ok(RuntimeStatus {
                 status: "stopped".to_string(),
@@ -255,21 +247,21 @@ pub async fn get_runtime_status(runtime_id: String) -> Result<RuntimeStatus, Str
                 error: None,
             })
         }
-        _ => Err(format!("Unknown runtime type: {}", runtime_type)),
+        (RuntimeKind::Ollama | RuntimeKind::LocalAi | RuntimeKind::Custom, _) => check_custom_status(&runtime_id),
     }
 }
 
 /// Check Ollama status
-async fn check_ollama_status() -> Result<RuntimeStatus, String> {
+async fn check_ollama_status(endpoint: &Endpoint) -> Result<RuntimeStatus, String> {
     // Try to connect to Ollama API
-    match reqwest::get("http://localhost:11434/api/version").await {
+    match reqwest::get(endpoint.url("/api/version")).await {
         Ok(response) if response.status().is_success() => {
             let version = response.text().await.ok();
             Ok(RuntimeStatus {
                 status: "running".to_string(),
                 version,
                 uptime_seconds: None,
-                port: Some(11434),
+                port: Some(endpoint.port),
                 error: None,
             })
         }
@@ -277,47 +269,59 @@ async fn check_ollama_status() -> Result<RuntimeStatus, String> {
             status: "error".to_string(),
             version: None,
             uptime_seconds: None,
-            port: Some(11434),
+            port: Some(endpoint.port),
             error: Some("Ollama API returned error".to_string()),
         }),
         Err(_) => Ok(RuntimeStatus {
             status: "stopped".to_string(),
             version: None,
             uptime_seconds: None,
-            port: Some(11434),
+            port: Some(endpoint.port),
             error: None,
         }),
     }
 }
 
 /// Check LocalAI status
-async fn check_localai_status() -> Result<RuntimeStatus, String> {
+async fn check_localai_status(endpoint: &Endpoint) -> Result<RuntimeStatus, String> {
     // Try to connect to LocalAI API
-    match reqwest::get("http://localhost:8080/readyz").await {
+    match reqwest::get(endpoint.url("/readyz")).await {
         Ok(response) if response.status().is_success() => Ok(RuntimeStatus {
             status: "running".to_string(),
             version: None,
             uptime_seconds: None,
-            port: Some(8080),
+            port: Some(endpoint.port),
             error: None,
         }),
         Ok(_) => Ok(RuntimeStatus {
             status: "error".to_string(),
             version: None,
             uptime_seconds: None,
-            port: Some(8080),
+            port: Some(endpoint.port),
             error: Some("LocalAI API returned error".to_string()),
         }),
         Err(_) => Ok(RuntimeStatus {
             status: "stopped".to_string(),
             version: None,
             uptime_seconds: None,
-            port: Some(8080),
+            port: Some(endpoint.port),
             error: None,
         }),
     }
 }
 
+/// Check a custom runtime by looking for the process we started for it
+fn check_custom_status(runtime_id: &RuntimeId) -> Result<RuntimeStatus, String> {
+    let running = process::find_runtime_pid(runtime_id)?.is_some();
+    Ok(RuntimeStatus {
+        status: if running { "running" } else { "stopped" }.to_string(),
+        version: None,
+        uptime_seconds: None,
+        port: runtime_instance::endpoint_for(runtime_id).map(|endpoint| endpoint.port),
+        error: None,
+    })
+}
+
 /// Check Docker container status
 async fn check_docker_status(container_id: &str) -> Result<RuntimeStatus, String> {
     let output = Command::new("docker")
@@ -344,31 +348,54 @@ async fn check_docker_status(container_id: &str) -> Result<RuntimeStatus, String
 }
 
 /// Estimate resource usage for a runtime
+///
+/// A runtime we started is measured through its own process tree, so each
+/// instance reports only its share. An Ollama or LocalAI server started
+/// elsewhere can only be matched by process name, which is done for the
+/// default instance alone.
 #[tauri::command]
-pub async fn estimate_resource_usage(runtime_id: String) -> Result<ResourceUsage, String> {
-    let parts: Vec<&str> = runtime_id.split('_').collect();
-    if parts.is_empty() {
-        return Err("Invalid runtime ID".to_string());
+pub async fn estimate_resource_usage(runtime_id: RuntimeId) -> Result<ResourceUsage, String> {
+    if runtime_id.kind == RuntimeKind::Docker {
+        return estimate_docker_usage(&runtime_id.locator).await;
+    }
+    if let Some(pid) = process::find_runtime_pid(&runtime_id)? {
+        return Ok(estimate_tree_usage(pid));
     }
 
-    let runtime_type = parts[0];
+    let unconfigured = runtime_instance::instance_for(&runtime_id) == RuntimeInstance::default();
+    match runtime_id.kind {
+        RuntimeKind::Ollama if unconfigured => estimate_process_usage("ollama").await,
+        RuntimeKind::LocalAi if unconfigured => estimate_process_usage("local-ai").await,
+        _ => Ok(ResourceUsage::default()),
+    }
+}
 
-    match runtime_type {
-        "ollama" => estimate_process_usage("ollama").await,
-        "localai" => estimate_process_usage("local-ai").await,
-        "docker" => {
-            if parts.len() >= 2 {
-                estimate_docker_usage(parts[1]).await
-            } else {
-                Err("Invalid Docker runtime ID".to_string())
-            }
+/// Resource usage of a process and everything it spawned
+///
+/// Ollama, for one, serves models from separate runner processes.
+fn estimate_tree_usage(pid: u32) -> ResourceUsage {
+    let mut system = System::new();
+    system.refresh_processes();
+
+    let root = Pid::from_u32(pid);
+    let in_tree = |mut pid: Pid| loop {
+        if pid == root {
+            return true;
+        }
+        match system.process(pid).and_then(|process| process.parent()) {
+            Some(parent) => pid = parent,
+            None => return false,
+        }
+    };
+
+    let mut usage = ResourceUsage::default();
+    for (pid, process) in system.processes() {
+        if in_tree(*pid) {
+            usage.memory_mb += process.memory() as f64 / 1024.0 / 1024.0;
+            usage.cpu_percent += process.cpu_usage() as f64;
         }
-        _ => Ok(ResourceUsage {
-            memory_mb: 0.0,
-            vram_mb: None,
-            cpu_percent: 0.0,
-        }),
     }
+    usage
 }
 
 /// Estimate resource usage for a process by name
@@ -85,7 +85,7 @@ mod property_tests {
         ];
         
         for runtime_id in test_runtime_ids {
-            let result = get_runtime_status(runtime_id.to_string()).await;
+            let result = get_runtime_status(runtime_id.parse().unwrap()).await;
             
             if let Ok(status) = result {
                 // Status should be one of the valid values
@@ -118,7 +118,7 @@ mod property_tests {
     /// Test that status remains consistent across multiple calls
     #[tokio::test]
     async fn property_2_status_stability() {
-        let runtime_id = "python_python3".to_string();
+        let runtime_id: RuntimeId = "python_python3".parse().unwrap();
         
         // Get status twice
         let status1 = get_runtime_status(runtime_id.clone()).await;
@@ -148,7 +148,7 @@ mod property_tests {
         ];
         
         for runtime_id in test_runtime_ids {
-            let result = estimate_resource_usage(runtime_id.to_string()).await;
+            let result = estimate_resource_usage(runtime_id.parse().unwrap()).await;
             
             if let Ok(usage) = result {
                 // Memory should be non-negative
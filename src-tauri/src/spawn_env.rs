@@ -0,0 +1,219 @@
+// Spawn Env - environment variables injected into spawned tools and runtimes
+// Values are either literals or credentials resolved from secure storage at spawn time
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::error::AppError;
+use crate::secure_storage;
+
+/// Placeholder shown instead of a secret value
+pub const REDACTED: &str = "***";
+
+/// Where the value of an injected environment variable comes from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum EnvSource {
+    /// A fixed value
+    Literal { value: String },
+    /// A credential from secure storage, looked up by key
+    ///
+    /// An optional secret that isn't stored is skipped, which leaves any value
+    /// inherited from the app's environment in place.
+    Secret {
+        key: String,
+        #[serde(default)]
+        optional: bool,
+    },
+}
+
+/// Environment variable name mapped to where its value comes from
+pub type EnvMappings = HashMap<String, EnvSource>;
+
+/// Environment variables ready to be applied to a command
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedEnv {
+    vars: Vec<(String, String)>,
+    /// Values that came from secure storage and must never be shown
+    secrets: Vec<String>,
+}
+
+impl ResolvedEnv {
+    /// Variables in the order they are applied
+    pub fn vars(&self) -> &[(String, String)] {
+        &self.vars
+    }
+
+    /// Set a variable, replacing any value it already has
+    pub fn set(&mut self, key: String, value: String) {
+        self.vars.retain(|(existing, _)| *existing != key);
+        self.vars.push((key, value));
+    }
+
+    /// Set the variables on a `std::process::Command`
+    pub fn apply(&self, command: &mut Command) {
+        command.envs(self.vars().iter().map(|(k, v)| (k, v)));
+    }
+
+    /// Set the variables on a pseudo-terminal command
+    pub fn apply_pty(&self, command: &mut portable_pty::CommandBuilder) {
+        for (key, value) in self.vars() {
+            command.env(key, value);
+        }
+    }
+
+    /// Replace every secret value in `text` with a placeholder
+    pub fn redact(&self, text: &str) -> String {
+        self.secrets
+            .iter()
+            .filter(|secret| !secret.is_empty())
+            .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), REDACTED))
+    }
+
+    /// Redact each part of a command line
+    pub fn redact_command_line(&self, command_line: Vec<String>) -> Vec<String> {
+        if self.secrets.is_empty() {
+            return command_line;
+        }
+        command_line.iter().map(|arg| self.redact(arg)).collect()
+    }
+}
+
+/// Resolve env mappings, reading secrets from secure storage
+///
+/// If secure storage is unavailable, optional secrets are skipped rather than
+/// failing the spawn.
+pub async fn resolve(mappings: &EnvMappings) -> Result<ResolvedEnv, String> {
+    let mut stored = HashMap::new();
+    for source in mappings.values() {
+        if let EnvSource::Secret { key, optional } = source {
+            if stored.contains_key(key) {
+                continue;
+            }
+            let value = match secure_storage::retrieve_credential(key.clone()).await {
+                Ok(value) => value,
+                Err(e) if *optional => {
+                    eprintln!("Warning: skipping credential {}: {}", key, e);
+                    None
+                }
+                Err(e) => return Err(e.to_string()),
+            };
+            stored.insert(key.clone(), value);
+        }
+    }
+
+    resolve_with(mappings, |key| stored.get(key).cloned().flatten())
+}
+
+/// Resolve env mappings using `lookup` to fetch secrets
+fn resolve_with(
+    mappings: &EnvMappings,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<ResolvedEnv, String> {
+    let mut resolved = ResolvedEnv::default();
+
+    // Sorted so the same mappings always produce the same environment
+    let mut names: Vec<&String> = mappings.keys().collect();
+    names.sort();
+
+    for name in names {
+        match &mappings[name] {
+            EnvSource::Literal { value } => resolved.vars.push((name.clone(), value.clone())),
+            EnvSource::Secret { key, optional } => match lookup(key) {
+                Some(value) => {
+                    resolved.secrets.push(value.clone());
+                    resolved.vars.push((name.clone(), value));
+                }
+                None if *optional => {}
+                None => {
+                    return Err(AppError::CredentialNotFound(format!(
+                        "{} (needed for {})",
+                        key, name
+                    ))
+                    .to_string())
+                }
+            },
+        }
+    }
+
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret(key: &str, optional: bool) -> EnvSource {
+        EnvSource::Secret {
+            key: key.to_string(),
+            optional,
+        }
+    }
+
+    fn lookup(key: &str) -> Option<String> {
+        (key == "openai_api_key").then(|| "sk-test-123".to_string())
+    }
+
+    #[test]
+    fn test_resolve_literals_and_secrets() {
+        let mappings = EnvMappings::from([
+            ("OPENAI_API_KEY".to_string(), secret("openai_api_key", false)),
+            (
+                "OLLAMA_HOST".to_string(),
+                EnvSource::Literal {
+                    value: "127.0.0.1:11500".to_string(),
+                },
+            ),
+        ]);
+
+        let env = resolve_with(&mappings, lookup).unwrap();
+        assert_eq!(
+            env.vars(),
+            [
+                ("OLLAMA_HOST".to_string(), "127.0.0.1:11500".to_string()),
+                ("OPENAI_API_KEY".to_string(), "sk-test-123".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_secret() {
+        let required = EnvMappings::from([("ANTHROPIC_API_KEY".to_string(), secret("missing", false))]);
+        let err = resolve_with(&required, lookup).unwrap_err();
+        assert!(err.contains("missing"));
+        assert!(err.contains("ANTHROPIC_API_KEY"));
+
+        let optional = EnvMappings::from([("ANTHROPIC_API_KEY".to_string(), secret("missing", true))]);
+        assert!(resolve_with(&optional, lookup).unwrap().vars().is_empty());
+    }
+
+    #[test]
+    fn test_redact_command_line() {
+        let mappings = EnvMappings::from([("OPENAI_API_KEY".to_string(), secret("openai_api_key", false))]);
+        let env = resolve_with(&mappings, lookup).unwrap();
+
+        let command_line = vec![
+            "codex".to_string(),
+            "--api-key=sk-test-123".to_string(),
+            "exec".to_string(),
+        ];
+        assert_eq!(
+            env.redact_command_line(command_line),
+            ["codex", "--api-key=***", "exec"]
+        );
+    }
+
+    #[test]
+    fn test_env_source_serialization() {
+        let json = r#"{"source":"secret","key":"openai_api_key"}"#;
+        let source: EnvSource = serde_json::from_str(json).unwrap();
+        assert_eq!(source, secret("openai_api_key", false));
+
+        let json = serde_json::to_string(&EnvSource::Literal {
+            value: "1".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"source":"literal","value":"1"}"#);
+    }
+}
@@ -3,9 +3,13 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use tauri::AppHandle;
 use tauri_plugin_store::StoreExt;
 
+use crate::runtime_instance::{self, RuntimeInstance};
+use crate::supervisor::{self, RestartPolicy};
+
 /// Settings data structure (settings.json)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SettingsData {
@@ -86,6 +90,12 @@ pub struct RuntimesData {
     pub custom_runtimes: Vec<Value>,
     #[serde(rename = "lastScan")]
     pub last_scan: u64,
+    /// Supervisor restart policies by runtime ID
+    #[serde(rename = "restartPolicies", default)]
+    pub restart_policies: HashMap<String, RestartPolicy>,
+    /// Host, port and models directory by runtime ID
+    #[serde(default)]
+    pub instances: HashMap<String, RuntimeInstance>,
 }
 
 impl Default for RuntimesData {
@@ -94,6 +104,8 @@ impl Default for RuntimesData {
             version: 1,
             custom_runtimes: Vec::new(),
             last_scan: 0,
+            restart_policies: HashMap::new(),
+            instances: HashMap::new(),
         }
     }
 }
@@ -210,6 +222,17 @@ pub async fn save_runtimes(app: AppHandle, runtimes: RuntimesData) -> Result<(),
         .save()
         .map_err(|e| format!("Failed to persist runtimes: {}", e))?;
 
+    supervisor::set_policies(runtimes.restart_policies);
+    runtime_instance::set_instances(runtimes.instances);
+
+    Ok(())
+}
+
+/// Hand the stored restart policies and instance settings to the backend at startup
+pub async fn sync_runtime_settings(app: AppHandle) -> Result<(), String> {
+    let runtimes = load_runtimes(app).await?;
+    supervisor::set_policies(runtimes.restart_policies);
+    runtime_instance::set_instances(runtimes.instances);
     Ok(())
 }
 
@@ -244,6 +267,16 @@ mod tests {
         assert_eq!(runtimes.version, 1);
         assert!(runtimes.custom_runtimes.is_empty());
         assert_eq!(runtimes.last_scan, 0);
+        assert!(runtimes.restart_policies.is_empty());
+    }
+
+    #[test]
+    fn test_runtimes_without_restart_policies() {
+        // runtimes.json written before restart policies existed
+        let json = r#"{"version":1,"customRuntimes":[],"lastScan":0}"#;
+        let runtimes: RuntimesData = serde_json::from_str(json).unwrap();
+        assert!(runtimes.restart_policies.is_empty());
+        assert!(runtimes.instances.is_empty());
     }
 
     #[test]
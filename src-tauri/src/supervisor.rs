@@ -0,0 +1,468 @@
+// Supervisor - restarts managed runtimes when they exit, according to per-runtime policies
+// Restarts back off exponentially and stop entirely once a runtime is crash-looping
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::events;
+use crate::output;
+use crate::process::{self, ProcessInfo, ProcessStatus};
+use crate::runtime_id::RuntimeId;
+
+/// Event emitted when the supervisor schedules a restart or gives up
+pub const SUPERVISOR_EVENT: &str = "runtime://supervisor";
+
+/// When a runtime should be restarted after it exits
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartMode {
+    /// Leave the runtime stopped
+    #[default]
+    Never,
+    /// Restart only after a non-zero exit or a signal
+    OnFailure,
+    /// Restart after any exit that wasn't requested by the user
+    Always,
+}
+
+/// Restart policy for one runtime, stored in runtimes.json
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RestartPolicy {
+    pub mode: RestartMode,
+    /// Delay before the first restart; doubles with each further restart
+    #[serde(rename = "initialBackoffMs")]
+    pub initial_backoff_ms: u64,
+    /// Upper bound for the restart delay
+    #[serde(rename = "maxBackoffMs")]
+    pub max_backoff_ms: u64,
+    /// Restarts allowed within the retry window before the circuit opens
+    #[serde(rename = "maxRetries")]
+    pub max_retries: u32,
+    /// Window over which restarts are counted
+    #[serde(rename = "retryWindowMs")]
+    pub retry_window_ms: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            mode: RestartMode::Never,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 60_000,
+            max_retries: 5,
+            retry_window_ms: 10 * 60 * 1_000,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Whether a process that ended in `status` should be restarted
+    fn wants_restart(&self, status: &ProcessStatus) -> bool {
+        match (self.mode, status) {
+            (RestartMode::Never, _) => false,
+            (RestartMode::OnFailure, ProcessStatus::Crashed | ProcessStatus::LimitExceeded) => true,
+            (
+                RestartMode::Always,
+                ProcessStatus::Crashed | ProcessStatus::LimitExceeded | ProcessStatus::Exited,
+            ) => true,
+            _ => false,
+        }
+    }
+
+    /// Delay before restart number `attempt` (starting at 1)
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX);
+        let delay = self.initial_backoff_ms.saturating_mul(factor);
+        Duration::from_millis(delay.min(self.max_backoff_ms))
+    }
+}
+
+/// Supervision state of one runtime
+#[derive(Debug, Clone, Default)]
+struct Supervision {
+    /// Times of recent restarts, oldest first
+    restarts: VecDeque<u64>,
+    /// Set once the runtime restarted too often; cleared by a reset
+    circuit_open: bool,
+    /// When the next scheduled restart fires
+    next_restart_at: Option<u64>,
+}
+
+/// What the supervisor does about an exit
+#[derive(Debug, Clone, PartialEq)]
+enum Decision {
+    Ignore,
+    Restart { attempt: u32, delay: Duration },
+    OpenCircuit,
+}
+
+impl Supervision {
+    /// Decide how to react to an exit and record the restart if there is one
+    fn decide(&mut self, policy: &RestartPolicy, status: &ProcessStatus, now: u64) -> Decision {
+        if self.circuit_open || !policy.wants_restart(status) {
+            return Decision::Ignore;
+        }
+
+        let window_start = now.saturating_sub(policy.retry_window_ms);
+        while self.restarts.front().is_some_and(|&t| t < window_start) {
+            self.restarts.pop_front();
+        }
+
+        if self.restarts.len() as u32 >= policy.max_retries {
+            self.circuit_open = true;
+            self.next_restart_at = None;
+            return Decision::OpenCircuit;
+        }
+
+        self.restarts.push_back(now);
+        let attempt = self.restarts.len() as u32;
+        let delay = policy.backoff(attempt);
+        self.next_restart_at = Some(now + delay.as_millis() as u64);
+        Decision::Restart { attempt, delay }
+    }
+}
+
+/// Supervisor state reported to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisorStatus {
+    pub runtime_id: RuntimeId,
+    pub policy: RestartPolicy,
+    pub recent_restarts: u32,
+    pub circuit_open: bool,
+    pub next_restart_at: Option<u64>,
+}
+
+/// Payload of a supervisor event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SupervisorEvent {
+    RestartScheduled {
+        runtime_id: RuntimeId,
+        attempt: u32,
+        delay_ms: u64,
+    },
+    Restarted {
+        runtime_id: RuntimeId,
+        pid: u32,
+    },
+    RestartFailed {
+        runtime_id: RuntimeId,
+        error: String,
+    },
+    CircuitOpen {
+        runtime_id: RuntimeId,
+        restarts: u32,
+    },
+}
+
+/// Restart policies by runtime ID
+fn policies() -> &'static Mutex<HashMap<RuntimeId, RestartPolicy>> {
+    static POLICIES: OnceLock<Mutex<HashMap<RuntimeId, RestartPolicy>>> = OnceLock::new();
+    POLICIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Supervision state by runtime ID
+fn supervisions() -> &'static Mutex<HashMap<RuntimeId, Supervision>> {
+    static SUPERVISIONS: OnceLock<Mutex<HashMap<RuntimeId, Supervision>>> = OnceLock::new();
+    SUPERVISIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Replace all restart policies, e.g. after runtimes.json was loaded or saved
+///
+/// Runtimes whose policy changed start over with a closed circuit. Entries
+/// with an invalid runtime ID are skipped.
+pub fn set_policies(new_policies: HashMap<String, RestartPolicy>) {
+    let new_policies: HashMap<RuntimeId, RestartPolicy> = new_policies
+        .into_iter()
+        .filter_map(|(id, policy)| match id.parse() {
+            Ok(id) => Some((id, policy)),
+            Err(e) => {
+                eprintln!("Warning: ignoring restart policy: {}", e);
+                None
+            }
+        })
+        .collect();
+
+    let Ok(mut current) = policies().lock() else {
+        return;
+    };
+    if let Ok(mut states) = supervisions().lock() {
+        states.retain(|id, _| current.get(id) == new_policies.get(id));
+    }
+    *current = new_policies;
+}
+
+/// Set or clear the restart policy of one runtime, leaving the others alone
+#[cfg(test)]
+pub(crate) fn set_policy(runtime_id: &RuntimeId, policy: Option<RestartPolicy>) {
+    let Ok(mut current) = policies().lock() else {
+        return;
+    };
+    if current.get(runtime_id) != policy.as_ref() {
+        if let Ok(mut states) = supervisions().lock() {
+            states.remove(runtime_id);
+        }
+    }
+    match policy {
+        Some(policy) => current.insert(runtime_id.clone(), policy),
+        None => current.remove(runtime_id),
+    };
+}
+
+/// Policy for a runtime, falling back to never restarting
+fn policy_for(runtime_id: &RuntimeId) -> RestartPolicy {
+    policies()
+        .lock()
+        .ok()
+        .and_then(|p| p.get(runtime_id).cloned())
+        .unwrap_or_default()
+}
+
+/// React to a runtime process reaching a final state
+///
+/// Called by the process reaper. Only processes started through
+/// `start_runtime` are supervised, and a user-requested stop never triggers a
+/// restart.
+pub(crate) fn on_exit(process: &ProcessInfo) {
+    let Some(runtime_id) = process.runtime_id.clone() else {
+        return;
+    };
+    schedule(runtime_id, &process.status);
+}
+
+/// Decide on a restart and, if one is due, run it in the background
+fn schedule(runtime_id: RuntimeId, status: &ProcessStatus) {
+    let policy = policy_for(&runtime_id);
+    let decision = match supervisions().lock() {
+        Ok(mut states) => states
+            .entry(runtime_id.clone())
+            .or_default()
+            .decide(&policy, status, output::now_millis()),
+        Err(_) => return,
+    };
+
+    match decision {
+        Decision::Ignore => {}
+        Decision::OpenCircuit => {
+            eprintln!("Warning: runtime {} is crash-looping, not restarting", runtime_id);
+            events::emit(
+                SUPERVISOR_EVENT,
+                SupervisorEvent::CircuitOpen {
+                    runtime_id,
+                    restarts: policy.max_retries,
+                },
+            );
+        }
+        Decision::Restart { attempt, delay } => {
+            events::emit(
+                SUPERVISOR_EVENT,
+                SupervisorEvent::RestartScheduled {
+                    runtime_id: runtime_id.clone(),
+                    attempt,
+                    delay_ms: delay.as_millis() as u64,
+                },
+            );
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(delay).await;
+                restart(runtime_id).await;
+            });
+        }
+    }
+}
+
+/// Run a scheduled restart unless it was cancelled in the meantime
+async fn restart(runtime_id: RuntimeId) {
+    let still_due = supervisions()
+        .lock()
+        .ok()
+        .and_then(|states| states.get(&runtime_id).map(|s| !s.circuit_open))
+        .unwrap_or(false);
+    if let Ok(mut states) = supervisions().lock() {
+        if let Some(state) = states.get_mut(&runtime_id) {
+            state.next_restart_at = None;
+        }
+    }
+
+    // Started by hand while we were waiting, or the policy was changed
+    let already_running = matches!(process::find_runtime_pid(&runtime_id), Ok(Some(_)));
+    if !still_due || already_running || policy_for(&runtime_id).mode == RestartMode::Never {
+        return;
+    }
+
+    match process::relaunch_runtime(&runtime_id).await {
+        Ok(pid) => events::emit(SUPERVISOR_EVENT, SupervisorEvent::Restarted { runtime_id, pid }),
+        Err(error) => {
+            eprintln!("Warning: failed to restart runtime {}: {}", runtime_id, error);
+            events::emit(
+                SUPERVISOR_EVENT,
+                SupervisorEvent::RestartFailed {
+                    runtime_id: runtime_id.clone(),
+                    error,
+                },
+            );
+            // A launch failure counts as a crash so it backs off and trips the breaker
+            schedule(runtime_id, &ProcessStatus::Crashed);
+        }
+    }
+}
+
+/// Get the supervisor state of a runtime
+#[tauri::command]
+pub fn get_supervisor_status(runtime_id: RuntimeId) -> Result<SupervisorStatus, String> {
+    let policy = policy_for(&runtime_id);
+    let state = supervisions()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&runtime_id)
+        .cloned()
+        .unwrap_or_default();
+
+    let window_start = output::now_millis().saturating_sub(policy.retry_window_ms);
+    let recent_restarts = state.restarts.iter().filter(|&&t| t >= window_start).count() as u32;
+
+    Ok(SupervisorStatus {
+        runtime_id,
+        policy,
+        recent_restarts,
+        circuit_open: state.circuit_open,
+        next_restart_at: state.next_restart_at,
+    })
+}
+
+/// Close the circuit breaker of a runtime so it is restarted again
+#[tauri::command]
+pub fn reset_supervisor(runtime_id: RuntimeId) -> Result<(), String> {
+    supervisions()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&runtime_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(mode: RestartMode) -> RestartPolicy {
+        RestartPolicy {
+            mode,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+            max_retries: 3,
+            retry_window_ms: 10_000,
+        }
+    }
+
+    #[test]
+    fn test_restart_modes() {
+        let never = policy(RestartMode::Never);
+        let on_failure = policy(RestartMode::OnFailure);
+        let always = policy(RestartMode::Always);
+
+        assert!(!never.wants_restart(&ProcessStatus::Crashed));
+        assert!(on_failure.wants_restart(&ProcessStatus::Crashed));
+        assert!(!on_failure.wants_restart(&ProcessStatus::Exited));
+        assert!(always.wants_restart(&ProcessStatus::Exited));
+
+        // A stop requested by the user is never undone
+        assert!(!always.wants_restart(&ProcessStatus::Stopped));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let policy = policy(RestartMode::Always);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_millis(1_000));
+        assert_eq!(policy.backoff(80), Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn test_circuit_opens_after_max_retries() {
+        let policy = policy(RestartMode::OnFailure);
+        let mut state = Supervision::default();
+
+        for attempt in 1..=3 {
+            assert!(matches!(
+                state.decide(&policy, &ProcessStatus::Crashed, 1_000 + attempt as u64),
+                Decision::Restart { attempt: a, .. } if a == attempt
+            ));
+        }
+        assert_eq!(
+            state.decide(&policy, &ProcessStatus::Crashed, 1_010),
+            Decision::OpenCircuit
+        );
+        assert_eq!(
+            state.decide(&policy, &ProcessStatus::Crashed, 1_020),
+            Decision::Ignore
+        );
+    }
+
+    #[test]
+    fn test_restarts_outside_window_are_forgotten() {
+        let policy = policy(RestartMode::OnFailure);
+        let mut state = Supervision::default();
+
+        for t in [0, 1, 2] {
+            state.decide(&policy, &ProcessStatus::Crashed, t);
+        }
+
+        // Long after the window the backoff starts over
+        assert_eq!(
+            state.decide(&policy, &ProcessStatus::Crashed, 50_000),
+            Decision::Restart {
+                attempt: 1,
+                delay: Duration::from_millis(100)
+            }
+        );
+    }
+
+    #[test]
+    fn test_policy_deserializes_with_defaults() {
+        let policy: RestartPolicy = serde_json::from_str(r#"{"mode":"on-failure"}"#).unwrap();
+        assert_eq!(policy.mode, RestartMode::OnFailure);
+        assert_eq!(policy.max_retries, RestartPolicy::default().max_retries);
+    }
+
+    #[tokio::test]
+    async fn test_crashed_runtime_is_restarted() {
+        let runtime_id: RuntimeId = "custom_supervisor_test".parse().unwrap();
+        set_policy(&runtime_id, Some(policy(RestartMode::OnFailure)));
+
+        let first = process::start_runtime(
+            runtime_id.clone(),
+            "sh".to_string(),
+            vec!["-c".to_string(), "sleep 0.2; exit 1".to_string()],
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let restarted = loop {
+            let pid = process::find_runtime_pid(&runtime_id).unwrap();
+            if let Some(pid) = pid.filter(|&pid| pid != first) {
+                break Some(pid);
+            }
+            if std::time::Instant::now() >= deadline {
+                break None;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        };
+
+        let restarted = restarted.expect("runtime should be restarted");
+        let status = get_supervisor_status(runtime_id.clone()).unwrap();
+        assert!(status.recent_restarts >= 1);
+
+        set_policy(&runtime_id, None);
+        let info = process::kill_process(restarted, Some(100)).await.unwrap();
+        assert_eq!(info.restart_count, 1);
+    }
+}
@@ -0,0 +1,36 @@
+// Test Support - fixtures shared by the unit tests of several modules
+// Fake tool executables, built-in adapters by ID and PIDs no real process can have
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::cli_adapter::{builtin_adapters, CLIAdapter};
+
+/// Write a shell script named `name` into `dir`, creating the directory, and make it executable
+#[cfg(unix)]
+pub fn fake_executable(dir: &Path, name: &str, body: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::create_dir_all(dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+/// The built-in adapter with the given ID
+pub fn adapter_named(id: &str) -> CLIAdapter {
+    builtin_adapters()
+        .into_iter()
+        .find(|adapter| adapter.id == id)
+        .unwrap_or_else(|| panic!("No built-in adapter '{}'", id))
+}
+
+/// A PID no real process can have, different on every call
+///
+/// For tests that only need per-process state, such as an output buffer.
+/// Counts down from `u32::MAX`, far above any kernel's PID limit.
+pub fn fake_pid() -> u32 {
+    static NEXT: AtomicU32 = AtomicU32::new(u32::MAX);
+    NEXT.fetch_sub(1, Ordering::Relaxed)
+}
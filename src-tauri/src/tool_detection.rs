@@ -0,0 +1,416 @@
+// Tool Detection - finds installed copies of a CLI tool and the version of each
+// Looks on PATH and in common install dirs that GUI apps often miss, then runs the version command
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::process::Command;
+
+use crate::spawn_env::ResolvedEnv;
+
+/// Time a detect or version command may take before it is killed
+pub const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One copy of a tool found on the system
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Installation {
+    pub path: String,
+    /// Semantic version parsed from the version command's output
+    pub version: Option<String>,
+    /// First line the version command printed
+    pub version_output: Option<String>,
+    /// Whether the copy is reachable through PATH rather than only a well-known dir
+    pub on_path: bool,
+    /// Why the detect or version command failed, if it did
+    pub error: Option<String>,
+}
+
+impl Installation {
+    pub fn works(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Directories searched for executables, PATH first
+///
+/// The rest are where npm, pip/pipx and cargo put global installs; an app
+/// started from a desktop launcher often has none of them on its PATH.
+pub fn search_dirs() -> Vec<(PathBuf, bool)> {
+    let path_dirs: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect())
+        .unwrap_or_default();
+    let mut dirs: Vec<(PathBuf, bool)> = path_dirs.into_iter().map(|dir| (dir, true)).collect();
+
+    for dir in well_known_dirs() {
+        if !dirs.iter().any(|(existing, _)| *existing == dir) {
+            dirs.push((dir, false));
+        }
+    }
+    dirs
+}
+
+fn well_known_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from);
+    let mut dirs = Vec::new();
+
+    if let Some(prefix) = std::env::var_os("NPM_CONFIG_PREFIX").map(PathBuf::from) {
+        dirs.push(if cfg!(windows) { prefix } else { prefix.join("bin") });
+    }
+    if let Some(cargo_home) = std::env::var_os("CARGO_HOME").map(PathBuf::from) {
+        dirs.push(cargo_home.join("bin"));
+    }
+    if let Some(home) = &home {
+        dirs.push(home.join(".local").join("bin"));
+        dirs.push(home.join(".cargo").join("bin"));
+        dirs.push(home.join(".npm-global").join("bin"));
+    }
+    #[cfg(windows)]
+    if let Some(app_data) = std::env::var_os("APPDATA").map(PathBuf::from) {
+        dirs.push(app_data.join("npm"));
+    }
+    #[cfg(unix)]
+    {
+        dirs.push(PathBuf::from("/usr/local/bin"));
+        dirs.push(PathBuf::from("/opt/homebrew/bin"));
+    }
+    dirs
+}
+
+/// Every copy of `executable` in `dirs`, in lookup order
+///
+/// An executable given as a path is only checked where it points.
+pub fn find_all(executable: &str, dirs: &[(PathBuf, bool)]) -> Vec<(PathBuf, bool)> {
+    if executable.contains('/') || executable.contains('\\') {
+        let path = PathBuf::from(executable);
+        return if is_executable(&path) { vec![(path, false)] } else { Vec::new() };
+    }
+
+    let mut found: Vec<(PathBuf, bool)> = Vec::new();
+    for (dir, on_path) in dirs {
+        for name in executable_names(executable) {
+            let candidate = dir.join(name);
+            if is_executable(&candidate) && !found.iter().any(|(path, _)| *path == candidate) {
+                found.push((candidate, *on_path));
+            }
+        }
+    }
+    found
+}
+
+/// The copy of `executable` detection reports, or the bare name if none is found
+///
+/// Spawning this instead of the bare name finds tools that are only in a
+/// well-known directory, which a desktop launcher's PATH leaves out.
+pub fn resolve(executable: &str, dirs: &[(PathBuf, bool)]) -> String {
+    find_all(executable, dirs)
+        .into_iter()
+        .next()
+        .map(|(path, _)| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|| executable.to_string())
+}
+
+/// File names `executable` may have on this platform
+fn executable_names(executable: &str) -> Vec<OsString> {
+    if cfg!(windows) {
+        ["", ".exe", ".cmd", ".bat"]
+            .iter()
+            .map(|ext| OsString::from(format!("{}{}", executable, ext)))
+            .collect()
+    } else {
+        vec![OsString::from(executable)]
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    let Ok(metadata) = path.metadata() else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.is_file()
+    }
+}
+
+/// Run `command_line` with its program replaced by `path` and return its output
+///
+/// The command line is split on whitespace. Both stdout and stderr are
+/// returned since some tools print their version on stderr.
+pub async fn run_with(path: &Path, command_line: &str, timeout: Duration) -> Result<String, String> {
+    run_with_env(path, command_line, timeout, &ResolvedEnv::default()).await
+}
+
+/// `run_with`, with `env` set on the command
+pub async fn run_with_env(
+    path: &Path,
+    command_line: &str,
+    timeout: Duration,
+    env: &ResolvedEnv,
+) -> Result<String, String> {
+    let args: Vec<&str> = command_line.split_whitespace().skip(1).collect();
+    let mut command = Command::new(path);
+    env.apply(command.as_std_mut());
+    command
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(timeout, command.output())
+        .await
+        .map_err(|_| format!("'{}' did not finish within {} s", command_line, timeout.as_secs_f32()))?
+        .map_err(|e| format!("Failed to run '{}': {}", command_line, e))?;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() {
+        return Err(format!(
+            "'{}' exited with {}: {}",
+            command_line,
+            output.status,
+            text.trim()
+        ));
+    }
+    Ok(text)
+}
+
+/// The first semantic version in `output`
+///
+/// "1.2" is read as 1.2.0 and a leading "v" is ignored.
+pub fn parse_version(output: &str) -> Option<semver::Version> {
+    static VERSION: OnceLock<Regex> = OnceLock::new();
+    let pattern = VERSION.get_or_init(|| {
+        Regex::new(r"(\d+)\.(\d+)(?:\.(\d+))?(-[0-9A-Za-z.-]+)?(\+[0-9A-Za-z.-]+)?").unwrap()
+    });
+
+    let captures = pattern.captures(output)?;
+    let text = format!(
+        "{}.{}.{}{}{}",
+        &captures[1],
+        &captures[2],
+        captures.get(3).map_or("0", |m| m.as_str()),
+        captures.get(4).map_or("", |m| m.as_str()),
+        captures.get(5).map_or("", |m| m.as_str()),
+    );
+    semver::Version::parse(&text).ok()
+}
+
+/// Probe one copy of a tool with its detect and version commands
+pub async fn inspect(
+    path: PathBuf,
+    on_path: bool,
+    detect_command: &str,
+    version_command: &str,
+    timeout: Duration,
+) -> Installation {
+    let mut installation = Installation {
+        path: path.display().to_string(),
+        version: None,
+        version_output: None,
+        on_path,
+        error: None,
+    };
+
+    if detect_command != version_command {
+        if let Err(e) = run_with(&path, detect_command, timeout).await {
+            installation.error = Some(e);
+            return installation;
+        }
+    }
+    match run_with(&path, version_command, timeout).await {
+        Ok(output) => {
+            installation.version = parse_version(&output).map(|v| v.to_string());
+            installation.version_output = output.lines().map(str::trim).find(|l| !l.is_empty()).map(String::from);
+        }
+        Err(e) => installation.error = Some(e),
+    }
+    installation
+}
+
+/// Package manager a copy of a tool was installed with
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallMethod {
+    Npm,
+    Brew,
+    Pipx,
+    Cargo,
+}
+
+impl InstallMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InstallMethod::Npm => "npm",
+            InstallMethod::Brew => "brew",
+            InstallMethod::Pipx => "pipx",
+            InstallMethod::Cargo => "cargo",
+        }
+    }
+
+    /// Command that upgrades `package` to its latest release
+    pub fn upgrade_command(&self, package: &str) -> String {
+        match self {
+            InstallMethod::Npm => format!("npm install -g {}@latest", package),
+            InstallMethod::Brew => format!("brew upgrade {}", package),
+            InstallMethod::Pipx => format!("pipx upgrade {}", package),
+            InstallMethod::Cargo => format!("cargo install {}", package),
+        }
+    }
+}
+
+/// Work out how the copy at `path` was installed, and under which package name
+///
+/// Launchers in bin dirs are usually symlinks into the package manager's own
+/// tree, so the link is resolved first.
+pub fn install_method(path: &Path) -> Option<(InstallMethod, Option<String>)> {
+    let resolved = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let components: Vec<String> = resolved
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let after = |marker: &str| {
+        components
+            .iter()
+            .position(|c| c == marker)
+            .and_then(|i| components.get(i + 1))
+            .cloned()
+    };
+
+    if let Some(i) = components.iter().position(|c| c == "node_modules") {
+        let package = match components.get(i + 1) {
+            Some(scope) if scope.starts_with('@') => components.get(i + 2).map(|name| format!("{}/{}", scope, name)),
+            other => other.cloned(),
+        };
+        return Some((InstallMethod::Npm, package));
+    }
+    if components.iter().any(|c| c == "Cellar") {
+        return Some((InstallMethod::Brew, after("Cellar")));
+    }
+    if components.iter().any(|c| c == "pipx") {
+        return Some((InstallMethod::Pipx, after("venvs")));
+    }
+    let in_dir = |parent: &str, dir: &str| {
+        components.windows(2).any(|pair| pair[0] == parent && pair[1] == dir)
+    };
+    if in_dir(".cargo", "bin") {
+        return Some((InstallMethod::Cargo, None));
+    }
+    if in_dir(".npm-global", "bin") || in_dir("npm", "bin") {
+        return Some((InstallMethod::Npm, None));
+    }
+    if components.iter().any(|c| c == "homebrew" || c == "linuxbrew") {
+        return Some((InstallMethod::Brew, None));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    #[cfg(unix)]
+    use crate::test_support::fake_executable;
+
+    #[test]
+    fn test_parse_version() {
+        let version = |text| parse_version(text).map(|v| v.to_string());
+        assert_eq!(version("codex-cli 0.21.0"), Some("0.21.0".to_string()));
+        assert_eq!(version("1.0.93 (Claude Code)"), Some("1.0.93".to_string()));
+        assert_eq!(version("aider v0.86"), Some("0.86.0".to_string()));
+        assert_eq!(version("tool 2.0.0-beta.3+build.7"), Some("2.0.0-beta.3+build.7".to_string()));
+        assert_eq!(version("no version here"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_finds_every_copy() {
+        let temp = TempDir::new().unwrap();
+        let first = temp.path().join("first");
+        let second = temp.path().join("second");
+        fake_executable(&first, "fake-tool", "echo 'fake-tool 1.4.2'");
+        fake_executable(&second, "fake-tool", "echo 'fake-tool 1.1.0' >&2");
+        std::fs::write(second.join("not-executable"), "").unwrap();
+
+        let dirs = vec![(first.clone(), true), (second.clone(), false), (first.clone(), true)];
+        let found = find_all("fake-tool", &dirs);
+        assert_eq!(found, vec![(first.join("fake-tool"), true), (second.join("fake-tool"), false)]);
+        assert!(find_all("not-executable", &dirs).is_empty());
+
+        let mut installations = Vec::new();
+        for (path, on_path) in found {
+            installations.push(inspect(path, on_path, "fake-tool --version", "fake-tool --version", COMMAND_TIMEOUT).await);
+        }
+        assert_eq!(installations[0].version.as_deref(), Some("1.4.2"));
+        assert_eq!(installations[1].version.as_deref(), Some("1.1.0"));
+        assert_eq!(installations[1].version_output.as_deref(), Some("fake-tool 1.1.0"));
+        assert!(!installations[1].on_path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_prefers_first_copy() {
+        let temp = TempDir::new().unwrap();
+        let off_path = temp.path().join("npm-global");
+        let tool = fake_executable(&off_path, "fake-tool", "exit 0");
+
+        let dirs = vec![(temp.path().join("empty"), true), (off_path, false)];
+        assert_eq!(resolve("fake-tool", &dirs), tool.to_string_lossy());
+        assert_eq!(resolve("missing-tool", &dirs), "missing-tool");
+        assert_eq!(resolve(&tool.to_string_lossy(), &[]), tool.to_string_lossy());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_method() {
+        let temp = TempDir::new().unwrap();
+        let package_dir = temp.path().join("lib/node_modules/@openai/codex/bin");
+        let launcher = fake_executable(&package_dir, "codex.js", "exit 0");
+        let bin = temp.path().join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::os::unix::fs::symlink(&launcher, bin.join("codex")).unwrap();
+        assert_eq!(
+            install_method(&bin.join("codex")),
+            Some((InstallMethod::Npm, Some("@openai/codex".to_string())))
+        );
+
+        let method = |path: &str| install_method(Path::new(path));
+        assert_eq!(
+            method("/opt/homebrew/Cellar/aider/0.86.1/bin/aider"),
+            Some((InstallMethod::Brew, Some("aider".to_string())))
+        );
+        assert_eq!(
+            method("/home/u/.local/share/pipx/venvs/aider-chat/bin/aider"),
+            Some((InstallMethod::Pipx, Some("aider-chat".to_string())))
+        );
+        assert_eq!(method("/home/u/.cargo/bin/tool"), Some((InstallMethod::Cargo, None)));
+        assert_eq!(method("/usr/bin/tool"), None);
+        assert_eq!(InstallMethod::Pipx.upgrade_command("aider-chat"), "pipx upgrade aider-chat");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_and_hanging_commands() {
+        let temp = TempDir::new().unwrap();
+        let broken = fake_executable(temp.path(), "broken", "echo 'missing runtime' >&2; exit 3");
+        let installation = inspect(broken, true, "broken --version", "broken --version", COMMAND_TIMEOUT).await;
+        assert!(!installation.works());
+        assert!(installation.error.unwrap().contains("missing runtime"));
+
+        let hanging = fake_executable(temp.path(), "hanging", "sleep 5");
+        let err = run_with(&hanging, "hanging --version", Duration::from_millis(200))
+            .await
+            .unwrap_err();
+        assert!(err.contains("did not finish"), "{}", err);
+    }
+}
@@ -0,0 +1,671 @@
+// Tool Events - turns the JSON output modes of CLI tools into one event model
+// Parses claude's stream-json and codex's exec --json line by line, emits each event and keeps them for the session
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::cli_adapter::get_available_adapters;
+use crate::database::{self, Message};
+use crate::events;
+use crate::output;
+
+/// Event name for parsed tool events
+pub const TOOL_EVENT: &str = "process://tool-event";
+
+/// Machine-readable output mode of a CLI tool
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// `claude -p --output-format stream-json --verbose`
+    ClaudeStreamJson,
+    /// `codex exec --json`
+    CodexJson,
+}
+
+impl OutputFormat {
+    /// Whether a tool started with `args` writes this format
+    pub fn is_enabled_by(&self, args: &[String]) -> bool {
+        match self {
+            OutputFormat::ClaudeStreamJson => {
+                args.windows(2).any(|pair| pair[0] == "--output-format" && pair[1] == "stream-json")
+                    || args.iter().any(|arg| arg == "--output-format=stream-json")
+            }
+            OutputFormat::CodexJson => args.iter().any(|arg| arg == "--json"),
+        }
+    }
+}
+
+/// What happened to a file a tool edited
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChange {
+    Add,
+    Update,
+    Delete,
+}
+
+/// One event from a tool, whichever tool produced it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolEvent {
+    /// Text the assistant wrote
+    TextDelta { text: String },
+    /// The assistant invoked a tool (a shell command, an edit, an MCP tool, ...)
+    ToolCall {
+        id: Option<String>,
+        name: String,
+        input: Value,
+    },
+    /// What a tool call returned
+    ToolResult {
+        id: Option<String>,
+        output: String,
+        is_error: bool,
+    },
+    /// A file was changed on disk
+    FileEdit { path: String, change: FileChange },
+    /// Tokens used by the run, and its cost when the tool reports one
+    Usage {
+        input_tokens: u64,
+        output_tokens: u64,
+        cached_input_tokens: u64,
+        cost_usd: Option<f64>,
+    },
+    /// The tool reported a failure
+    Error { message: String },
+}
+
+/// Claude tools whose successful result means a file changed
+const CLAUDE_EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Incremental parser for one tool's output
+#[derive(Debug)]
+pub struct EventParser {
+    format: OutputFormat,
+    /// Text after the last complete line
+    pending: String,
+    /// Whether text arrived as deltas, so the full message mustn't repeat it
+    streamed_text: bool,
+    /// Edits awaiting the result of the call that makes them, by call ID
+    pending_edits: HashMap<String, Vec<(String, FileChange)>>,
+}
+
+impl EventParser {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            pending: String::new(),
+            streamed_text: false,
+            pending_edits: HashMap::new(),
+        }
+    }
+
+    /// Parse every line completed by `chunk`
+    pub fn feed(&mut self, chunk: &str) -> Vec<ToolEvent> {
+        self.pending.push_str(chunk);
+        let Some(end) = self.pending.rfind('\n') else {
+            return Vec::new();
+        };
+        let complete: String = self.pending.drain(..=end).collect();
+        complete.lines().flat_map(|line| self.parse_line(line)).collect()
+    }
+
+    /// Parse whatever is left once the output has ended
+    pub fn finish(&mut self) -> Vec<ToolEvent> {
+        let rest = std::mem::take(&mut self.pending);
+        self.parse_line(&rest)
+    }
+
+    /// Parse one line; anything that isn't a JSON object is ignored
+    fn parse_line(&mut self, line: &str) -> Vec<ToolEvent> {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            return Vec::new();
+        }
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            return Vec::new();
+        };
+        match self.format {
+            OutputFormat::ClaudeStreamJson => self.parse_claude(&value),
+            OutputFormat::CodexJson => self.parse_codex(&value),
+        }
+    }
+
+    fn parse_claude(&mut self, value: &Value) -> Vec<ToolEvent> {
+        let mut events = Vec::new();
+        match value["type"].as_str() {
+            // Only present with --include-partial-messages
+            Some("stream_event") => {
+                let delta = &value["event"]["delta"];
+                if delta["type"] == "text_delta" {
+                    self.streamed_text = true;
+                    events.push(text_delta(&delta["text"]));
+                }
+            }
+            Some("assistant") => {
+                for block in content_blocks(&value["message"]) {
+                    match block["type"].as_str() {
+                        Some("text") if !self.streamed_text => events.push(text_delta(&block["text"])),
+                        Some("tool_use") => {
+                            let id = string(&block["id"]);
+                            let name = string(&block["name"]).unwrap_or_default();
+                            let input = block["input"].clone();
+                            let path = input["file_path"].as_str().or_else(|| input["notebook_path"].as_str());
+                            if let (Some(id), Some(path)) = (&id, path) {
+                                if CLAUDE_EDIT_TOOLS.contains(&name.as_str()) {
+                                    // claude doesn't say whether Write created the file
+                                    self.pending_edits
+                                        .insert(id.clone(), vec![(path.to_string(), FileChange::Update)]);
+                                }
+                            }
+                            events.push(ToolEvent::ToolCall { id, name, input });
+                        }
+                        _ => {}
+                    }
+                }
+                self.streamed_text = false;
+            }
+            Some("user") => {
+                for block in content_blocks(&value["message"]) {
+                    if block["type"] != "tool_result" {
+                        continue;
+                    }
+                    let id = string(&block["tool_use_id"]);
+                    let is_error = block["is_error"].as_bool().unwrap_or(false);
+                    events.push(ToolEvent::ToolResult {
+                        id: id.clone(),
+                        output: result_text(&block["content"]),
+                        is_error,
+                    });
+                    events.extend(self.complete_edits(id.as_deref(), !is_error));
+                }
+            }
+            Some("result") => {
+                let usage = &value["usage"];
+                events.push(ToolEvent::Usage {
+                    input_tokens: usage["input_tokens"].as_u64().unwrap_or(0),
+                    output_tokens: usage["output_tokens"].as_u64().unwrap_or(0),
+                    cached_input_tokens: usage["cache_read_input_tokens"].as_u64().unwrap_or(0),
+                    cost_usd: value["total_cost_usd"].as_f64(),
+                });
+                let subtype = value["subtype"].as_str().unwrap_or("success");
+                if value["is_error"].as_bool().unwrap_or(false) || subtype != "success" {
+                    let message = string(&value["result"]).unwrap_or_else(|| subtype.to_string());
+                    events.push(ToolEvent::Error { message });
+                }
+            }
+            _ => {}
+        }
+        events
+    }
+
+    fn parse_codex(&mut self, value: &Value) -> Vec<ToolEvent> {
+        let item = &value["item"];
+        let mut events = Vec::new();
+        match (value["type"].as_str(), item["type"].as_str()) {
+            (Some("item.started"), Some("command_execution")) => events.push(ToolEvent::ToolCall {
+                id: string(&item["id"]),
+                name: "shell".to_string(),
+                input: json!({ "command": item["command"] }),
+            }),
+            (Some("item.started"), Some("mcp_tool_call")) => events.push(ToolEvent::ToolCall {
+                id: string(&item["id"]),
+                name: format!(
+                    "{}.{}",
+                    item["server"].as_str().unwrap_or_default(),
+                    item["tool"].as_str().unwrap_or_default()
+                ),
+                input: item["arguments"].clone(),
+            }),
+            (Some("item.completed"), Some("agent_message")) => events.push(text_delta(&item["text"])),
+            (Some("item.completed"), Some("command_execution")) => events.push(ToolEvent::ToolResult {
+                id: string(&item["id"]),
+                output: string(&item["aggregated_output"]).unwrap_or_default(),
+                is_error: item["status"] == "failed" || item["exit_code"].as_i64().is_some_and(|code| code != 0),
+            }),
+            (Some("item.completed"), Some("mcp_tool_call")) => events.push(ToolEvent::ToolResult {
+                id: string(&item["id"]),
+                output: result_text(if item["error"].is_null() { &item["result"] } else { &item["error"] }),
+                is_error: item["status"] == "failed",
+            }),
+            (Some("item.completed"), Some("file_change")) if item["status"] != "failed" => {
+                for change in item["changes"].as_array().into_iter().flatten() {
+                    if let (Some(path), Some(kind)) = (string(&change["path"]), file_change(&change["kind"])) {
+                        events.push(ToolEvent::FileEdit { path, change: kind });
+                    }
+                }
+            }
+            (Some("item.completed"), Some("error")) => events.push(ToolEvent::Error {
+                message: string(&item["message"]).unwrap_or_default(),
+            }),
+            (Some("turn.completed"), _) => events.push(codex_usage(&value["usage"])),
+            (Some("turn.failed"), _) => events.push(ToolEvent::Error {
+                message: string(&value["error"]["message"]).unwrap_or_else(|| "Turn failed".to_string()),
+            }),
+            (Some("error"), _) => events.push(ToolEvent::Error {
+                message: string(&value["message"]).unwrap_or_default(),
+            }),
+            _ => {
+                if value["msg"].is_object() {
+                    events.extend(self.parse_codex_legacy(&value["msg"]));
+                }
+            }
+        }
+        events
+    }
+
+    /// Events from codex releases that wrapped each event in `{"id", "msg"}`
+    fn parse_codex_legacy(&mut self, msg: &Value) -> Vec<ToolEvent> {
+        let call_id = string(&msg["call_id"]);
+        match msg["type"].as_str() {
+            Some("agent_message_delta") => {
+                self.streamed_text = true;
+                vec![text_delta(&msg["delta"])]
+            }
+            Some("agent_message") => {
+                let streamed = std::mem::take(&mut self.streamed_text);
+                if streamed {
+                    Vec::new()
+                } else {
+                    vec![text_delta(&msg["message"])]
+                }
+            }
+            Some("exec_command_begin") => vec![ToolEvent::ToolCall {
+                id: call_id,
+                name: "shell".to_string(),
+                input: json!({ "command": msg["command"] }),
+            }],
+            Some("exec_command_end") => vec![ToolEvent::ToolResult {
+                id: call_id,
+                output: format!(
+                    "{}{}",
+                    msg["stdout"].as_str().unwrap_or_default(),
+                    msg["stderr"].as_str().unwrap_or_default()
+                ),
+                is_error: msg["exit_code"].as_i64().is_some_and(|code| code != 0),
+            }],
+            Some("patch_apply_begin") => {
+                // Changes are keyed by path, each an object named after its kind
+                let changes = msg["changes"]
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(path, change)| {
+                        let kind = change.as_object()?.keys().next()?;
+                        Some((path.clone(), file_change(&Value::String(kind.clone()))?))
+                    })
+                    .collect();
+                if let Some(id) = call_id {
+                    self.pending_edits.insert(id, changes);
+                }
+                Vec::new()
+            }
+            Some("patch_apply_end") => {
+                let success = msg["success"].as_bool().unwrap_or(false);
+                self.complete_edits(call_id.as_deref(), success)
+            }
+            Some("token_count") => vec![codex_usage(msg)],
+            Some("error") => vec![ToolEvent::Error {
+                message: string(&msg["message"]).unwrap_or_default(),
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Report the edits of call `id` if it succeeded, and forget them either way
+    fn complete_edits(&mut self, id: Option<&str>, succeeded: bool) -> Vec<ToolEvent> {
+        let edits = id.and_then(|id| self.pending_edits.remove(id)).unwrap_or_default();
+        if !succeeded {
+            return Vec::new();
+        }
+        edits
+            .into_iter()
+            .map(|(path, change)| ToolEvent::FileEdit { path, change })
+            .collect()
+    }
+}
+
+fn string(value: &Value) -> Option<String> {
+    value.as_str().map(String::from)
+}
+
+fn text_delta(value: &Value) -> ToolEvent {
+    ToolEvent::TextDelta {
+        text: string(value).unwrap_or_default(),
+    }
+}
+
+fn content_blocks(message: &Value) -> impl Iterator<Item = &Value> {
+    message["content"].as_array().into_iter().flatten()
+}
+
+/// Text of a tool result, which is a string or a list of content blocks
+fn result_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn file_change(kind: &Value) -> Option<FileChange> {
+    match kind.as_str()? {
+        "add" => Some(FileChange::Add),
+        "update" => Some(FileChange::Update),
+        "delete" => Some(FileChange::Delete),
+        _ => None,
+    }
+}
+
+fn codex_usage(usage: &Value) -> ToolEvent {
+    ToolEvent::Usage {
+        input_tokens: usage["input_tokens"].as_u64().unwrap_or(0),
+        output_tokens: usage["output_tokens"].as_u64().unwrap_or(0),
+        cached_input_tokens: usage["cached_input_tokens"].as_u64().unwrap_or(0),
+        cost_usd: None,
+    }
+}
+
+/// Assistant text of a run, joined from its text events
+pub fn assistant_text(events: &[ToolEvent]) -> String {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            ToolEvent::TextDelta { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Payload of `TOOL_EVENT`
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolEventPayload {
+    pub pid: u32,
+    pub event: ToolEvent,
+}
+
+/// Parsing state of a running tool
+struct ActiveParse {
+    parser: EventParser,
+    tool_id: String,
+    session_id: Option<String>,
+    events: Vec<ToolEvent>,
+}
+
+/// Tools whose output is being parsed, keyed by PID
+fn active_parsers() -> &'static Mutex<HashMap<u32, ActiveParse>> {
+    static PARSERS: OnceLock<Mutex<HashMap<u32, ActiveParse>>> = OnceLock::new();
+    PARSERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Start parsing a tool's output if its adapter has a JSON mode and `args` turn it on
+pub(crate) fn start(pid: u32, tool_id: &str, args: &[String]) {
+    let format = get_available_adapters()
+        .into_iter()
+        .find(|a| a.id == tool_id)
+        .and_then(|adapter| adapter.output_format)
+        .filter(|format| format.is_enabled_by(args));
+    let Some(format) = format else {
+        return;
+    };
+
+    if let Ok(mut parsers) = active_parsers().lock() {
+        parsers.insert(
+            pid,
+            ActiveParse {
+                parser: EventParser::new(format),
+                tool_id: tool_id.to_string(),
+                session_id: None,
+                events: Vec::new(),
+            },
+        );
+    }
+}
+
+/// Attach the session the tool's events are saved to
+pub(crate) fn set_session(pid: u32, session_id: String) {
+    if let Ok(mut parsers) = active_parsers().lock() {
+        if let Some(active) = parsers.get_mut(&pid) {
+            active.session_id = Some(session_id);
+        }
+    }
+}
+
+/// Parse output of a tool and emit the events it completes
+pub(crate) fn feed(pid: u32, text: &str) {
+    let events = {
+        let Ok(mut parsers) = active_parsers().lock() else {
+            return;
+        };
+        let Some(active) = parsers.get_mut(&pid) else {
+            return;
+        };
+        let events = active.parser.feed(text);
+        active.events.extend(events.iter().cloned());
+        events
+    };
+    emit_all(pid, events);
+}
+
+/// Flush the parser once the tool's output has ended and save its events
+///
+/// With a session and a database, the run is stored as an assistant message.
+pub(crate) fn finish(pid: u32) {
+    let Some(mut active) = active_parsers().lock().ok().and_then(|mut p| p.remove(&pid)) else {
+        return;
+    };
+    let rest = active.parser.finish();
+    active.events.extend(rest.iter().cloned());
+    emit_all(pid, rest);
+
+    if let Some(session_id) = active.session_id {
+        let content = assistant_text(&active.events);
+        save_run(pid, &active.tool_id, session_id, content, &active.events);
+    }
+}
+
+/// Store a finished run as an assistant message whose metadata holds every event
+pub(crate) fn save_run(pid: u32, tool_id: &str, session_id: String, content: String, events: &[ToolEvent]) {
+    let Some(db_path) = database::current_database() else {
+        return;
+    };
+    let message = to_message(pid, tool_id, session_id, content, events);
+    if let Err(e) = database::insert_message(&db_path, &message) {
+        eprintln!("Warning: failed to save events of process {}: {}", pid, e);
+    }
+}
+
+fn to_message(pid: u32, tool_id: &str, session_id: String, content: String, events: &[ToolEvent]) -> Message {
+    let timestamp = output::now_millis();
+    Message {
+        id: format!("{}-{}", timestamp, pid),
+        session_id,
+        role: "assistant".to_string(),
+        content,
+        timestamp,
+        metadata: Some(
+            json!({
+                "tool_id": tool_id,
+                "pid": pid,
+                "events": events,
+            })
+            .to_string(),
+        ),
+    }
+}
+
+pub(crate) fn emit_all(pid: u32, parsed: Vec<ToolEvent>) {
+    for event in parsed {
+        events::emit(TOOL_EVENT, ToolEventPayload { pid, event });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::fake_pid;
+
+    fn parse_all(format: OutputFormat, output: &str) -> Vec<ToolEvent> {
+        let mut parser = EventParser::new(format);
+        let mut events = parser.feed(output);
+        events.extend(parser.finish());
+        events
+    }
+
+    const CLAUDE_STREAM: &str = r#"{"type":"system","subtype":"init","session_id":"s1","model":"m"}
+{"type":"assistant","message":{"content":[{"type":"text","text":"I'll fix it."},{"type":"tool_use","id":"toolu_1","name":"Edit","input":{"file_path":"/p/src/main.rs","old_string":"a","new_string":"b"}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"The file has been updated."}]}}
+{"type":"assistant","message":{"content":[{"type":"tool_use","id":"toolu_2","name":"Bash","input":{"command":"cargo test"}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"toolu_2","content":[{"type":"text","text":"1 failed"}],"is_error":true}]}}
+{"type":"result","subtype":"success","is_error":false,"result":"Done","total_cost_usd":0.012,"usage":{"input_tokens":120,"output_tokens":45,"cache_read_input_tokens":300}}
+"#;
+
+    const CODEX_STREAM: &str = r#"{"type":"thread.started","thread_id":"t1"}
+{"type":"turn.started"}
+{"type":"item.started","item":{"id":"item_0","type":"command_execution","command":"bash -lc ls","aggregated_output":"","status":"in_progress"}}
+{"type":"item.completed","item":{"id":"item_0","type":"command_execution","command":"bash -lc ls","aggregated_output":"src\n","exit_code":0,"status":"completed"}}
+{"type":"item.completed","item":{"id":"item_1","type":"file_change","changes":[{"path":"src/lib.rs","kind":"update"},{"path":"src/new.rs","kind":"add"}],"status":"completed"}}
+{"type":"item.completed","item":{"id":"item_2","type":"agent_message","text":"Added the module."}}
+{"type":"turn.completed","usage":{"input_tokens":900,"cached_input_tokens":100,"output_tokens":60}}
+"#;
+
+    #[test]
+    fn test_claude_stream_json() {
+        let events = parse_all(OutputFormat::ClaudeStreamJson, CLAUDE_STREAM);
+        assert_eq!(
+            events[0],
+            ToolEvent::TextDelta {
+                text: "I'll fix it.".to_string()
+            }
+        );
+        assert!(matches!(&events[1], ToolEvent::ToolCall { name, .. } if name == "Edit"));
+        assert!(matches!(&events[2], ToolEvent::ToolResult { is_error: false, .. }));
+        assert_eq!(
+            events[3],
+            ToolEvent::FileEdit {
+                path: "/p/src/main.rs".to_string(),
+                change: FileChange::Update
+            }
+        );
+        assert_eq!(
+            events[5],
+            ToolEvent::ToolResult {
+                id: Some("toolu_2".to_string()),
+                output: "1 failed".to_string(),
+                is_error: true
+            }
+        );
+        assert_eq!(
+            events[6],
+            ToolEvent::Usage {
+                input_tokens: 120,
+                output_tokens: 45,
+                cached_input_tokens: 300,
+                cost_usd: Some(0.012)
+            }
+        );
+        assert_eq!(events.len(), 7);
+    }
+
+    #[test]
+    fn test_claude_partial_messages_and_errors() {
+        let stream = r#"{"type":"stream_event","event":{"type":"content_block_delta","delta":{"type":"text_delta","text":"Hel"}}}
+{"type":"stream_event","event":{"type":"content_block_delta","delta":{"type":"text_delta","text":"lo"}}}
+{"type":"assistant","message":{"content":[{"type":"text","text":"Hello"}]}}
+{"type":"result","subtype":"error_max_turns","is_error":true,"usage":{}}"#;
+        let events = parse_all(OutputFormat::ClaudeStreamJson, stream);
+        assert_eq!(assistant_text(&events), "Hello");
+        assert_eq!(
+            events.last(),
+            Some(&ToolEvent::Error {
+                message: "error_max_turns".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_codex_json() {
+        let events = parse_all(OutputFormat::CodexJson, CODEX_STREAM);
+        assert!(matches!(&events[0], ToolEvent::ToolCall { name, input, .. }
+            if name == "shell" && input["command"] == "bash -lc ls"));
+        assert!(matches!(&events[1], ToolEvent::ToolResult { output, is_error: false, .. } if output == "src\n"));
+        assert_eq!(
+            events[3],
+            ToolEvent::FileEdit {
+                path: "src/new.rs".to_string(),
+                change: FileChange::Add
+            }
+        );
+        assert_eq!(assistant_text(&events), "Added the module.");
+        assert!(matches!(events.last(), Some(ToolEvent::Usage { input_tokens: 900, .. })));
+    }
+
+    #[test]
+    fn test_codex_legacy_events() {
+        let stream = r#"{"id":"0","msg":{"type":"exec_command_begin","call_id":"c1","command":["ls"]}}
+{"id":"0","msg":{"type":"exec_command_end","call_id":"c1","stdout":"","stderr":"denied","exit_code":1}}
+{"id":"0","msg":{"type":"patch_apply_begin","call_id":"c2","changes":{"a.txt":{"delete":{}}}}}
+{"id":"0","msg":{"type":"patch_apply_end","call_id":"c2","success":true}}
+{"id":"0","msg":{"type":"agent_message","message":"Removed a.txt"}}"#;
+        let events = parse_all(OutputFormat::CodexJson, stream);
+        assert!(matches!(&events[1], ToolEvent::ToolResult { is_error: true, .. }));
+        assert_eq!(
+            events[2],
+            ToolEvent::FileEdit {
+                path: "a.txt".to_string(),
+                change: FileChange::Delete
+            }
+        );
+        assert_eq!(assistant_text(&events), "Removed a.txt");
+    }
+
+    #[test]
+    fn test_split_chunks_and_noise() {
+        let mut parser = EventParser::new(OutputFormat::CodexJson);
+        assert!(parser.feed("warning: not json\r\n{\"type\":\"item.completed\",\"item\":{\"type\":\"agent_").is_empty());
+        let events = parser.feed("message\",\"text\":\"hi\"}}\r\n");
+        assert_eq!(events, vec![ToolEvent::TextDelta { text: "hi".to_string() }]);
+        assert!(parser.finish().is_empty());
+    }
+
+    #[test]
+    fn test_tracks_tool_runs() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let (plain, json) = (fake_pid(), fake_pid());
+        start(plain, "codex", &args(&["fix the tests"]));
+        start(json, "codex", &args(&["exec", "--json", "fix the tests"]));
+        set_session(json, "session-1".to_string());
+        feed(plain, CODEX_STREAM);
+        feed(json, CODEX_STREAM);
+
+        let parsers = active_parsers().lock().unwrap();
+        assert!(!parsers.contains_key(&plain));
+        let active = &parsers[&json];
+        assert_eq!(active.events.len(), 6);
+
+        let content = assistant_text(&active.events);
+        let message = to_message(json, &active.tool_id, "session-1".to_string(), content, &active.events);
+        assert_eq!(message.role, "assistant");
+        assert_eq!(message.content, "Added the module.");
+        let metadata: Value = serde_json::from_str(message.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(metadata["tool_id"], "codex");
+        assert_eq!(metadata["events"][2]["type"], "file_edit");
+        assert_eq!(metadata["events"][5]["type"], "usage");
+    }
+
+    #[test]
+    fn test_enabled_by_args() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert!(OutputFormat::ClaudeStreamJson.is_enabled_by(&args(&["-p", "hi", "--output-format", "stream-json"])));
+        assert!(!OutputFormat::ClaudeStreamJson.is_enabled_by(&args(&["--output-format", "json"])));
+        assert!(OutputFormat::CodexJson.is_enabled_by(&args(&["exec", "--json", "hi"])));
+        assert!(!OutputFormat::CodexJson.is_enabled_by(&args(&[])));
+    }
+}
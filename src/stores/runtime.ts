@@ -1,7 +1,16 @@
 import { defineStore } from 'pinia';
 import { ref, computed, watch } from 'vue';
 import { invoke } from '@tauri-apps/api/core';
-import { loadRuntimes, saveRuntimes, type RuntimesData } from '@/utils/store';
+import {
+  loadRuntimes,
+  saveRuntimes,
+  type EnvSource,
+  type Readiness,
+  type ResourceLimits,
+  type RestartPolicy,
+  type RuntimeInstance,
+  type RuntimesData,
+} from '@/utils/store';
 
 // Runtime types
 export interface AIRuntime {
@@ -15,6 +24,9 @@ export interface AIRuntime {
   vramUsage?: number; // MB (if GPU available)
   port?: number;
   lastChecked: number;
+  env?: Record<string, EnvSource>;
+  limits?: ResourceLimits;
+  readiness?: Readiness;
 }
 
 export interface RuntimeStatus {
@@ -47,6 +59,8 @@ export const useRuntimeStore = defineStore('runtime', () => {
   const logs = ref<Map<string, string[]>>(new Map());
   const isScanning = ref(false);
   const lastScan = ref<number>(0);
+  const restartPolicies = ref<Record<string, RestartPolicy>>({});
+  const instances = ref<Record<string, RuntimeInstance>>({});
 
   // Getters
   const activeRuntime = computed(() =>
@@ -69,6 +83,13 @@ export const useRuntimeStore = defineStore('runtime', () => {
     Array.from(runtimes.value.values()).filter((r) => r.type === 'custom')
   );
 
+  // Runtimes that aren't rediscovered by a scan and must be saved
+  const savedRuntimes = computed(() =>
+    Array.from(runtimes.value.values()).filter(
+      (r) => r.type === 'custom' || r.id in instances.value
+    )
+  );
+
   // Auto-save custom runtimes when they change
   watch(
     [runtimes, lastScan],
@@ -153,6 +174,48 @@ export const useRuntimeStore = defineStore('runtime', () => {
     }
   }
 
+  /**
+   * Add another instance of a runtime, e.g. a second Ollama server on its own port
+   */
+  async function addRuntimeInstance(
+    baseRuntimeId: string,
+    name: string,
+    instance: RuntimeInstance
+  ): Promise<AIRuntime> {
+    const base = runtimes.value.get(baseRuntimeId);
+    if (!base) {
+      throw new Error(`Runtime not found: ${baseRuntimeId}`);
+    }
+
+    const id = `${base.type}_${name}`;
+    if (runtimes.value.has(id)) {
+      throw new Error(`Runtime already exists: ${id}`);
+    }
+
+    const runtime: AIRuntime = {
+      ...base,
+      id,
+      name: `${base.name} (${name})`,
+      status: 'stopped',
+      port: instance.port,
+      memoryUsage: undefined,
+      vramUsage: undefined,
+      lastChecked: Date.now(),
+    };
+    instances.value = { ...instances.value, [id]: instance };
+    runtimes.value.set(id, runtime);
+    await persistRuntimes();
+    return runtime;
+  }
+
+  /**
+   * Change where a runtime instance listens; applies from its next start
+   */
+  async function setRuntimeInstance(runtimeId: string, instance: RuntimeInstance): Promise<void> {
+    instances.value = { ...instances.value, [runtimeId]: instance };
+    await persistRuntimes();
+  }
+
   /**
    * Start a runtime
    */
@@ -168,6 +231,9 @@ export const useRuntimeStore = defineStore('runtime', () => {
         executablePath: runtime.executablePath,
         args: [],
         workingDir: null,
+        env: runtime.env ?? null,
+        limits: runtime.limits ?? null,
+        readiness: runtime.readiness ?? null,
       });
 
       runtime.status = 'running';
@@ -280,9 +346,11 @@ export const useRuntimeStore = defineStore('runtime', () => {
    */
   async function getLogs(runtimeId: string): Promise<string[]> {
     try {
-      const logLines = await invoke<string[]>('stream_process_output', {
+      const output = await invoke<{ seq: number; data: string }[]>('resume_process_output', {
         pid: 0, // This would need to be tracked per runtime
+        fromSeq: 0,
       });
+      const logLines = output.map((line) => line.data);
       
       logs.value.set(runtimeId, logLines);
       return logLines;
@@ -337,6 +405,10 @@ export const useRuntimeStore = defineStore('runtime', () => {
   function removeRuntime(runtimeId: string): void {
     runtimes.value.delete(runtimeId);
     logs.value.delete(runtimeId);
+    if (runtimeId in instances.value) {
+      const { [runtimeId]: _removed, ...rest } = instances.value;
+      instances.value = rest;
+    }
     
     if (activeRuntimeId.value === runtimeId) {
       activeRuntimeId.value = null;
@@ -374,10 +446,15 @@ export const useRuntimeStore = defineStore('runtime', () => {
           version: runtime.version,
           status: runtime.status as AIRuntime['status'],
           lastChecked: runtime.lastChecked,
+          env: runtime.env,
+          limits: runtime.limits,
+          readiness: runtime.readiness,
         });
       }
       
       lastScan.value = data.lastScan;
+      restartPolicies.value = data.restartPolicies ?? {};
+      instances.value = data.instances ?? {};
     } catch (error) {
       console.error('Failed to load runtimes from store:', error);
     }
@@ -387,7 +464,7 @@ export const useRuntimeStore = defineStore('runtime', () => {
     try {
       const data: RuntimesData = {
         version: 1,
-        customRuntimes: customRuntimes.value.map(r => ({
+        customRuntimes: savedRuntimes.value.map(r => ({
           id: r.id,
           name: r.name,
           type: r.type,
@@ -395,8 +472,13 @@ export const useRuntimeStore = defineStore('runtime', () => {
           version: r.version,
           status: r.status,
           lastChecked: r.lastChecked,
+          env: r.env,
+          limits: r.limits,
+          readiness: r.readiness,
         })),
         lastScan: lastScan.value,
+        restartPolicies: restartPolicies.value,
+        instances: instances.value,
       };
       await saveRuntimes(data);
     } catch (error) {
@@ -404,6 +486,11 @@ export const useRuntimeStore = defineStore('runtime', () => {
     }
   }
 
+  async function setRestartPolicy(runtimeId: string, policy: RestartPolicy): Promise<void> {
+    restartPolicies.value = { ...restartPolicies.value, [runtimeId]: policy };
+    await persistRuntimes();
+  }
+
   // Legacy methods for backward compatibility
   function loadFromStorage(data: {
     runtimes?: Array<[string, AIRuntime]>;
@@ -434,6 +521,8 @@ export const useRuntimeStore = defineStore('runtime', () => {
     logs,
     isScanning,
     lastScan,
+    restartPolicies,
+    instances,
     // Getters
     activeRuntime,
     runtimeList,
@@ -443,6 +532,8 @@ export const useRuntimeStore = defineStore('runtime', () => {
     // Actions
     scanForRuntimes,
     addCustomRuntime,
+    addRuntimeInstance,
+    setRuntimeInstance,
     startRuntime,
     stopRuntime,
     restartRuntime,
@@ -455,6 +546,7 @@ export const useRuntimeStore = defineStore('runtime', () => {
     setActiveRuntime,
     removeRuntime,
     startStatusPolling,
+    setRestartPolicy,
     loadFromStore,
     persistRuntimes,
     loadFromStorage,
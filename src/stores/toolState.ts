@@ -1,6 +1,6 @@
 import { defineStore } from 'pinia';
 import { ref, computed } from 'vue';
-import type { AITool, InstalledTool, ToolProcess, HealthCheckResult } from '@/types';
+import type { AITool, AdapterReload, DetectionResult, InstalledTool, ToolProcess, HealthCheckResult, ControlKey, ProcessInput, PromptRequest, PromptResult, ConfigValidationResult, ConfigLocation, ConfigVersion, ConfigDiff } from '@/types';
 import { invoke } from '@tauri-apps/api/core';
 
 export const useToolStateStore = defineStore('toolState', () => {
@@ -34,14 +34,24 @@ export const useToolStateStore = defineStore('toolState', () => {
     }
   }
 
+  // Re-read adapter files, including the project's .aitm/adapters when given
+  async function reloadAdapters(projectDir?: string): Promise<AdapterReload> {
+    try {
+      const result = await invoke<AdapterReload>('reload_adapters', {
+        projectDir: projectDir ?? null,
+      });
+      availableTools.value = result.adapters;
+      return result;
+    } catch (error) {
+      console.error('Failed to reload adapters:', error);
+      throw error;
+    }
+  }
+
   async function detectInstalledTools(): Promise<void> {
     for (const tool of availableTools.value) {
       try {
-        const result = await invoke<{
-          installed: boolean;
-          version: string | null;
-          path: string | null;
-        }>('detect_cli_tool', { toolId: tool.id });
+        const result = await invoke<DetectionResult>('detect_cli_tool', { toolId: tool.id });
 
         if (result.installed) {
           installedTools.value.set(tool.id, {
@@ -88,6 +98,15 @@ export const useToolStateStore = defineStore('toolState', () => {
     }
   }
 
+  async function locateToolConfig(toolId: string): Promise<ConfigLocation> {
+    try {
+      return await invoke<ConfigLocation>('locate_tool_config', { toolId });
+    } catch (error) {
+      console.error(`Failed to locate config for ${toolId}:`, error);
+      throw error;
+    }
+  }
+
   async function saveToolConfig(toolId: string, content: string): Promise<void> {
     try {
       await invoke('write_tool_config', { toolId, content });
@@ -97,6 +116,44 @@ export const useToolStateStore = defineStore('toolState', () => {
     }
   }
 
+  // Change one setting in place; the rest of the file, comments included, is left alone
+  async function setToolConfigValue(toolId: string, pointer: string, value: unknown): Promise<ConfigValidationResult> {
+    try {
+      return await invoke<ConfigValidationResult>('set_tool_config_value', { toolId, pointer, value });
+    } catch (error) {
+      console.error(`Failed to set ${pointer} in config for ${toolId}:`, error);
+      throw error;
+    }
+  }
+
+  async function listConfigHistory(toolId: string): Promise<ConfigVersion[]> {
+    try {
+      return await invoke<ConfigVersion[]>('list_config_history', { toolId });
+    } catch (error) {
+      console.error(`Failed to list config history for ${toolId}:`, error);
+      throw error;
+    }
+  }
+
+  async function diffConfigVersions(toolId: string, from: string, to = 'current'): Promise<ConfigDiff> {
+    try {
+      return await invoke<ConfigDiff>('diff_config_versions', { toolId, from, to });
+    } catch (error) {
+      console.error(`Failed to diff config versions for ${toolId}:`, error);
+      throw error;
+    }
+  }
+
+  // Returns the snapshot of the config that was replaced, so the restore can be undone
+  async function restoreConfigVersion(toolId: string, versionId: string): Promise<ConfigVersion | null> {
+    try {
+      return await invoke<ConfigVersion | null>('restore_config_version', { toolId, versionId });
+    } catch (error) {
+      console.error(`Failed to restore config version ${versionId} for ${toolId}:`, error);
+      throw error;
+    }
+  }
+
   async function startToolProcess(toolId: string, projectPath: string): Promise<void> {
     try {
       const pid = await invoke<number>('spawn_cli_process', {
@@ -135,20 +192,37 @@ export const useToolStateStore = defineStore('toolState', () => {
     }
   }
 
-  async function sendMessage(toolId: string, message: string): Promise<void> {
+  async function sendInput(toolId: string, input: ProcessInput): Promise<void> {
     const process = toolProcesses.value.get(toolId);
     if (!process || process.status !== 'running') {
       throw new Error(`No running process for tool: ${toolId}`);
     }
 
     try {
-      await invoke('send_to_process', { pid: process.pid, input: message });
+      await invoke('send_to_process', { pid: process.pid, input });
     } catch (error) {
-      console.error(`Failed to send message to ${toolId}:`, error);
+      console.error(`Failed to send input to ${toolId}:`, error);
       throw error;
     }
   }
 
+  async function sendMessage(toolId: string, message: string): Promise<void> {
+    await sendInput(toolId, { mode: 'line', text: message });
+  }
+
+  async function sendKey(toolId: string, key: ControlKey): Promise<void> {
+    await sendInput(toolId, { mode: 'key', key });
+  }
+
+  // Run one prompt without a terminal; rejects on timeout or cancelPrompt
+  async function runPrompt(request: PromptRequest): Promise<PromptResult> {
+    return invoke<PromptResult>('run_prompt', { request });
+  }
+
+  async function cancelPrompt(runId: string): Promise<void> {
+    await invoke('cancel_prompt', { runId });
+  }
+
   function setActiveTool(toolId: string | null): void {
     activeToolId.value = toolId;
   }
@@ -182,13 +256,23 @@ export const useToolStateStore = defineStore('toolState', () => {
     readyTools,
     // Actions
     loadAvailableTools,
+    reloadAdapters,
     detectInstalledTools,
     checkToolHealth,
     getToolConfig,
+    locateToolConfig,
     saveToolConfig,
+    setToolConfigValue,
+    listConfigHistory,
+    diffConfigVersions,
+    restoreConfigVersion,
     startToolProcess,
     stopToolProcess,
+    sendInput,
     sendMessage,
+    sendKey,
+    runPrompt,
+    cancelPrompt,
     setActiveTool,
     loadFromStorage,
     toStorageData,
@@ -58,7 +58,150 @@ export interface AITool {
   description: string;
   installCommand: Record<string, string>;
   configPath: Record<string, string>;
+  config_paths?: Record<string, string[]>; // candidates per platform in priority order; ~, $VAR and %VAR% are expanded
   healthCheckCommand: string;
+  source?: string | null; // adapter file that defined or overrode the tool
+  supported_versions?: string | null; // semver range, e.g. ">=0.20.0, <1"
+  broken_versions?: string[];
+  packages?: Record<string, string>; // package name by install method
+  output_format?: 'claude-stream-json' | 'codex-json' | null;
+  invocation?: ToolInvocation | null;
+  health_checks?: AdapterHealthCheck[];
+  config_schema?: Record<string, unknown> | null; // JSON Schema for the tool's config file
+  config_format?: 'json' | 'toml' | 'yaml' | null; // taken from the config path's extension when null
+  backup_retention?: number | null; // config snapshots kept, 20 when null; 0 turns backups off
+}
+
+// Argv template for one-shot prompts; {prompt}, {model}, {file} and {project_dir} are filled in
+export interface ToolInvocation {
+  args: string[];
+  prompt_on_stdin?: boolean;
+}
+
+// One installed copy of a CLI tool
+export interface ToolInstallation {
+  path: string;
+  version: string | null;
+  version_output: string | null;
+  on_path: boolean;
+  error: string | null;
+}
+
+export interface DetectionResult {
+  installed: boolean;
+  version: string | null;
+  path: string | null;
+  installations: ToolInstallation[]; // first entry shadows the rest
+}
+
+// Parsed from a tool's JSON output; emitted as "process://tool-event" with { pid, event }
+export type ToolEvent =
+  | { type: 'text_delta'; text: string }
+  | { type: 'tool_call'; id: string | null; name: string; input: unknown }
+  | { type: 'tool_result'; id: string | null; output: string; is_error: boolean }
+  | { type: 'file_edit'; path: string; change: 'add' | 'update' | 'delete' }
+  | {
+      type: 'usage';
+      input_tokens: number;
+      output_tokens: number;
+      cached_input_tokens: number;
+      cost_usd: number | null;
+    }
+  | { type: 'error'; message: string };
+
+export interface ToolEventPayload {
+  pid: number;
+  event: ToolEvent;
+}
+
+// Result of validate_config; warnings (unknown keys) don't block write_tool_config
+export interface ConfigIssue {
+  severity: 'error' | 'warning';
+  pointer: string; // JSON pointer, "" for the whole document
+  line: number | null;
+  column: number | null;
+  message: string;
+}
+
+// Result of locate_tool_config: the first candidate that exists, else the first that expands
+export interface ConfigPathCandidate {
+  template: string; // as the adapter lists it, e.g. "$XDG_CONFIG_HOME/claude/config.json"
+  path: string | null; // null when a variable it needs is unset
+  exists: boolean;
+  error: string | null;
+}
+
+export interface ConfigLocation {
+  path: string;
+  index: number; // chosen entry of candidates
+  exists: boolean; // false when path is where a new file would go
+  candidates: ConfigPathCandidate[];
+}
+
+// A snapshot taken before a config write; "current" stands for the live file in diffs
+export interface ConfigVersion {
+  id: string; // e.g. "20261017-093015.250"
+  created_at: number; // ms since epoch
+  size_bytes: number;
+  path: string;
+}
+
+export interface ConfigDiff {
+  from: string;
+  to: string;
+  unified: string; // empty when the versions match
+  added: number;
+  removed: number;
+}
+
+export interface ConfigValidationResult {
+  valid: boolean;
+  errors: string[];
+  warnings: string[];
+  issues: ConfigIssue[];
+}
+
+// A single non-interactive prompt for the run_prompt command
+export interface PromptRequest {
+  run_id: string; // pass to cancel_prompt to stop the run
+  tool_id: string;
+  project_dir: string;
+  prompt: string;
+  model?: string | null;
+  context_files?: string[];
+  extra_args?: string[];
+  timeout_ms?: number | null;
+  session_id?: string | null;
+}
+
+export interface PromptUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cached_input_tokens: number;
+  cost_usd: number | null;
+}
+
+export interface PromptResult {
+  run_id: string;
+  pid: number;
+  answer: string;
+  usage: PromptUsage | null;
+  modified_files: string[];
+  events: ToolEvent[];
+  exit_code: number | null;
+  errors: string[];
+  duration_ms: number;
+}
+
+// An adapter file that was skipped on reload
+export interface AdapterFileError {
+  path: string;
+  errors: string[];
+}
+
+export interface AdapterReload {
+  adapters: AITool[];
+  errors: AdapterFileError[];
 }
 
 export interface InstalledTool {
@@ -75,14 +218,54 @@ export interface ToolProcess {
   status: 'running' | 'stopped' | 'error';
 }
 
+export type ControlKey =
+  | 'ctrl_c'
+  | 'ctrl_d'
+  | 'ctrl_z'
+  | 'esc'
+  | 'enter'
+  | 'tab'
+  | 'shift_tab'
+  | 'backspace'
+  | 'delete'
+  | 'up'
+  | 'down'
+  | 'right'
+  | 'left'
+  | 'home'
+  | 'end'
+  | 'page_up'
+  | 'page_down';
+
+export type ProcessInput =
+  | { mode: 'line'; text: string }
+  | { mode: 'raw'; bytes: number[] }
+  | { mode: 'key'; key: ControlKey };
+
 export interface HealthCheckResult {
   toolId: string;
-  status: 'healthy' | 'unhealthy';
+  status: 'healthy' | 'degraded' | 'unhealthy';
   version?: string;
   errors?: string[];
   suggestions?: string[];
+  checks?: HealthCheckRow[]; // one per adapter check: config file, credentials, auth command
 }
 
+export interface HealthCheckRow {
+  name: string;
+  passed: boolean;
+  optional: boolean; // a failure degrades rather than breaks the tool
+  message: string;
+  suggestion: string | null;
+}
+
+// Adapter-declared check run by run_health_check
+export type AdapterHealthCheck = (
+  | { kind: 'config_file' }
+  | { kind: 'credentials'; keys?: string[] }
+  | { kind: 'command'; command: string; timeout_ms?: number | null }
+) & { name?: string | null; optional?: boolean; suggestion?: string | null };
+
 // Settings types
 export interface PromptTemplate {
   id: string;
@@ -33,6 +33,34 @@ export interface ProjectsData {
   recentProjects: string[]; // project IDs
 }
 
+// Where an injected environment variable gets its value
+export type EnvSource =
+  | { source: 'literal'; value: string }
+  | { source: 'secret'; key: string; optional?: boolean };
+
+// Optional resource limits for a runtime's process
+export interface ResourceLimits {
+  max_address_space_bytes?: number;
+  max_rss_bytes?: number;
+  max_cpu_seconds?: number;
+  max_open_files?: number;
+  cgroup_memory_bytes?: number;
+  cgroup_cpu_percent?: number;
+}
+
+// How to tell that a started runtime is ready to take requests
+export type ReadinessProbe =
+  | { type: 'started' }
+  | { type: 'http'; url: string }
+  | { type: 'tcp'; address: string }
+  | { type: 'log-line'; pattern: string };
+
+// Readiness settings; without a probe the runtime type's default is used
+export interface Readiness {
+  probe?: ReadinessProbe;
+  timeoutMs?: number;
+}
+
 // Runtimes data structure matching Rust backend
 export interface RuntimesData {
   version: number;
@@ -44,8 +72,28 @@ export interface RuntimesData {
     version: string | null;
     status: string;
     lastChecked: number;
+    env?: Record<string, EnvSource>;
+    limits?: ResourceLimits;
+    readiness?: Readiness;
   }>;
   lastScan: number;
+  restartPolicies?: Record<string, RestartPolicy>;
+  instances?: Record<string, RuntimeInstance>;
+}
+
+// Where a runtime instance listens; unset fields use the runtime type's defaults
+export interface RuntimeInstance {
+  host?: string;
+  port?: number;
+  modelsDir?: string;
+}
+
+export interface RestartPolicy {
+  mode: 'never' | 'on-failure' | 'always';
+  initialBackoffMs?: number;
+  maxBackoffMs?: number;
+  maxRetries?: number;
+  retryWindowMs?: number;
 }
 
 /**